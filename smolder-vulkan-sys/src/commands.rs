@@ -0,0 +1,81 @@
+//! Tables of the Vulkan commands, which are loaded through an `Entry`, with methods that wrap each
+//! command to take slices, references, and `CStr`s instead of pointers, and to return values instead
+//! of writing them through pointers.
+//!
+//! Without the `runtime-loader` feature, the core commands are also declared as the loader exports
+//! them, with the same wrappers as free functions.
+//!
+//! The wrappers are `unsafe` because they don't check that their handles are valid, or any of the
+//! other valid usage rules.
+#![allow(non_snake_case)]
+// The wrappers take and return the structs of deprecated extensions like any others
+#![allow(deprecated)]
+#![allow(clippy::missing_safety_doc, clippy::needless_lifetimes, clippy::missing_transmute_annotations)]
+#![allow(clippy::too_many_arguments)]
+
+// `safe_ffi` has the rest of `ffi` too, with the safe structs in place of the raw ones
+use safe_ffi::*;
+use libc::*;
+use std::ffi::CStr;
+use std::mem::{ self, MaybeUninit };
+use std::ptr;
+
+use entry::Entry;
+
+include!(concat!(env!("OUT_DIR"), "/commands.rs"));
+#[cfg(not(feature = "runtime-loader"))]
+include!(concat!(env!("OUT_DIR"), "/linked_commands.rs"));
+
+/// Looks up a command by each of its names in turn, since implementations of older versions only
+/// have promoted commands under their extensions' names
+unsafe fn load<F: FnMut(&CStr) -> PFN_vkVoidFunction>(f: &mut F, names: &[&[u8]]) -> PFN_vkVoidFunction {
+    names.iter().filter_map(|name| f(CStr::from_bytes_with_nul_unchecked(name))).next()
+}
+
+impl EntryFns {
+    pub unsafe fn load(entry: &Entry) -> EntryFns {
+        EntryFns::load_with(|name| entry.get_entry_proc_addr(name))
+    }
+}
+
+impl InstanceFns {
+    pub unsafe fn load(entry: &Entry, instance: VkInstance) -> InstanceFns {
+        let get_instance_proc_addr = entry.get_instance_proc_addr();
+        InstanceFns::load_with(|name| get_instance_proc_addr(Some(&mut *instance), name.as_ptr()))
+    }
+}
+
+type GetDeviceProcAddr = unsafe extern "system" fn(VkDevice, *const c_char) -> PFN_vkVoidFunction;
+
+impl DeviceFns {
+    /// Loads the device-level commands for `device`, which was created from `instance`
+    pub unsafe fn load(entry: &Entry, instance: VkInstance, device: VkDevice) -> DeviceFns {
+        let name = b"vkGetDeviceProcAddr\0";
+        let get_device_proc_addr: Option<GetDeviceProcAddr> = mem::transmute((entry.get_instance_proc_addr())(Some(instance), name.as_ptr() as *const c_char));
+        let get_device_proc_addr = get_device_proc_addr.expect("vkGetDeviceProcAddr isn't available");
+        DeviceFns::load_with(|name| get_device_proc_addr(&mut *device, name.as_ptr()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ffi::{ VkInstance, VkPhysicalDevice, VkResult };
+    use safe_ffi::VkExtensionProperties;
+    use std::ffi::CStr;
+    use super::{ DeviceFns, EntryFns, InstanceFns };
+
+    #[test]
+    fn enumeration_returns_vecs() {
+        let _: unsafe fn(&EntryFns, Option<&CStr>) -> Result<Vec<VkExtensionProperties>, VkResult> = EntryFns::enumerate_instance_extension_properties;
+        #[cfg(not(feature = "runtime-loader"))]
+        let _: unsafe fn(Option<&CStr>) -> Result<Vec<VkExtensionProperties>, VkResult> = super::enumerate_instance_extension_properties;
+    }
+
+    #[test]
+    fn tables_are_split_by_first_parameter() {
+        let _: for<'a> unsafe fn(&'a InstanceFns, VkInstance<'a>) -> Result<Vec<VkPhysicalDevice<'a>>, VkResult> = InstanceFns::enumerate_physical_devices;
+        let _ = |fns: &EntryFns| fns.vkCreateInstance.is_some();
+        let _ = |fns: &InstanceFns| fns.vkGetPhysicalDeviceProperties2.is_some();
+        let _ = |fns: &DeviceFns| fns.vkCmdSetBlendConstants.is_some();
+    }
+}
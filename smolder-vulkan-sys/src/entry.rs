@@ -0,0 +1,208 @@
+//! Getting `vkGetInstanceProcAddr`, which every other command is loaded with.
+//!
+//! With the `runtime-loader` feature (on by default), the Vulkan loader is opened with `dlopen`
+//! when an `Entry` is made, so that programs using smolder can still start (and report an error)
+//! on machines that don't have it. Without it, smolder links to the loader instead.
+use ffi::{ GetInstanceProcAddr, PFN_vkVoidFunction };
+use std::ffi::CStr;
+#[cfg(feature = "runtime-loader")]
+use std::{ error, fmt };
+#[cfg(feature = "runtime-loader")]
+use std::ffi::CString;
+#[cfg(feature = "runtime-loader")]
+use std::path::{ Path, PathBuf };
+#[cfg(feature = "runtime-loader")]
+use std::sync::Arc;
+
+/// The name of the loader's library, which `Entry::load` opens
+#[cfg(all(feature = "runtime-loader", not(target_os = "macos")))]
+pub const LIBRARY_NAME: &str = "libvulkan.so.1";
+#[cfg(all(feature = "runtime-loader", target_os = "macos"))]
+pub const LIBRARY_NAME: &str = "libvulkan.1.dylib";
+
+/// Reasons the loader couldn't be opened
+#[cfg(feature = "runtime-loader")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The library couldn't be opened, with the reason given by `dlerror`
+    Library(String),
+    /// The path of the library has a nul byte in it
+    InvalidPath(PathBuf),
+    /// The library doesn't export `vkGetInstanceProcAddr`, so it isn't a Vulkan loader
+    MissingEntryPoint,
+}
+
+#[cfg(feature = "runtime-loader")]
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Library(ref reason) => write!(f, "couldn't open the Vulkan loader: {}", reason),
+            LoadError::InvalidPath(ref path) => write!(f, "invalid Vulkan loader path: {}", path.display()),
+            LoadError::MissingEntryPoint => f.write_str("the Vulkan loader doesn't export vkGetInstanceProcAddr"),
+        }
+    }
+}
+
+#[cfg(feature = "runtime-loader")]
+impl error::Error for LoadError {}
+
+/// A library opened with `dlopen`, which is closed when it's dropped
+#[cfg(feature = "runtime-loader")]
+#[derive(Debug)]
+struct Library(*mut ::libc::c_void);
+
+// dlopen handles can be used from (and closed on) any thread
+#[cfg(feature = "runtime-loader")]
+unsafe impl Send for Library {}
+#[cfg(feature = "runtime-loader")]
+unsafe impl Sync for Library {}
+
+#[cfg(feature = "runtime-loader")]
+impl Library {
+    unsafe fn open(name: &CStr) -> Result<Library, LoadError> {
+        let handle = ::libc::dlopen(name.as_ptr(), ::libc::RTLD_NOW | ::libc::RTLD_LOCAL);
+        if handle.is_null() {
+            let reason = ::libc::dlerror();
+            let reason = if reason.is_null() {
+                String::from("unknown error")
+            } else {
+                CStr::from_ptr(reason).to_string_lossy().into_owned()
+            };
+            return Err(LoadError::Library(reason));
+        }
+        Ok(Library(handle))
+    }
+
+    unsafe fn symbol(&self, name: &CStr) -> *mut ::libc::c_void {
+        ::libc::dlsym(self.0, name.as_ptr())
+    }
+}
+
+#[cfg(feature = "runtime-loader")]
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe {
+            ::libc::dlclose(self.0);
+        }
+    }
+}
+
+/// Converts a path to the bytes that the platform names the file with, if it has no nul bytes
+#[cfg(all(feature = "runtime-loader", unix))]
+fn path_to_c_string(path: &Path) -> Option<CString> {
+    use std::os::unix::ffi::OsStrExt;
+    CString::new(path.as_os_str().as_bytes()).ok()
+}
+
+/// Converts a path to UTF-8, if it is UTF-8 and has no nul bytes
+#[cfg(all(feature = "runtime-loader", not(unix)))]
+fn path_to_c_string(path: &Path) -> Option<CString> {
+    path.to_str().and_then(|path| CString::new(path).ok())
+}
+
+/// The loader's `vkGetInstanceProcAddr`.
+///
+/// When the loader was opened at runtime, it stays open until every clone of the `Entry` it was
+/// opened for is dropped, so commands loaded through an `Entry` mustn't be called after that.
+#[derive(Clone)]
+pub struct Entry {
+    get_instance_proc_addr: GetInstanceProcAddr,
+    #[cfg(feature = "runtime-loader")]
+    _library: Arc<Library>,
+}
+
+impl Entry {
+    /// Uses the loader that smolder was linked to
+    #[cfg(not(feature = "runtime-loader"))]
+    pub fn linked() -> Entry {
+        Entry {
+            get_instance_proc_addr: ::ffi::vkGetInstanceProcAddr,
+        }
+    }
+
+    /// Opens the loader (`LIBRARY_NAME`) from the library search path.
+    ///
+    /// # Safety
+    ///
+    /// Opening a library runs its initialization code, which has to be sound to run.
+    #[cfg(feature = "runtime-loader")]
+    pub unsafe fn load() -> Result<Entry, LoadError> {
+        Entry::open(CString::new(LIBRARY_NAME).unwrap())
+    }
+
+    /// Opens the loader from a specific path, i.e. to use one that's set up with a mock ICD in tests.
+    ///
+    /// # Safety
+    ///
+    /// Opening a library runs its initialization code, which has to be sound to run, and the
+    /// library's `vkGetInstanceProcAddr` has to have the right signature.
+    #[cfg(feature = "runtime-loader")]
+    pub unsafe fn load_from<P: AsRef<Path>>(path: P) -> Result<Entry, LoadError> {
+        let path = path.as_ref();
+        match path_to_c_string(path) {
+            Some(name) => Entry::open(name),
+            None => Err(LoadError::InvalidPath(path.to_path_buf())),
+        }
+    }
+
+    #[cfg(feature = "runtime-loader")]
+    unsafe fn open(name: CString) -> Result<Entry, LoadError> {
+        let library = Library::open(&name)?;
+        let symbol = library.symbol(CStr::from_bytes_with_nul_unchecked(b"vkGetInstanceProcAddr\0"));
+        if symbol.is_null() {
+            return Err(LoadError::MissingEntryPoint);
+        }
+        Ok(Entry {
+            get_instance_proc_addr: ::std::mem::transmute::<*mut ::libc::c_void, GetInstanceProcAddr>(symbol),
+            _library: Arc::new(library),
+        })
+    }
+
+    #[inline(always)]
+    pub fn get_instance_proc_addr(&self) -> GetInstanceProcAddr {
+        self.get_instance_proc_addr
+    }
+
+    /// Gets a command that doesn't need an instance, like `vkCreateInstance`
+    ///
+    /// # Safety
+    ///
+    /// The loader has to still be open, which it is as long as this `Entry` hasn't been dropped.
+    #[inline]
+    pub unsafe fn get_entry_proc_addr(&self, name: &CStr) -> PFN_vkVoidFunction {
+        (self.get_instance_proc_addr)(None, name.as_ptr())
+    }
+}
+
+#[cfg(all(test, feature = "runtime-loader"))]
+mod tests {
+    use super::{ Entry, LoadError };
+
+    #[test]
+    fn missing_library() {
+        match unsafe { Entry::load_from("/nonexistent/libvulkan.so.1") } {
+            Err(LoadError::Library(_)) => {},
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("opened a library that doesn't exist"),
+        }
+    }
+
+    #[test]
+    fn invalid_path() {
+        match unsafe { Entry::load_from("libvulkan\0.so.1") } {
+            Err(LoadError::InvalidPath(_)) => {},
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("opened a library with a nul in its path"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn not_a_loader() {
+        match unsafe { Entry::load_from("libc.so.6") } {
+            Err(LoadError::MissingEntryPoint) => {},
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("libc was taken as a Vulkan loader"),
+        }
+    }
+}
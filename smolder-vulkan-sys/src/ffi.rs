@@ -1,54 +1,81 @@
+//! The C types of the API, generated from the registry.
+//!
+//! Structs and unions are laid out as C declares them, with raw pointers, and with each run of
+//! bitfields in a `_bitfield_N` member that has accessors for its bitfields. Handles in them are
+//! pointers (dispatchable) or `NondispatchableHandle`s, while the aliases for handles (like
+//! `VkDevice<'a>`) are references. Enums are newtypes with a constant for each enumerant, which
+//! print as the enumerant's name, and `*Flags` types are sets with a constant for each of their
+//! bits.
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+// Which of these are used depends on the registry
+#[allow(unused_imports)]
+use libc::{ c_char, c_double, c_float, c_int, c_long, c_void };
+
 pub mod flags {
-    use ::std::marker::PhantomData;
-    use ::std::ops::{ BitAnd, BitOr };
+    use ::std::ops::BitXor;
 
-    #[repr(C)]
-    #[derive(Clone, Copy, PartialEq, Eq)]
-    pub struct Flags<T, Repr: BitAnd + BitOr> {
-        repr: Repr,
-        flag_type: PhantomData<T>,
+    /// The integer types that flags are stored in
+    pub trait Representation: Copy + BitXor<Output=Self> {
+        fn is_zero(self) -> bool;
+        /// Gets just the lowest bit that's set
+        fn lowest_bit(self) -> Self;
     }
 
-    impl<T, Repr: BitAnd + BitOr> Flags<T, Repr> {
-        #[inline(always)]
-        pub fn new(repr: Repr) -> Flags<T, Repr> {
-            Flags {
-                repr: repr,
-                flag_type: PhantomData,
+    macro_rules! representation {
+        ($t: ty) => {
+            impl Representation for $t {
+                #[inline(always)]
+                fn is_zero(self) -> bool {
+                    self == 0
+                }
+
+                #[inline(always)]
+                fn lowest_bit(self) -> Self {
+                    self & self.wrapping_neg()
+                }
             }
-        }
+        };
     }
 
-    impl<T, Repr: BitAnd<Output=Repr> + BitOr> BitAnd for Flags<T, Repr> {
-        type Output = Self;
-        #[inline(always)]
-        fn bitand(self, rhs: Self) -> Self {
-            Flags::new(self.repr & rhs.repr)
-        }
+    representation!(u32);
+    representation!(u64);
+
+    /// An iterator over the bits that are set in some flags, from the lowest bit up
+    #[derive(Clone)]
+    pub struct Iter<F, R> {
+        remaining: R,
+        flag: fn(R) -> F,
     }
 
-    impl<T, Repr: BitAnd + BitOr<Output=Repr>> BitOr for Flags<T, Repr> {
-        type Output = Self;
+    impl<F, R> Iter<F, R> {
         #[inline(always)]
-        fn bitor(self, rhs: Self) -> Self {
-            Flags::new(self.repr | rhs.repr)
+        pub fn new(bits: R, flag: fn(R) -> F) -> Iter<F, R> {
+            Iter {
+                remaining: bits,
+                flag,
+            }
         }
     }
 
-    impl<T, Repr: BitAnd + BitOr + ::std::fmt::Binary> ::std::fmt::Debug for Flags<T, Repr> {
-        fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-            write!(f, "{:#b}", self.repr)
+    impl<F, R: Representation> Iterator for Iter<F, R> {
+        type Item = F;
+
+        fn next(&mut self) -> Option<F> {
+            if self.remaining.is_zero() {
+                return None;
+            }
+            let bit = self.remaining.lowest_bit();
+            self.remaining = self.remaining ^ bit;
+            Some((self.flag)(bit))
         }
     }
 }
 
 pub mod handle {
     use ::std::marker::PhantomData;
-    use super::VK_NULL_HANDLE;
 
     pub trait NullableHandle {
         fn null() -> Self;
@@ -68,13 +95,78 @@ pub mod handle {
         }
     }
 
+    /// A non-dispatchable handle as C has it, which is a 64-bit integer on every platform
+    #[repr(C)]
+    #[derive(PartialEq, Eq)]
+    pub struct NondispatchableHandle<T> {
+        handle: u64,
+        handle_type: PhantomData<*mut T>,
+    }
+
+    impl<T> NondispatchableHandle<T> {
+        #[inline(always)]
+        pub fn from_raw(handle: u64) -> NondispatchableHandle<T> {
+            NondispatchableHandle {
+                handle,
+                handle_type: PhantomData,
+            }
+        }
+
+        #[inline(always)]
+        pub fn as_raw(self) -> u64 {
+            self.handle
+        }
+    }
+
+    impl<T> NullableHandle for NondispatchableHandle<T> {
+        #[inline(always)]
+        fn null() -> NondispatchableHandle<T> {
+            NondispatchableHandle::from_raw(super::VK_NULL_HANDLE)
+        }
+    }
+
+    impl<T> Clone for NondispatchableHandle<T> {
+        #[inline(always)]
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<T> Copy for NondispatchableHandle<T> {}
+
+    impl<T> ::std::fmt::Debug for NondispatchableHandle<T> {
+        fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+            write!(f, "0x{:x}", self.handle)
+        }
+    }
+
     #[repr(C)]
-    #[derive(Clone, Copy, PartialEq, Eq)]
+    #[derive(PartialEq, Eq)]
     pub struct NondispatchableHandleRef<'a, T> {
         handle: u64,
         handle_type: PhantomData<Option<&'a mut T>>,
     }
 
+    // Deriving these would require the (opaque) handle type to be `Copy`
+    impl<'a, T> Clone for NondispatchableHandleRef<'a, T> {
+        #[inline(always)]
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<'a, T> Copy for NondispatchableHandleRef<'a, T> {}
+
+    impl<'a, T> NullableHandle for NondispatchableHandleRef<'a, T> {
+        #[inline(always)]
+        fn null() -> NondispatchableHandleRef<'a, T> {
+            NondispatchableHandleRef {
+                handle: super::VK_NULL_HANDLE,
+                handle_type: PhantomData,
+            }
+        }
+    }
+
     impl<'a, T> ::std::fmt::Debug for NondispatchableHandleRef<'a, T> {
         fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
             write!(f, "0x{:x}", self.handle)
@@ -88,39 +180,408 @@ pub mod handle {
             use ::std::mem;
             assert_eq!(mem::size_of::<::ffi::VkImage>(), mem::size_of::<::libc::uint64_t>());
         }
+
+        #[test]
+        fn null_nondispatchable_handle_ref() {
+            use super::NullableHandle;
+            let fence: ::ffi::VkFence = NullableHandle::null();
+            assert_eq!(fence.handle, ::ffi::VK_NULL_HANDLE);
+        }
     }
 }
 
 pub use self::handle::NullableHandle;
 
+/// vk.xml defines this for C and C++ at once, which doesn't translate
+pub const VK_NULL_HANDLE: u64 = 0;
+
+/// The error from parsing an enum from a name that isn't one of its enumerants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownEnumerant {
+    pub enum_name: &'static str,
+    pub name: String,
+}
+
+impl ::std::fmt::Display for UnknownEnumerant {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(f, "{} isn't a {}", self.name, self.enum_name)
+    }
+}
+
+impl ::std::error::Error for UnknownEnumerant {}
+
+/// Declares an enum as a newtype, so that values that drivers or newer headers add don't make it
+/// invalid. Each enumerant is an associated constant, and the second list is of enumerants that
+/// repeat an earlier value, which are only used by name.
+macro_rules! vk_enum {
+    ($name: ident, $representation: ty, { $($value: ident = $v: expr,)* }, { $($repeat: ident = $first: ident,)* }) => {
+        #[repr(transparent)]
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name(pub $representation);
+
+        impl $name {
+            $(pub const $value: $name = $name($v);)*
+            $(pub const $repeat: $name = $name::$first;)*
+
+            /// Gets the name of this value, or `None` if it isn't one of the known enumerants
+            pub fn name(self) -> Option<&'static str> {
+                match self {
+                    $($name::$value => Some(stringify!($value)),)*
+                    _ => None,
+                }
+            }
+
+            /// Checks if this value is one of the known enumerants
+            #[inline]
+            pub fn is_known(self) -> bool {
+                self.name().is_some()
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+                match self.name() {
+                    Some(name) => f.write_str(name),
+                    None => write!(f, "{}({})", stringify!($name), self.0),
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+                match self.name() {
+                    Some(name) => f.write_str(name),
+                    None => write!(f, "{}", self.0),
+                }
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = UnknownEnumerant;
+
+            fn from_str(s: &str) -> Result<$name, UnknownEnumerant> {
+                match s {
+                    $(stringify!($value) => Ok($name::$value),)*
+                    $(stringify!($repeat) => Ok($name::$repeat),)*
+                    _ => Err(UnknownEnumerant {
+                        enum_name: stringify!($name),
+                        name: String::from(s),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! vk_flag_bits {
+    ($name: ident, $representation: ty) => {
+        #[repr(transparent)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name(pub $representation);
+
+        impl ::std::ops::BitOr for $name {
+            type Output = Self;
+            #[inline(always)]
+            fn bitor(self, rhs: Self) -> Self {
+                $name(self.0 | rhs.0)
+            }
+        }
+
+        impl ::std::ops::BitOrAssign for $name {
+            #[inline(always)]
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl ::std::ops::BitAnd for $name {
+            type Output = Self;
+            #[inline(always)]
+            fn bitand(self, rhs: Self) -> Self {
+                $name(self.0 & rhs.0)
+            }
+        }
+
+        impl ::std::ops::BitAndAssign for $name {
+            #[inline(always)]
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+    };
+}
+
+/// Declares a `*Flags` type as a set of bits. The first list is every named value, and the second
+/// is the names of the single bits, which make up `all()` and are what the flags are printed as.
 macro_rules! vk_flags {
-    ($name: ident, $representation: ty, $flag_type: ty) => {
-        pub type $name = flags::Flags<$flag_type, $representation>;
+    ($name: ident, $representation: ty, $flag_type: ident, { $($flag: ident = $value: expr,)* }, { $($bit: ident,)* }) => {
+        vk_flags!($name, $representation, { $($flag = $value,)* }, { $($bit,)* });
 
-        impl Into<$name> for $flag_type {
+        impl From<$flag_type> for $name {
             #[inline(always)]
-            fn into(self) -> $name {
-                flags::Flags::new(self.0 as $representation)
+            fn from(bits: $flag_type) -> $name {
+                $name(bits.0)
+            }
+        }
+    };
+    ($name: ident, $representation: ty, { $($flag: ident = $value: expr,)* }, { $($bit: ident,)* }) => {
+        #[repr(transparent)]
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name(pub $representation);
+
+        impl $name {
+            $(pub const $flag: $name = $name($value);)*
+
+            #[inline(always)]
+            pub fn empty() -> $name {
+                $name(0)
+            }
+
+            /// Gets the flags with every known bit set
+            #[inline(always)]
+            pub fn all() -> $name {
+                $name(0 $(| $name::$bit.0)*)
+            }
+
+            #[inline(always)]
+            pub fn bits(self) -> $representation {
+                self.0
+            }
+
+            /// Gets the flags with these bits set, or `None` if any of them aren't known
+            #[inline]
+            pub fn from_bits(bits: $representation) -> Option<$name> {
+                if bits & !$name::all().0 == 0 {
+                    Some($name(bits))
+                } else {
+                    None
+                }
+            }
+
+            /// Gets the flags with these bits set, leaving out any that aren't known
+            #[inline(always)]
+            pub fn from_bits_truncate(bits: $representation) -> $name {
+                $name(bits & $name::all().0)
+            }
+
+            #[inline(always)]
+            pub fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            #[inline(always)]
+            pub fn is_all(self) -> bool {
+                self == $name::all()
+            }
+
+            /// Checks if every bit of `other` is set
+            #[inline(always)]
+            pub fn contains(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Checks if any bit of `other` is set
+            #[inline(always)]
+            pub fn intersects(self, other: $name) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            #[inline(always)]
+            pub fn insert(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+
+            #[inline(always)]
+            pub fn remove(&mut self, other: $name) {
+                self.0 &= !other.0;
+            }
+
+            #[inline(always)]
+            pub fn toggle(&mut self, other: $name) {
+                self.0 ^= other.0;
+            }
+
+            /// Iterates over each bit that's set, as flags of their own
+            #[inline(always)]
+            pub fn iter(self) -> flags::Iter<$name, $representation> {
+                flags::Iter::new(self.0, $name)
+            }
+
+            fn bit_name(bit: $name) -> Option<&'static str> {
+                match bit {
+                    $($name::$bit => Some(stringify!($bit)),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl IntoIterator for $name {
+            type Item = $name;
+            type IntoIter = flags::Iter<$name, $representation>;
+
+            #[inline(always)]
+            fn into_iter(self) -> flags::Iter<$name, $representation> {
+                self.iter()
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+                if self.is_empty() {
+                    return f.write_str("(empty)");
+                }
+                for (i, bit) in self.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    match $name::bit_name(bit) {
+                        Some(name) => f.write_str(name)?,
+                        None => write!(f, "{:#x}", bit.0)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        impl ::std::ops::Not for $name {
+            type Output = Self;
+            /// Gets the known bits that aren't set
+            #[inline(always)]
+            fn not(self) -> Self {
+                $name(!self.0 & $name::all().0)
+            }
+        }
+
+        vk_flags!(@operator $name, BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+        vk_flags!(@operator $name, BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a & b);
+        vk_flags!(@operator $name, BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a ^ b);
+        vk_flags!(@operator $name, Sub, sub, SubAssign, sub_assign, |a, b| a & !b);
+    };
+    (@operator $name: ident, $op: ident, $op_fn: ident, $assign: ident, $assign_fn: ident, |$a: ident, $b: ident| $e: expr) => {
+        impl ::std::ops::$op for $name {
+            type Output = Self;
+            #[inline(always)]
+            fn $op_fn(self, rhs: Self) -> Self {
+                let ($a, $b) = (self.0, rhs.0);
+                $name($e)
+            }
+        }
+
+        impl ::std::ops::$assign for $name {
+            #[inline(always)]
+            fn $assign_fn(&mut self, rhs: Self) {
+                *self = ::std::ops::$op::$op_fn(*self, rhs);
             }
         }
     };
 }
 
 macro_rules! vk_handle {
-    ($name: ident, $t: ty) => {
+    ($name: ident, $t: ident) => {
+        #[repr(C)]
+        pub struct $t {
+            _private: [u8; 0],
+        }
+
         pub type $name<'a> = &'a mut $t;
     };
 }
 
 macro_rules! vk_non_dispatchable_handle {
-    ($name: ident, $t: ty) => {
+    ($name: ident, $t: ident) => {
+        #[repr(C)]
+        pub struct $t {
+            _private: [u8; 0],
+        }
+
         pub type $name<'a> = handle::NondispatchableHandleRef<'a, $t>;
     };
 }
 
-include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+include!(concat!(env!("OUT_DIR"), "/types.rs"));
+
+pub type GetInstanceProcAddr = unsafe extern "system" fn(instance: Option<VkInstance>, pName: *const ::libc::c_char) -> PFN_vkVoidFunction;
 
-#[link="vulkan"]
+#[cfg(not(feature = "runtime-loader"))]
+#[link(name = "vulkan")]
 extern "system" {
-    pub fn vkGetInstanceProcAddr(instance: VkInstance, pName: *const ::libc::c_char) -> PFN_vkVoidFunction;
+    pub fn vkGetInstanceProcAddr(instance: Option<VkInstance>, pName: *const ::libc::c_char) -> PFN_vkVoidFunction;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ VkQueueFlagBits, VkQueueFlags, VkResult, VK_QUEUE_COMPUTE_BIT };
+
+    #[test]
+    fn enum_names() {
+        assert_eq!(format!("{:?}", VkResult::VK_SUCCESS), "VK_SUCCESS");
+        assert_eq!(VkResult::VK_ERROR_OUT_OF_HOST_MEMORY.to_string(), "VK_ERROR_OUT_OF_HOST_MEMORY");
+        assert_eq!(VkResult::VK_ERROR_OUT_OF_HOST_MEMORY.0, -1);
+    }
+
+    #[test]
+    fn unknown_enum_values() {
+        let unknown = VkResult(-0x7fff_0000);
+        assert!(!unknown.is_known());
+        assert!(VkResult::VK_INCOMPLETE.is_known());
+        assert_eq!(format!("{:?}", unknown), "VkResult(-2147418112)");
+        assert_eq!(unknown.to_string(), "-2147418112");
+    }
+
+    #[test]
+    fn parse_enum() {
+        assert_eq!("VK_TIMEOUT".parse(), Ok(VkResult::VK_TIMEOUT));
+        assert!("VK_TIMEOUT_KHR".parse::<VkResult>().is_err());
+    }
+
+    #[test]
+    fn flag_operations() {
+        let mut flags = VkQueueFlags::GRAPHICS | VkQueueFlags::COMPUTE;
+        assert!(flags.contains(VkQueueFlags::GRAPHICS));
+        assert!(!VkQueueFlags::GRAPHICS.contains(flags));
+        assert!(VkQueueFlags::GRAPHICS.intersects(flags));
+        flags.remove(VkQueueFlags::COMPUTE);
+        assert_eq!(flags, VkQueueFlags::GRAPHICS);
+        flags.toggle(VkQueueFlags::COMPUTE);
+        flags.insert(VkQueueFlags::GRAPHICS);
+        assert_eq!(flags, VkQueueFlags::GRAPHICS | VkQueueFlags::COMPUTE);
+        assert!(VkQueueFlags::all().is_all());
+        assert!(!(VkQueueFlags::all() - VkQueueFlags::GRAPHICS).is_all());
+        assert_eq!(flags - VkQueueFlags::GRAPHICS, VkQueueFlags::COMPUTE);
+        assert_eq!(flags ^ VkQueueFlags::GRAPHICS, VkQueueFlags::COMPUTE);
+        assert_eq!(!VkQueueFlags::GRAPHICS & flags, VkQueueFlags::COMPUTE);
+        assert_eq!(!VkQueueFlags::GRAPHICS, VkQueueFlags::all() - VkQueueFlags::GRAPHICS);
+        assert!((!VkQueueFlags::all()).is_empty());
+        assert_eq!(VkQueueFlags::from(VK_QUEUE_COMPUTE_BIT), VkQueueFlags::COMPUTE);
+        let _: VkQueueFlagBits = VK_QUEUE_COMPUTE_BIT;
+    }
+
+    #[test]
+    fn flag_bits() {
+        let flags = VkQueueFlags::COMPUTE | VkQueueFlags::GRAPHICS;
+        assert_eq!(flags.iter().collect::<Vec<_>>(), vec![VkQueueFlags::GRAPHICS, VkQueueFlags::COMPUTE]);
+        // Every known bit comes out once, from the lowest up
+        let all = VkQueueFlags::all();
+        let bits: Vec<VkQueueFlags> = all.iter().collect();
+        assert!(bits.iter().all(|bit| bit.bits().count_ones() == 1));
+        assert!(bits.windows(2).all(|pair| pair[0].bits() < pair[1].bits()));
+        assert_eq!(bits.iter().fold(VkQueueFlags::empty(), |flags, &bit| flags | bit), all);
+        assert_eq!(VkQueueFlags::from_bits(all.bits()), Some(all));
+        assert_eq!(VkQueueFlags::from_bits(0x8000_0000 | all.bits()), None);
+        assert_eq!(VkQueueFlags::from_bits_truncate(0x8000_0000 | all.bits()), all);
+    }
+
+    #[test]
+    fn flag_names() {
+        assert_eq!(format!("{:?}", VkQueueFlags::GRAPHICS | VkQueueFlags::COMPUTE), "GRAPHICS | COMPUTE");
+        assert_eq!(format!("{:?}", VkQueueFlags::empty()), "(empty)");
+        assert_eq!(format!("{:?}", VkQueueFlags(0x8000_0001)), "GRAPHICS | 0x80000000");
+    }
+}
+
+/// Checks the layouts of the structs and unions against the C headers, if they were found when
+/// building
+#[cfg(test)]
+mod layout_tests {
+    include!(concat!(env!("OUT_DIR"), "/layout_tests.rs"));
 }
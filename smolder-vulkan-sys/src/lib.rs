@@ -1,10 +1,16 @@
-#![feature(optin_builtin_traits)]
 extern crate libc;
 
 pub mod ffi;
 pub mod safe_ffi;
 pub mod mem;
+pub mod commands;
+pub mod entry;
 
+pub use entry::Entry;
+#[cfg(feature = "runtime-loader")]
+pub use entry::LoadError;
+
+#[cfg(not(feature = "runtime-loader"))]
 use std::ffi::CStr;
 
 #[macro_export] macro_rules! vk_make_version {
@@ -13,10 +19,10 @@ use std::ffi::CStr;
     };
 }
 
+#[cfg(not(feature = "runtime-loader"))]
 #[inline]
 pub fn get_entry_proc_addr(name: &CStr) -> ffi::PFN_vkVoidFunction {
-    use ffi::*;
     unsafe {
-        vkGetInstanceProcAddr(NullableHandle::null(), name.as_ptr())
+        ffi::vkGetInstanceProcAddr(None, name.as_ptr())
     }
 }
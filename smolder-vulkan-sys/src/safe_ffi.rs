@@ -1,19 +1,33 @@
-use ::ffi;
-use ::ffi::*;
-use ::mem::{ VkSlice, NTV };
+// Items from deprecated extensions are still used to define the rest of the module
+#![allow(deprecated)]
+// The fields keep the registry's names unless the structs are generated with Rust naming
+#![allow(non_snake_case)]
+
+pub use ::ffi::*;
+use ::mem::NTV;
 
-use ffi::VkStructureType;
-use libc;
 use libc::*;
 use libc::c_char;
-use std::ffi::CStr;
-use std::{ fmt, ptr };
+use std::ptr;
+use std::marker::PhantomData;
 
+/// The `sType` and `pNext` of a struct. It isn't `Clone`, since a copy would alias the mutable
+/// borrow of the chain.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct VkStructInfo<'a> {
     ty: VkStructureType,
-    next: Option<&'a VkStructInfo<'a>>,
+    /// The next struct in the chain, which was borrowed mutably for `'a`, since commands write to
+    /// the structs chained to their outputs
+    next: Option<ptr::NonNull<VkStructInfo<'a>>>,
+    _borrow: PhantomData<&'a mut ()>,
+}
+
+impl<'a> VkStructInfo<'a> {
+    #[inline(always)]
+    pub fn structure_type(&self) -> VkStructureType {
+        self.ty
+    }
 }
 
 pub trait VkStruct {
@@ -23,21 +37,60 @@ pub trait VkStruct {
     fn default_struct_info<'a>() -> VkStructInfo<'a>{
         VkStructInfo {
             ty: <Self as VkStruct>::structure_type(),
-            next: Default::default(),
+            next: None,
+            _borrow: PhantomData,
         }
     }
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct VkStructInstance<'a, T> {
     struct_info: VkStructInfo<'a>,
     data: T,
 }
 
+/// Marks the structs that can be in the `pNext` chain of `Base`, which the registry gives as their
+/// `structextends`
+pub trait Extends<Base>: VkStruct {}
+
 impl<'a, T> VkStructInstance<'a, T> {
-    pub fn set_next<N: 'a>(&mut self, next: Option<&'a VkStructInstance<'a, N>>) {
-        self.struct_info.next = next.map(|n| &n.struct_info);
+    /// Chains `next` right after this struct, in front of any structs that were already chained
+    /// to it.
+    ///
+    /// # Panics
+    ///
+    /// If `next` has structs chained to it already, since they extend `N` and might not extend `T`
+    pub fn push_next<'b: 'a, N: Extends<T>>(&mut self, next: &'b mut VkStructInstance<'a, N>) -> &mut Self {
+        assert!(next.struct_info.next.is_none(), "can't chain a struct that has its own chain");
+        next.struct_info.next = self.struct_info.next.take();
+        // Points to the whole struct, since commands write past its `VkStructInfo`
+        self.struct_info.next = Some(ptr::NonNull::from(next).cast());
+        self
+    }
+
+    /// Iterates over the structs that are chained after this one
+    #[inline]
+    pub fn chain<'s>(&'s self) -> VkStructChain<'s> {
+        VkStructChain {
+            next: self.struct_info.next.map(|info| unsafe { &*info.as_ptr() }),
+        }
+    }
+}
+
+/// An iterator over a `pNext` chain
+#[derive(Debug, Clone)]
+pub struct VkStructChain<'a> {
+    next: Option<&'a VkStructInfo<'a>>,
+}
+
+impl<'a> Iterator for VkStructChain<'a> {
+    type Item = &'a VkStructInfo<'a>;
+
+    fn next(&mut self) -> Option<&'a VkStructInfo<'a>> {
+        let next = self.next;
+        self.next = next.and_then(|info| info.next).map(|info| unsafe { &*info.as_ptr() });
+        next
     }
 }
 
@@ -52,7 +105,8 @@ impl<'a, T: VkStruct> From<T> for VkStructInstance<'a, T> {
 }
 
 macro_rules! vk_extendable_struct {
-    ($base: ident ($($p:tt)+), ($name: ident, $sty: expr)) => {
+    ($(#[$attr: meta])* $base: ident ($($p:tt)+), ($name: ident, $sty: expr)) => {
+        $(#[$attr])*
         pub type $name < $( $p )+ > = VkStructInstance<'a, $base < $( $p )+ >>;
         impl < $( $p )+ > VkStruct for $base < $( $p )+ > {
             #[inline(always)]
@@ -61,9 +115,10 @@ macro_rules! vk_extendable_struct {
             }
         }
     };
-    ($base: ty, ($name: ident, $sty: expr)) => {
+    ($(#[$attr: meta])* $base: ty, ($name: ident, $sty: expr)) => {
+        $(#[$attr])*
         pub type $name<'a>  = VkStructInstance<'a, $base>;
-        impl<'a> VkStruct for $name<'a> {
+        impl VkStruct for $base {
             #[inline(always)]
             fn structure_type() -> ::ffi::VkStructureType {
                 $sty
@@ -72,72 +127,87 @@ macro_rules! vk_extendable_struct {
     };
 }
 
-use self::VkStructureType::*;
-include!(concat!(env!("OUT_DIR"), "/safe_structs.rs"));
+/// Gets the length of a slice as a `uint32_t` count, for the builder methods of the structs and the
+/// command wrappers
+#[inline]
+pub(crate) fn count_u32(len: usize) -> u32 {
+    if len > (u32::MAX as usize) {
+        panic!("slice length {} is greater than u32::MAX ({})", len, u32::MAX);
+    }
+    len as u32
+}
 
-//#[repr(C)]
-//#[derive(Clone, Copy)]
-//pub struct VkApplicationInfoBase<'a> {
-//    pub application_name: Option<&'a NTV<libc::c_char>>,
-//    pub application_version: libc::uint32_t,
-//    pub engine_name: Option<&'a NTV<libc::c_char>>,
-//    pub engine_version: libc::uint32_t,
-//    pub api_version: libc::uint32_t,
-//}
-//vk_extendable_struct!(VkApplicationInfoBase('a), (VkApplicationInfo, VK_STRUCTURE_TYPE_APPLICATION_INFO));
-//
-//#[repr(C)]
-//#[derive(Clone, Copy)]
-//pub struct VkInstanceCreateInfoBase<'a> {
-//    pub flags: ::ffi::VkInstanceCreateFlags,
-//    pub application_info: Option<&'a VkApplicationInfo<'a>>,
-//    pub enabled_layer_names: VkSlice<'a, &'a NTV<c_char>, libc::uint32_t>,
-//    pub enabled_extension_names: VkSlice<'a, &'a NTV<c_char>, libc::uint32_t>
-//}
-//vk_extendable_struct!(VkInstanceCreateInfoBase('a), (VkInstanceCreateInfo, VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO));
-//
-//#[repr(C)]
-//#[derive(Clone, Copy)]
-//pub struct VkBufferViewCreateInfoBase<'a> {
-//    pub flags: ::ffi::VkBufferViewCreateFlags,
-//    pub buffer: ::ffi::VkBuffer<'a>,
-//    pub format: ::ffi::VkFormat,
-//    pub offset: ::ffi::VkDeviceSize,
-//    pub range: ::ffi::VkDeviceSize,
-//}
-//vk_extendable_struct!(VkBufferViewCreateInfoBase('a), (VkBufferViewCreateInfo, VK_STRUCTURE_TYPE_BUFFER_VIEW_CREATE_INFO));
-//
-//#[repr(C)]
-//#[derive(Clone, Copy)]
-//pub struct VkAllocationCallbacks<'a, UserData: 'a + ?Sized> {
-//    pub user_data: &'a mut UserData,
-//    pub allocation: ::ffi::PFN_vkAllocationFunction,
-//    pub reallocation: ::ffi::PFN_vkReallocationFunction,
-//    pub free: ::ffi::PFN_vkFreeFunction,
-//    pub internal_allocation: ::ffi::PFN_vkInternalAllocationNotification,
-//    pub internal_free: ::ffi::PFN_vkInternalFreeNotification,
-//}
+include!(concat!(env!("OUT_DIR"), "/safe_structs.rs"));
 
 #[cfg(test)]
 mod tests {
-    macro_rules! assert_sizes {
-        ($a: ty, $b: ty) => {
-            assert_eq!(::std::mem::size_of::<$a>(), ::std::mem::size_of::<$b>());
-        };
+    #[test]
+    fn slice_builder() {
+        use super::{ VkPipelineVertexInputStateCreateInfoBase, VkVertexInputBindingDescription };
+        let bindings = [VkVertexInputBindingDescription::new().binding(0), VkVertexInputBindingDescription::new().binding(1)];
+        let info = VkPipelineVertexInputStateCreateInfoBase::default().vertex_binding_descriptions(&bindings);
+        assert_eq!(info.vertexBindingDescriptionCount, 2);
+        assert_eq!(info.pVertexBindingDescriptions.map(|b| b as *const _), Some(bindings.as_ptr()));
+        let info = info.vertex_binding_descriptions(&[]);
+        assert_eq!(info.vertexBindingDescriptionCount, 0);
+        assert!(info.pVertexBindingDescriptions.is_none());
     }
 
     #[test]
-    fn application_info_size() {
-        assert_sizes!(super::VkApplicationInfo, ::ffi::VkApplicationInfo);
+    fn struct_chain() {
+        use super::{ VkPhysicalDevice16BitStorageFeatures, VkPhysicalDevice16BitStorageFeaturesBase, VkPhysicalDeviceFeatures2, VkPhysicalDeviceFeatures2Base, VkPhysicalDeviceVariablePointersFeatures, VkPhysicalDeviceVariablePointersFeaturesBase, VkStructureType };
+        let mut storage = VkPhysicalDevice16BitStorageFeatures::from(VkPhysicalDevice16BitStorageFeaturesBase::default());
+        let mut pointers = VkPhysicalDeviceVariablePointersFeatures::from(VkPhysicalDeviceVariablePointersFeaturesBase::default());
+        let mut features = VkPhysicalDeviceFeatures2::from(VkPhysicalDeviceFeatures2Base::default());
+        features.push_next(&mut storage).push_next(&mut pointers);
+        let types: Vec<VkStructureType> = features.chain().map(|info| info.structure_type()).collect();
+        assert_eq!(types, vec![VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VARIABLE_POINTERS_FEATURES, VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_16BIT_STORAGE_FEATURES]);
     }
 
     #[test]
-    fn instance_create_info_size() {
-        assert_sizes!(super::VkInstanceCreateInfo, ::ffi::VkInstanceCreateInfo);
+    fn chain_is_writable() {
+        use ffi;
+        use libc::c_void;
+        use super::{ VkPhysicalDevice16BitStorageFeatures, VkPhysicalDevice16BitStorageFeaturesBase, VkPhysicalDeviceFeatures2, VkPhysicalDeviceFeatures2Base };
+        let mut storage = VkPhysicalDevice16BitStorageFeatures::from(VkPhysicalDevice16BitStorageFeaturesBase::default());
+        let storage_ptr = &mut storage as *mut VkPhysicalDevice16BitStorageFeatures as *mut c_void;
+        let mut features = VkPhysicalDeviceFeatures2::from(VkPhysicalDeviceFeatures2Base::default());
+        features.push_next(&mut storage);
+        // Like vkGetPhysicalDeviceFeatures2 does, through the `pNext` of the struct it's given
+        unsafe {
+            let features = &mut features as *mut VkPhysicalDeviceFeatures2 as *mut ffi::VkPhysicalDeviceFeatures2;
+            assert_eq!((*features).pNext, storage_ptr);
+            let storage = (*features).pNext as *mut ffi::VkPhysicalDevice16BitStorageFeatures;
+            (*storage).storageBuffer16BitAccess = 1;
+            assert_eq!((*storage).sType, ffi::VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_16BIT_STORAGE_FEATURES);
+            assert_eq!((*storage).storageBuffer16BitAccess, 1);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn pushing_a_chain() {
+        use std::ptr::NonNull;
+        use super::{ VkPhysicalDevice16BitStorageFeatures, VkPhysicalDevice16BitStorageFeaturesBase, VkPhysicalDeviceFeatures2, VkPhysicalDeviceFeatures2Base, VkPhysicalDeviceVariablePointersFeatures, VkPhysicalDeviceVariablePointersFeaturesBase };
+        let mut storage = VkPhysicalDevice16BitStorageFeatures::from(VkPhysicalDevice16BitStorageFeaturesBase::default());
+        let mut pointers = VkPhysicalDeviceVariablePointersFeatures::from(VkPhysicalDeviceVariablePointersFeaturesBase::default());
+        // Nothing extends `pointers`, but a chain on it is rejected rather than dropped
+        pointers.struct_info.next = Some(NonNull::from(&mut storage).cast());
+        let mut features = VkPhysicalDeviceFeatures2::from(VkPhysicalDeviceFeatures2Base::default());
+        features.push_next(&mut pointers);
     }
 
     #[test]
-    fn allocation_callbacks_size() {
-        assert_sizes!(super::VkAllocationCallbacks<()>, ::ffi::VkAllocationCallbacks);
+    #[should_panic]
+    fn shared_count_lengths() {
+        use super::{ VkPipelineStageFlags, VkSubmitInfoBase };
+        VkSubmitInfoBase::default().wait_semaphores(&[], &[VkPipelineStageFlags::TOP_OF_PIPE]);
     }
 }
+
+/// Checks that each safe struct is laid out the same as its `ffi` struct
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod layout_tests {
+    include!(concat!(env!("OUT_DIR"), "/safe_layout_tests.rs"));
+}
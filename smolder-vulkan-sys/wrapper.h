@@ -1 +0,0 @@
-#include <vulkan/vulkan.h>
@@ -2,7 +2,12 @@ extern crate vulkan_sys as vk_sys;
 
 pub mod mem;
 pub mod handles;
+pub mod version;
 use mem::VkOwned;
 use vk_sys::{ ffi };
 
+pub use vk_sys::Entry;
+#[cfg(feature = "runtime-loader")]
+pub use vk_sys::LoadError;
+
 pub type VkResult<T> = Result<T, ffi::VkResult>;
@@ -0,0 +1,120 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::mem::transmute;
+use vk_sys::{ ffi, Entry };
+use VkResult;
+
+/// A Vulkan version number, packed the same way as `VK_MAKE_API_VERSION`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(pub u32);
+
+impl Version {
+    #[inline(always)]
+    pub fn new(variant: u32, major: u32, minor: u32, patch: u32) -> Version {
+        Version(ffi::VK_MAKE_API_VERSION(variant, major, minor, patch))
+    }
+
+    #[inline(always)]
+    pub fn variant(self) -> u32 {
+        ffi::VK_API_VERSION_VARIANT(self.0)
+    }
+
+    #[inline(always)]
+    pub fn major(self) -> u32 {
+        ffi::VK_API_VERSION_MAJOR(self.0)
+    }
+
+    #[inline(always)]
+    pub fn minor(self) -> u32 {
+        ffi::VK_API_VERSION_MINOR(self.0)
+    }
+
+    #[inline(always)]
+    pub fn patch(self) -> u32 {
+        ffi::VK_API_VERSION_PATCH(self.0)
+    }
+
+    /// Checks if two versions have the same variant, major, and minor versions (patch versions
+    /// don't change the API)
+    pub fn is_compatible_with(self, other: Version) -> bool {
+        (self.variant(), self.major(), self.minor()) == (other.variant(), other.major(), other.minor())
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.variant() != 0 {
+            write!(f, "{}:", self.variant())?;
+        }
+        write!(f, "{}.{}.{}", self.major(), self.minor(), self.patch())
+    }
+}
+
+/// Version of the Vulkan headers that the bindings were generated from
+pub const HEADER_VERSION: Version = Version(ffi::VK_HEADER_VERSION_COMPLETE);
+
+type EnumerateInstanceVersion = unsafe extern "system" fn(*mut u32) -> ffi::VkResult;
+
+/// Gets the version of Vulkan supported by the installed loader and drivers.
+///
+/// Vulkan 1.0 loaders don't have `vkEnumerateInstanceVersion`, so they're reported as 1.0.
+pub fn instance_version(entry: &Entry) -> VkResult<Version> {
+    let name = CStr::from_bytes_with_nul(b"vkEnumerateInstanceVersion\0").unwrap();
+    let enumerate_instance_version: Option<EnumerateInstanceVersion> = unsafe {
+        transmute(entry.get_entry_proc_addr(name))
+    };
+    match enumerate_instance_version {
+        Some(f) => {
+            let mut version = 0u32;
+            match unsafe { f(&mut version) } {
+                ffi::VkResult::VK_SUCCESS => Ok(Version(version)),
+                e => Err(e),
+            }
+        },
+        None => Ok(Version(ffi::VK_API_VERSION_1_0)),
+    }
+}
+
+/// The version of the headers that smolder was built against, along with the version that's
+/// installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionReport {
+    pub header: Version,
+    pub instance: Version,
+}
+
+impl VersionReport {
+    pub fn new(entry: &Entry) -> VkResult<VersionReport> {
+        instance_version(entry).map(|instance| VersionReport {
+            header: HEADER_VERSION,
+            instance: instance,
+        })
+    }
+
+    /// Checks if the installed version is older than the headers, in which case some of the
+    /// commands and structures that smolder knows about won't be available
+    #[inline]
+    pub fn instance_is_older(&self) -> bool {
+        (self.instance.variant(), self.instance.major(), self.instance.minor()) < (self.header.variant(), self.header.major(), self.header.minor())
+    }
+}
+
+impl fmt::Display for VersionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "built against Vulkan {}, running on Vulkan {}", self.header, self.instance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Version;
+
+    #[test]
+    fn version_parts() {
+        let version = Version::new(0, 1, 3, 250);
+        assert_eq!((version.variant(), version.major(), version.minor(), version.patch()), (0, 1, 3, 250));
+        assert_eq!(version.to_string(), "1.3.250");
+        assert!(version.is_compatible_with(Version::new(0, 1, 3, 0)));
+        assert!(!version.is_compatible_with(Version::new(0, 1, 2, 250)));
+    }
+}
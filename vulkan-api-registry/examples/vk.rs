@@ -1,14 +1,11 @@
 extern crate vulkan_api_registry;
-extern crate xml;
 
-use std::fs;
+use std::env;
 use vulkan_api_registry::Registry;
 
 fn main() {
-    let include_all = |_: &str| true;
-    let events = fs::File::open("../Vulkan-Docs/src/spec/vk.xml")
-        .map(xml::reader::EventReader::new).unwrap();
-    let registry = Registry::new(events.into_iter(), &include_all, &include_all).unwrap();
+    let path = env::args().nth(1).unwrap_or_else(|| String::from("../Vulkan-Docs/src/spec/vk.xml"));
+    let registry = Registry::from_path(path, Some("vulkan")).unwrap();
     println!("types:");
     for (_, ty) in registry.types.iter() {
         println!("  - {:?}", ty);
@@ -25,4 +22,12 @@ fn main() {
     for cmd in registry.commands.iter() {
         println!("  - {:?}", cmd);
     }
+    let problems = registry.validate();
+    if problems.len() > 0 {
+        println!("problems:");
+        for problem in problems.iter() {
+            println!("  - {}", problem);
+        }
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,25 @@
+#!/bin/sh
+# Refreshes the copy of the registry that the `embedded` feature builds in to the crate.
+#
+# With no arguments, vk.xml and video.xml are downloaded from the Vulkan-Docs tag pinned in
+# VERSION. Given another tag, that one is downloaded and pinned instead. Given a Vulkan-Docs
+# checkout (i.e. ../../Vulkan-Docs), its copy is used and pinned to whatever it has checked out.
+set -e
+here=$(cd "$(dirname "$0")" && pwd)
+source=${1:-$(cat "$here/VERSION")}
+if [ -d "$source" ]; then
+    for dir in "$source/xml" "$source/src/spec"; do
+        if [ -f "$dir/vk.xml" ]; then
+            cp "$dir/vk.xml" "$here/vk.xml"
+            cp "$dir/video.xml" "$here/video.xml"
+            (cd "$source" && git describe --tags --always) > "$here/VERSION"
+            exit 0
+        fi
+    done
+    echo "no vk.xml found in $source" >&2
+    exit 1
+fi
+url="https://raw.githubusercontent.com/KhronosGroup/Vulkan-Docs/$source/xml"
+curl -fsSL -o "$here/vk.xml" "$url/vk.xml"
+curl -fsSL -o "$here/video.xml" "$url/video.xml"
+echo "$source" > "$here/VERSION"
@@ -0,0 +1,376 @@
+//! Generation of a C header from a `Registry`.
+//!
+//! The header declares everything that the registry describes, so compiling it is a check that
+//! the parser kept everything needed to use the API.
+
+use std::collections::{ BTreeSet, HashMap, HashSet };
+use std::io::{ self, Write };
+use ::{ EnumValue, HandleType, Provider, Registry, Requirement, TypeInfo, TyperefInfo };
+use ::writer::pointer_declaration;
+
+/// Types that come from the C standard headers instead of the registry
+const C_TYPES: &[&str] = &[
+    "void", "char", "float", "double", "int",
+    "int8_t", "uint8_t", "int16_t", "uint16_t", "int32_t", "uint32_t", "int64_t", "uint64_t",
+    "size_t",
+];
+
+/// Converts a type name like `VkQueueFlagBits` to the `VK_QUEUE_FLAG_BITS` style used by its
+/// enumerants
+fn upper_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut ret = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map(|c| c.is_lowercase()).unwrap_or(false);
+            if previous.is_lowercase() || previous.is_ascii_digit() || (previous.is_uppercase() && next_is_lower) {
+                ret.push('_');
+            }
+        }
+        ret.extend(c.to_uppercase());
+    }
+    ret
+}
+
+/// Gets the name of the `_MAX_ENUM` enumerant that forces an enum to be 32 bits, like
+/// `VK_SURFACE_TRANSFORM_FLAG_BITS_MAX_ENUM_KHR` for `VkSurfaceTransformFlagBitsKHR`
+fn max_enum_name(name: &str) -> String {
+    // Vendor tags go after the `MAX_ENUM`
+    let tag_length = name.chars().rev().take_while(|c| c.is_uppercase()).count();
+    let (base, tag) = if tag_length > 1 && tag_length < name.len() {
+        name.split_at(name.len() - tag_length)
+    } else {
+        (name, "")
+    };
+    if !tag.is_empty() {
+        format!("{}_MAX_ENUM_{}", upper_snake_case(base), tag)
+    } else {
+        format!("{}_MAX_ENUM", upper_snake_case(base))
+    }
+}
+
+/// Renders a C declaration of `name` with the given type
+fn declaration(ty: &TyperefInfo, name: &str) -> String {
+    let (prefix, pointers) = pointer_declaration(ty);
+    let mut ret = format!("{}{}{} {}", prefix, &ty.ty, pointers, name);
+    for size in ty.array.iter() {
+        ret.push_str(format!("[{}]", size).as_str());
+    }
+    ret
+}
+
+fn enum_value(value: &EnumValue) -> String {
+    match *value {
+        EnumValue::Bitpos(bp) => format!("0x{:08X}", 1u64 << bp),
+        EnumValue::Value(v) => v.to_string(),
+        EnumValue::StrValue(ref s) => s.clone(),
+        EnumValue::Alias(ref alias) => alias.clone(),
+    }
+}
+
+fn argument_list<It: Iterator<Item=String>>(arguments: It) -> String {
+    let arguments: Vec<String> = arguments.collect();
+    if arguments.is_empty() {
+        "void".into()
+    } else {
+        format!("\n    {}", arguments.join(",\n    "))
+    }
+}
+
+/// Works out what goes in the header, and in what order
+struct HeaderWriter<'a> {
+    registry: &'a Registry,
+    /// Types that can be declared
+    available: HashSet<&'a str>,
+    /// Enums whose values are 64 bits, mapped to the type of their flags
+    wide_enums: HashMap<&'a str, &'a str>,
+    written: HashSet<&'a str>,
+}
+
+impl<'a> HeaderWriter<'a> {
+    fn new(registry: &'a Registry) -> HeaderWriter<'a> {
+        let available = registry.portable_types();
+        let mut wide_enums = HashMap::new();
+        for info in registry.types.values() {
+            match *info {
+                TypeInfo::Bitmask { ref ty, ref requires, .. } if ty.ends_with("64") => {
+                    for bits in requires.iter() {
+                        wide_enums.insert(bits.as_str(), ty.as_str());
+                    }
+                },
+                _ => {},
+            }
+        }
+        HeaderWriter {
+            registry,
+            available,
+            wide_enums,
+            written: HashSet::new(),
+        }
+    }
+
+    /// Gets the types that a type refers to, and whether each is referred to through a pointer
+    fn references(info: &'a TypeInfo) -> Vec<(&'a str, bool)> {
+        let typeref = |ty: &'a TyperefInfo| (ty.ty.as_str(), !ty.constness.is_empty());
+        match *info {
+            TypeInfo::Basetype { ref ty, .. } | TypeInfo::Bitmask { ref ty, .. } => vec![(ty.as_str(), false)],
+            TypeInfo::Alias { ref alias, .. } => vec![(alias.as_str(), false)],
+            TypeInfo::Struct { ref members, .. } | TypeInfo::Union { ref members, .. } => {
+                members.iter().map(|m| typeref(&m.ty)).collect()
+            },
+            TypeInfo::Funcpointer { ref arguments, ref return_type, .. } => {
+                ::std::iter::once(typeref(return_type))
+                    .chain(arguments.iter().map(|(_, ty)| typeref(ty)))
+                    .collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    fn is_aggregate(&self, name: &str) -> bool {
+        matches!(self.registry.types.get(name), Some(&TypeInfo::Struct { .. }) | Some(&TypeInfo::Union { .. }))
+    }
+
+    fn is_command_available(&self, name: &str) -> bool {
+        let command = &self.registry.commands[name];
+        self.available.contains(command.return_type.ty.as_str()) &&
+            command.params.iter().all(|p| self.available.contains(p.ty.ty.as_str()))
+    }
+
+    /// Writes the values of an enum, including the ones added by features and extensions
+    fn enum_values(&self, name: &str) -> Vec<(&'a str, String)> {
+        self.registry.enum_values(name).into_iter()
+            .map(|(value_name, value)| (value_name, enum_value(&value)))
+            .collect()
+    }
+
+    /// Writes a type, after writing anything that it needs to have been declared first
+    fn write_type<W: Write>(&mut self, sink: &mut W, name: &'a str) -> io::Result<()> {
+        if !self.available.contains(name) || !self.written.insert(name) {
+            return Ok(());
+        }
+        let info = &self.registry.types[name];
+        // Structs and unions are all declared up front, so only their definitions need ordering,
+        // and only when they're used by value
+        let mut dependencies: Vec<&str> = HeaderWriter::references(info).into_iter()
+            .filter(|&(r, pointer)| !(self.is_aggregate(r) && (pointer || !self.is_aggregate(name))))
+            .map(|(r, _)| r)
+            .collect();
+        if let Some(&flags) = self.wide_enums.get(name) {
+            dependencies.push(flags);
+        }
+        for dependency in dependencies {
+            self.write_type(sink, dependency)?;
+        }
+        match *info {
+            TypeInfo::Basetype { ref ty, .. } | TypeInfo::Bitmask { ref ty, .. } => {
+                writeln!(sink, "typedef {} {};", ty, name)?;
+            },
+            TypeInfo::Alias { ref alias, .. } => {
+                writeln!(sink, "typedef {} {};", alias, name)?;
+            },
+            TypeInfo::Handle { ty, .. } => {
+                // Non-dispatchable handles are declared the way they are on 64-bit platforms
+                let comment = match ty {
+                    HandleType::Normal => "",
+                    HandleType::NonDispatchable => " /* non-dispatchable */",
+                };
+                writeln!(sink, "typedef struct {}_T* {};{}", name, name, comment)?;
+            },
+            TypeInfo::Enum { .. } => {
+                let values = self.enum_values(name);
+                if let Some(&flags) = self.wide_enums.get(name) {
+                    writeln!(sink, "typedef {} {};", flags, name)?;
+                    for (value_name, value) in values {
+                        let suffix = if value.starts_with(|c: char| c.is_ascii_digit()) { "ULL" } else { "" };
+                        writeln!(sink, "static const {} {} = {}{};", name, value_name, value, suffix)?;
+                    }
+                } else {
+                    writeln!(sink, "typedef enum {} {{", name)?;
+                    for (value_name, value) in values {
+                        writeln!(sink, "    {} = {},", value_name, value)?;
+                    }
+                    writeln!(sink, "    {} = 0x7FFFFFFF", max_enum_name(name))?;
+                    writeln!(sink, "}} {};", name)?;
+                }
+            },
+            TypeInfo::Struct { ref members, .. } | TypeInfo::Union { ref members, .. } => {
+                let keyword = if let &TypeInfo::Struct { .. } = info { "struct" } else { "union" };
+                writeln!(sink, "{} {} {{", keyword, name)?;
+                for member in members.iter() {
+                    let bitfield = member.bitfield.map(|bits| format!(":{}", bits)).unwrap_or_else(String::new);
+                    writeln!(sink, "    {}{};", declaration(&member.ty, member.name.as_str()), bitfield)?;
+                }
+                writeln!(sink, "}};")?;
+            },
+            TypeInfo::Funcpointer { ref arguments, ref return_type, .. } => {
+                let arguments = argument_list(arguments.iter().map(|(argument, ty)| declaration(ty, argument.as_str())));
+                let (prefix, pointers) = pointer_declaration(return_type);
+                writeln!(sink, "typedef {}{}{} ({}API_PTR *{})({});", prefix, &return_type.ty, pointers, self.registry.dialect.macro_prefix, name, arguments)?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write<W: Write>(&mut self, sink: &mut W) -> io::Result<()> {
+        let prefix = self.registry.dialect.macro_prefix;
+        writeln!(sink, "/* Generated from the API registry. Do not edit. */")?;
+        writeln!(sink, "#ifndef {}_GENERATED_H_", prefix)?;
+        writeln!(sink, "#define {}_GENERATED_H_ 1", prefix)?;
+        writeln!(sink)?;
+        writeln!(sink, "#include <stddef.h>")?;
+        writeln!(sink, "#include <stdint.h>")?;
+        writeln!(sink)?;
+        for suffix in &["API_ATTR", "API_CALL", "API_PTR"] {
+            writeln!(sink, "#ifndef {}{}", prefix, suffix)?;
+            writeln!(sink, "#define {}{}", prefix, suffix)?;
+            writeln!(sink, "#endif")?;
+        }
+        writeln!(sink)?;
+
+        // Only defines of expressions (i.e. version numbers) are kept, since the rest are
+        // declarations that this header spells out itself
+        let defines = self.registry.defines();
+        for define in defines.iter() {
+            match define.parameters {
+                Some(ref parameters) => writeln!(sink, "#define {}({}) {}", &define.name, parameters.join(", "), &define.body)?,
+                None => writeln!(sink, "#define {} {}", &define.name, &define.body)?,
+            }
+        }
+        if !defines.is_empty() {
+            writeln!(sink)?;
+        }
+
+        // Constants can be used as array sizes, so they go first
+        let platform_extensions: BTreeSet<&str> = self.registry.extensions.iter()
+            .filter(|e| e.protect.is_some())
+            .map(|e| e.name.as_str())
+            .collect();
+        for feature in self.registry.features.iter() {
+            writeln!(sink, "#define {} 1", &feature.name)?;
+        }
+        for extension in self.registry.extensions.iter().filter(|e| !platform_extensions.contains(e.name.as_str())) {
+            writeln!(sink, "#define {} 1", &extension.name)?;
+        }
+        for enums in self.registry.enums.iter().filter(|e| e.ty.is_none()) {
+            for (name, value) in enums.values.iter() {
+                writeln!(sink, "#define {} {}", name, enum_value(value))?;
+            }
+        }
+        let extension_constants = self.registry.extensions.iter()
+            .filter(|e| !platform_extensions.contains(e.name.as_str()))
+            .flat_map(|e| e.requirements());
+        let mut constants = HashSet::new();
+        for requirement in extension_constants {
+            if let Requirement::Constant { ref name, ref value, .. } = *requirement {
+                if constants.insert(name.as_str()) {
+                    writeln!(sink, "#define {} {}", name, enum_value(value))?;
+                }
+            }
+        }
+        writeln!(sink)?;
+
+        let mut names: Vec<&'a str> = self.available.iter().cloned().collect();
+        names.sort();
+        for &name in names.iter() {
+            match self.registry.types[name] {
+                TypeInfo::Struct { .. } => writeln!(sink, "typedef struct {} {};", name, name)?,
+                TypeInfo::Union { .. } => writeln!(sink, "typedef union {} {};", name, name)?,
+                _ => {},
+            }
+        }
+        writeln!(sink)?;
+        for &name in names.iter() {
+            self.write_type(sink, name)?;
+        }
+        writeln!(sink)?;
+
+        let mut commands: Vec<&str> = self.registry.commands.keys()
+            .map(|c| c.as_str())
+            .filter(|c| self.is_command_available(c))
+            .collect();
+        commands.sort();
+        for &name in commands.iter() {
+            let command = &self.registry.commands[name];
+            let arguments = argument_list(command.params.iter().map(|p| declaration(&p.ty, p.name.as_str())));
+            let (ret_prefix, ret_pointers) = pointer_declaration(&command.return_type);
+            writeln!(sink, "typedef {}{}{} ({}API_PTR *PFN_{})({});", ret_prefix, &command.return_type.ty, ret_pointers, prefix, name, arguments)?;
+        }
+        writeln!(sink)?;
+        writeln!(sink, "#ifndef {}_NO_PROTOTYPES", prefix)?;
+        for &name in commands.iter() {
+            let command = &self.registry.commands[name];
+            let arguments = argument_list(command.params.iter().map(|p| declaration(&p.ty, p.name.as_str())));
+            let (ret_prefix, ret_pointers) = pointer_declaration(&command.return_type);
+            writeln!(sink, "{}API_ATTR {}{}{} {}API_CALL {}({});", prefix, ret_prefix, &command.return_type.ty, ret_pointers, prefix, name, arguments)?;
+        }
+        writeln!(sink, "#endif")?;
+        writeln!(sink)?;
+        writeln!(sink, "#endif")
+    }
+}
+
+impl Registry {
+    /// Gets the types that can be declared without any platform's headers.
+    ///
+    /// Like `vulkan_core.h`, this leaves out anything that only platform specific extensions
+    /// provide, along with anything that refers to those. Defines, includes, and C types that
+    /// aren't from the standard headers are left out too.
+    pub fn portable_types(&self) -> HashSet<&str> {
+        let providers = self.providers();
+        let is_platform_specific = |name: &str| {
+            providers.get(name).map(|p| p.iter().all(|p| match *p {
+                Provider::Extension { ref name, .. } => self.extension(name).map(|e| e.protect.is_some()).unwrap_or(false),
+                _ => false,
+            })).unwrap_or(false)
+        };
+        let mut available: HashSet<&str> = self.types.iter()
+            .filter(|&(name, info)| match *info {
+                TypeInfo::Uncategorized { .. } => C_TYPES.contains(&name.as_str()),
+                TypeInfo::Define { .. } | TypeInfo::Include(_) | TypeInfo::Constant { .. } | TypeInfo::Group => false,
+                _ => !is_platform_specific(name.as_str()),
+            })
+            .map(|(name, _)| name.as_str())
+            .collect();
+        // Drop anything that refers to something that isn't available until nothing changes
+        loop {
+            let unavailable: Vec<&str> = available.iter()
+                .filter(|&&name| HeaderWriter::references(&self.types[name]).iter().any(|&(r, _)| !available.contains(r)))
+                .cloned()
+                .collect();
+            if unavailable.is_empty() {
+                break;
+            }
+            for name in unavailable {
+                available.remove(name);
+            }
+        }
+        available
+    }
+
+    /// Writes a C header declaring the types, enums, constants and commands of this registry,
+    /// similar to `vulkan_core.h`.
+    ///
+    /// Like `vulkan_core.h`, anything that's only provided by platform specific extensions is left
+    /// out, along with anything that depends on it. Only defines of C expressions are written,
+    /// since the others (like `VK_DEFINE_HANDLE`) are replaced by the declarations they'd expand
+    /// to.
+    pub fn write_c_header<W: Write>(&self, mut sink: W) -> io::Result<()> {
+        HeaderWriter::new(self).write(&mut sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::max_enum_name;
+
+    #[test]
+    fn max_enum_names() {
+        assert_eq!(max_enum_name("VkResult"), "VK_RESULT_MAX_ENUM");
+        assert_eq!(max_enum_name("VkQueueFlagBits"), "VK_QUEUE_FLAG_BITS_MAX_ENUM");
+        assert_eq!(max_enum_name("VkSurfaceTransformFlagBitsKHR"), "VK_SURFACE_TRANSFORM_FLAG_BITS_MAX_ENUM_KHR");
+        assert_eq!(max_enum_name("VkImageViewType"), "VK_IMAGE_VIEW_TYPE_MAX_ENUM");
+    }
+}
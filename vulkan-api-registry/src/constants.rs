@@ -0,0 +1,391 @@
+//! Evaluation of the C expressions used as the values of API constants
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use ::{ EnumValue, ParseError, ParseResult, Registry, Requirement };
+
+/// The value of an API constant, typed the way that C types the expression it came from (or by its
+/// `type` attribute, if it has one).
+///
+/// `(~0U)` is a `U32`, `(~0ULL)` is a `U64`, `1000.0F` is an `F32`, and `256` is an `I32`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+    I32(i32),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    Str(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Literal(&'a str),
+    Str(&'a str),
+    Not,
+    Minus,
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> ParseResult<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut literal_start: Option<usize> = None;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_alphanumeric() || c == '.' || c == '_' {
+            if literal_start.is_none() {
+                literal_start = Some(i);
+            }
+            continue;
+        }
+        if let Some(start) = literal_start.take() {
+            tokens.push(Token::Literal(&s[start..i]));
+        }
+        match c {
+            '"' => {
+                let end = s[i + 1..].find('"').map(|n| i + 1 + n);
+                match end {
+                    Some(end) => {
+                        tokens.push(Token::Str(&s[i + 1..end]));
+                        while chars.peek().map(|&(j, _)| j <= end).unwrap_or(false) {
+                            chars.next();
+                        }
+                    },
+                    None => {
+                        return Err(ParseError::Custom(format!("unterminated string in constant: {}", s).into()));
+                    },
+                }
+            },
+            '~' => tokens.push(Token::Not),
+            '-' => tokens.push(Token::Minus),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_whitespace() => {},
+            c => {
+                return Err(ParseError::Custom(format!("unexpected character '{}' in constant: {}", c, s).into()));
+            },
+        }
+    }
+    if let Some(start) = literal_start {
+        tokens.push(Token::Literal(&s[start..]));
+    }
+    Ok(tokens)
+}
+
+/// Parses a C integer or floating point literal, including its suffix
+pub fn parse_literal(literal: &str) -> ParseResult<ConstantValue> {
+    let bad_literal = || ParseError::Custom(format!("bad literal in constant: {}", literal).into());
+    let lower = literal.to_lowercase();
+    let is_hex = lower.starts_with("0x");
+    if !is_hex && (lower.contains('.') || lower.contains('e') || lower.ends_with('f')) {
+        return lower.trim_end_matches('f').parse::<f32>()
+            .map(ConstantValue::F32)
+            .map_err(|_| bad_literal());
+    }
+    let digits = lower.trim_end_matches(['u', 'l']);
+    let suffix = &lower[digits.len()..];
+    let unsigned = suffix.contains('u');
+    let long = suffix.contains('l');
+    let value = if is_hex {
+        u64::from_str_radix(&digits[2..], 16)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse::<u64>()
+    }.map_err(|_| bad_literal())?;
+    // Like C, use the first type that the value fits in (except that there are no signed 64-bit
+    // constants)
+    let fits_u32 = value <= u32::MAX as u64;
+    if !unsigned && !long && value <= i32::MAX as u64 {
+        Ok(ConstantValue::I32(value as i32))
+    } else if (unsigned || is_hex) && !long && fits_u32 {
+        Ok(ConstantValue::U32(value as u32))
+    } else {
+        Ok(ConstantValue::U64(value))
+    }
+}
+
+struct Parser<'a, 'b> {
+    tokens: &'b [Token<'a>],
+    position: usize,
+}
+
+impl<'a, 'b> Parser<'a, 'b> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.position)
+    }
+
+    fn bad_expression(&self) -> ParseError {
+        ParseError::Custom(format!("bad constant expression at token {}: {:?}", self.position, self.peek()).into())
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<ConstantValue> {
+        match self.peek().cloned() {
+            Some(Token::Not) => {
+                self.position += 1;
+                let operand = self.parse_unary()?;
+                match operand {
+                    ConstantValue::I32(v) => Ok(ConstantValue::I32(!v)),
+                    ConstantValue::U32(v) => Ok(ConstantValue::U32(!v)),
+                    ConstantValue::U64(v) => Ok(ConstantValue::U64(!v)),
+                    _ => Err(ParseError::Custom("~ can only be applied to integers".into())),
+                }
+            },
+            Some(Token::Minus) => {
+                self.position += 1;
+                let operand = self.parse_unary()?;
+                match operand {
+                    ConstantValue::I32(v) => Ok(ConstantValue::I32(v.wrapping_neg())),
+                    ConstantValue::U32(v) => Ok(ConstantValue::U32(v.wrapping_neg())),
+                    ConstantValue::U64(v) => Ok(ConstantValue::U64(v.wrapping_neg())),
+                    ConstantValue::F32(v) => Ok(ConstantValue::F32(-v)),
+                    ConstantValue::Str(_) => Err(ParseError::Custom("strings can't be negated".into())),
+                }
+            },
+            Some(Token::Literal(literal)) => {
+                self.position += 1;
+                parse_literal(literal)
+            },
+            Some(Token::Str(s)) => {
+                self.position += 1;
+                Ok(ConstantValue::Str(s.into()))
+            },
+            Some(Token::Open) => {
+                self.position += 1;
+                let inner = self.parse_unary()?;
+                if self.peek() == Some(&Token::Close) {
+                    self.position += 1;
+                    Ok(inner)
+                } else {
+                    Err(self.bad_expression())
+                }
+            },
+            _ => Err(self.bad_expression()),
+        }
+    }
+}
+
+/// Checks if a constant's value is the name of another constant, rather than an expression
+fn is_identifier(s: &str) -> bool {
+    s.chars().next().map(|c| c.is_alphabetic() || c == '_').unwrap_or(false) &&
+        s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+impl ConstantValue {
+    /// Evaluates a constant expression.
+    ///
+    /// Only the forms found in registries are supported: integer, floating point, and string
+    /// literals, unary `~` and `-`, and parentheses.
+    pub fn evaluate(expression: &str) -> ParseResult<ConstantValue> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let value = parser.parse_unary()?;
+        if parser.position == tokens.len() {
+            Ok(value)
+        } else {
+            Err(parser.bad_expression())
+        }
+    }
+
+    /// Converts this value to a C type (`uint32_t`, `uint64_t`, `int32_t`, or `float`) the way
+    /// that C would
+    pub fn cast(&self, c_type: &str) -> ParseResult<ConstantValue> {
+        let value = match (c_type, self) {
+            ("uint32_t", &ConstantValue::I32(v)) => ConstantValue::U32(v as u32),
+            ("uint32_t", &ConstantValue::U32(v)) => ConstantValue::U32(v),
+            ("uint32_t", &ConstantValue::U64(v)) => ConstantValue::U32(v as u32),
+            ("uint64_t", &ConstantValue::I32(v)) => ConstantValue::U64(v as i64 as u64),
+            ("uint64_t", &ConstantValue::U32(v)) => ConstantValue::U64(v as u64),
+            ("uint64_t", &ConstantValue::U64(v)) => ConstantValue::U64(v),
+            ("int32_t", &ConstantValue::I32(v)) => ConstantValue::I32(v),
+            ("int32_t", &ConstantValue::U32(v)) => ConstantValue::I32(v as i32),
+            ("int32_t", &ConstantValue::U64(v)) => ConstantValue::I32(v as i32),
+            ("float", &ConstantValue::I32(v)) => ConstantValue::F32(v as f32),
+            ("float", &ConstantValue::U32(v)) => ConstantValue::F32(v as f32),
+            ("float", &ConstantValue::U64(v)) => ConstantValue::F32(v as f32),
+            ("float", &ConstantValue::F32(v)) => ConstantValue::F32(v),
+            (ty, value) => {
+                return Err(ParseError::Custom(format!("can't convert {:?} to {}", value, ty).into()));
+            },
+        };
+        Ok(value)
+    }
+
+    /// Gets the C type of this value
+    pub fn c_type(&self) -> &'static str {
+        match *self {
+            ConstantValue::I32(_) => "int32_t",
+            ConstantValue::U32(_) => "uint32_t",
+            ConstantValue::U64(_) => "uint64_t",
+            ConstantValue::F32(_) => "float",
+            ConstantValue::Str(_) => "const char*",
+        }
+    }
+
+    /// Gets the Rust type of this value
+    pub fn rust_type(&self) -> &'static str {
+        match *self {
+            ConstantValue::I32(_) => "i32",
+            ConstantValue::U32(_) => "u32",
+            ConstantValue::U64(_) => "u64",
+            ConstantValue::F32(_) => "f32",
+            ConstantValue::Str(_) => "&'static str",
+        }
+    }
+
+    /// Gets this value as an unsigned integer, if it is a non-negative integer
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            ConstantValue::I32(v) if v >= 0 => Some(v as u64),
+            ConstantValue::U32(v) => Some(v as u64),
+            ConstantValue::U64(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ConstantValue {
+    type Err = ParseError;
+    #[inline(always)]
+    fn from_str(s: &str) -> ParseResult<ConstantValue> {
+        ConstantValue::evaluate(s)
+    }
+}
+
+/// Formats the value as a Rust literal
+impl fmt::Display for ConstantValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConstantValue::I32(v) => write!(f, "{}", v),
+            ConstantValue::U32(v) => write!(f, "{}", v),
+            ConstantValue::U64(v) => write!(f, "{}", v),
+            ConstantValue::F32(v) => write!(f, "{:?}", v),
+            ConstantValue::Str(ref s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+/// How a constant is defined, after evaluating any expression
+enum Definition<'a> {
+    Value(ConstantValue),
+    Alias(&'a str),
+}
+
+impl<'a> Definition<'a> {
+    fn new(value: &'a EnumValue, ty: Option<&str>) -> ParseResult<Option<Definition<'a>>> {
+        let value = match *value {
+            EnumValue::Value(v) => ConstantValue::evaluate(v.to_string().as_str())?,
+            EnumValue::StrValue(ref s) if is_identifier(s.as_str()) => return Ok(Some(Definition::Alias(s.as_str()))),
+            EnumValue::StrValue(ref s) => ConstantValue::evaluate(s.as_str())?,
+            EnumValue::Alias(ref alias) => return Ok(Some(Definition::Alias(alias.as_str()))),
+            EnumValue::Bitpos(_) => return Ok(None),
+        };
+        match ty {
+            Some(ty) => value.cast(ty).map(|v| Some(Definition::Value(v))),
+            None => Ok(Some(Definition::Value(value))),
+        }
+    }
+}
+
+impl Registry {
+    /// Evaluates the API constants, and the constants defined by features and extensions.
+    ///
+    /// Constants come in the order that they're first defined. Aliases get the value of the
+    /// constant they alias, and values naming anything other than a constant (i.e. old-style
+    /// aliases of enumerants) are skipped.
+    pub fn constants(&self) -> ParseResult<Vec<(&str, ConstantValue)>> {
+        let api_constants = self.enums.iter()
+            .filter(|e| e.ty.is_none())
+            .flat_map(|e| e.values.iter().map(move |(name, value)| (name, value, e.value_types.get(name))));
+        let required_constants = self.features.iter().flat_map(|f| f.requirements())
+            .chain(self.extensions.iter().flat_map(|e| e.requirements()))
+            .filter_map(|requirement| match *requirement {
+                Requirement::Constant { ref name, ref value, ref ty } => Some((name, value, ty.as_ref())),
+                _ => None,
+            });
+        let mut names: Vec<&str> = Vec::new();
+        let mut aliases: Vec<(&str, &str)> = Vec::new();
+        let mut values: HashMap<&str, ConstantValue> = HashMap::new();
+        for (name, value, ty) in api_constants.chain(required_constants) {
+            if names.contains(&name.as_str()) {
+                continue;
+            }
+            let definition = Definition::new(value, ty.map(|s| s.as_str()))
+                .map_err(|e| ParseError::Custom(format!("constant {} didn't evaluate: {:?}", name, e).into()))?;
+            match definition {
+                Some(Definition::Value(v)) => {
+                    values.insert(name.as_str(), v);
+                },
+                Some(Definition::Alias(alias)) => aliases.push((name.as_str(), alias)),
+                None => continue,
+            }
+            names.push(name.as_str());
+        }
+        // Aliases can be chained, so keep resolving them until nothing changes
+        loop {
+            let resolved: Vec<(&str, ConstantValue)> = aliases.iter()
+                .filter(|&&(name, _)| !values.contains_key(name))
+                .filter_map(|&(name, alias)| values.get(alias).map(|v| (name, v.clone())))
+                .collect();
+            if resolved.is_empty() {
+                break;
+            }
+            values.extend(resolved);
+        }
+        Ok(names.into_iter().filter_map(|name| values.get(name).map(|v| (name, v.clone()))).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConstantValue;
+    use super::ConstantValue::*;
+
+    fn evaluate(s: &str) -> ConstantValue {
+        ConstantValue::evaluate(s).unwrap()
+    }
+
+    #[test]
+    fn evaluate_suffixed_literals() {
+        assert_eq!(evaluate("(~0U)"), U32(0xFFFFFFFF));
+        assert_eq!(evaluate("(~1U)"), U32(0xFFFFFFFE));
+        assert_eq!(evaluate("(~2U)"), U32(0xFFFFFFFD));
+        assert_eq!(evaluate("(~0ULL)"), U64(0xFFFFFFFFFFFFFFFF));
+        assert_eq!(evaluate("1000.0F"), F32(1000.0));
+        assert_eq!(evaluate("1000.0f"), F32(1000.0));
+    }
+
+    #[test]
+    fn evaluate_unsuffixed_literals() {
+        assert_eq!(evaluate("256"), I32(256));
+        assert_eq!(evaluate("-1"), I32(-1));
+        assert_eq!(evaluate("0x7FFFFFFF"), I32(0x7FFFFFFF));
+        assert_eq!(evaluate("0xFFFFFFFF"), U32(0xFFFFFFFF));
+        assert_eq!(evaluate("4294967296"), U64(4294967296));
+    }
+
+    #[test]
+    fn evaluate_strings() {
+        assert_eq!(evaluate("\"VK_KHR_surface\""), Str("VK_KHR_surface".into()));
+    }
+
+    #[test]
+    fn cast_like_c() {
+        assert_eq!(evaluate("256").cast("uint32_t").unwrap(), U32(256));
+        assert_eq!(evaluate("-1").cast("uint64_t").unwrap(), U64(!0));
+        assert_eq!(evaluate("1000").cast("float").unwrap(), F32(1000.0));
+        assert!(evaluate("1.5F").cast("uint32_t").is_err());
+    }
+
+    #[test]
+    fn bad_expressions_are_errors() {
+        assert!(ConstantValue::evaluate("(~0U").is_err());
+        assert!(ConstantValue::evaluate("~1.0F").is_err());
+        assert!(ConstantValue::evaluate("1 << 4").is_err());
+        assert!(ConstantValue::evaluate("\"unterminated").is_err());
+    }
+}
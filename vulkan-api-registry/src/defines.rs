@@ -0,0 +1,450 @@
+//! Parsing of the bodies of defines whose replacement text is a C expression (i.e. version
+//! numbers and the macros that make and take them apart)
+
+use std::collections::BTreeSet;
+use std::fmt;
+use constants::parse_literal;
+use ::{ ConstantValue, ParseError, ParseResult, Registry, TypeInfo };
+
+/// C types that can appear in casts
+const CAST_TYPES: &[&str] = &["uint8_t", "uint16_t", "uint32_t", "uint64_t", "int8_t", "int16_t", "int32_t", "int64_t", "int", "unsigned", "size_t", "float", "double"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnaryOperator {
+    Not,
+    Negate,
+}
+
+impl UnaryOperator {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOperator::Not => "~",
+            UnaryOperator::Negate => "-",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BinaryOperator {
+    Multiply,
+    Divide,
+    Remainder,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Xor,
+    Or,
+}
+
+impl BinaryOperator {
+    /// The symbol for this operator, which is the same in C and Rust
+    pub fn symbol(self) -> &'static str {
+        use self::BinaryOperator::*;
+        match self {
+            Multiply => "*",
+            Divide => "/",
+            Remainder => "%",
+            Add => "+",
+            Subtract => "-",
+            ShiftLeft => "<<",
+            ShiftRight => ">>",
+            And => "&",
+            Xor => "^",
+            Or => "|",
+        }
+    }
+
+    /// C precedence of this operator, where higher binds tighter
+    fn precedence(self) -> u8 {
+        use self::BinaryOperator::*;
+        match self {
+            Multiply | Divide | Remainder => 5,
+            Add | Subtract => 4,
+            ShiftLeft | ShiftRight => 3,
+            And => 2,
+            Xor => 1,
+            Or => 0,
+        }
+    }
+}
+
+/// A C expression from the body of a define
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(ConstantValue),
+    /// A macro parameter, or another define
+    Name(String),
+    /// A function-like macro invocation
+    Call(String, Vec<Expression>),
+    /// A cast to a C type
+    Cast(String, Box<Expression>),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Literal(&'a str),
+    Name(&'a str),
+    Unary(UnaryOperator),
+    Binary(BinaryOperator),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(s: &str) -> ParseResult<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut word_start: Option<usize> = None;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            if word_start.is_none() {
+                word_start = Some(i);
+            }
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            tokens.push(word_token(&s[start..i]));
+        }
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '~' => Token::Unary(UnaryOperator::Not),
+            // `-` is disambiguated by the parser
+            '-' => Token::Binary(BinaryOperator::Subtract),
+            '+' => Token::Binary(BinaryOperator::Add),
+            '*' => Token::Binary(BinaryOperator::Multiply),
+            '/' => Token::Binary(BinaryOperator::Divide),
+            '%' => Token::Binary(BinaryOperator::Remainder),
+            '&' => Token::Binary(BinaryOperator::And),
+            '^' => Token::Binary(BinaryOperator::Xor),
+            '|' => Token::Binary(BinaryOperator::Or),
+            '<' | '>' if chars.peek().map(|&(_, next)| next) == Some(c) => {
+                chars.next();
+                Token::Binary(if c == '<' { BinaryOperator::ShiftLeft } else { BinaryOperator::ShiftRight })
+            },
+            c if c.is_whitespace() => continue,
+            c => {
+                return Err(ParseError::Custom(format!("unexpected character '{}' in expression: {}", c, s).into()));
+            },
+        };
+        tokens.push(token);
+    }
+    if let Some(start) = word_start {
+        tokens.push(word_token(&s[start..]));
+    }
+    Ok(tokens)
+}
+
+fn word_token(word: &str) -> Token<'_> {
+    if word.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        Token::Literal(word)
+    } else {
+        Token::Name(word)
+    }
+}
+
+struct Parser<'a, 'b> {
+    tokens: &'b [Token<'a>],
+    position: usize,
+}
+
+impl<'a, 'b> Parser<'a, 'b> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.position)
+    }
+
+    fn bad_expression(&self) -> ParseError {
+        ParseError::Custom(format!("bad expression at token {}: {:?}", self.position, self.peek()).into())
+    }
+
+    fn expect(&mut self, token: Token<'a>) -> ParseResult<()> {
+        if self.peek() == Some(&token) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.bad_expression())
+        }
+    }
+
+    /// Parses binary operators by precedence climbing
+    fn parse_binary(&mut self, min_precedence: u8) -> ParseResult<Expression> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some(&Token::Binary(operator)) if operator.precedence() >= min_precedence => operator,
+                _ => return Ok(lhs),
+            };
+            self.position += 1;
+            let rhs = self.parse_binary(operator.precedence() + 1)?;
+            lhs = Expression::Binary(operator, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<Expression> {
+        let operator = match self.peek() {
+            Some(&Token::Unary(operator)) => Some(operator),
+            Some(&Token::Binary(BinaryOperator::Subtract)) => Some(UnaryOperator::Negate),
+            _ => None,
+        };
+        match operator {
+            Some(operator) => {
+                self.position += 1;
+                let operand = self.parse_unary()?;
+                Ok(Expression::Unary(operator, Box::new(operand)))
+            },
+            None => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<Expression> {
+        match self.peek().cloned() {
+            Some(Token::Literal(literal)) => {
+                self.position += 1;
+                parse_literal(literal).map(Expression::Literal)
+            },
+            Some(Token::Name(name)) => {
+                self.position += 1;
+                if self.peek() != Some(&Token::Open) {
+                    return Ok(Expression::Name(name.into()));
+                }
+                self.position += 1;
+                let mut arguments = Vec::new();
+                if self.peek() != Some(&Token::Close) {
+                    arguments.push(self.parse_binary(0)?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        arguments.push(self.parse_binary(0)?);
+                    }
+                }
+                self.expect(Token::Close)?;
+                Ok(Expression::Call(name.into(), arguments))
+            },
+            Some(Token::Open) => {
+                self.position += 1;
+                match (self.peek().cloned(), self.tokens.get(self.position + 1)) {
+                    (Some(Token::Name(ty)), Some(&Token::Close)) if CAST_TYPES.contains(&ty) => {
+                        self.position += 2;
+                        let operand = self.parse_unary()?;
+                        Ok(Expression::Cast(ty.into(), Box::new(operand)))
+                    },
+                    _ => {
+                        let inner = self.parse_binary(0)?;
+                        self.expect(Token::Close)?;
+                        Ok(inner)
+                    },
+                }
+            },
+            _ => Err(self.bad_expression()),
+        }
+    }
+}
+
+impl Expression {
+    /// Parses a C expression made of literals, names, function-like macro invocations, casts, and
+    /// arithmetic and bitwise operators
+    pub fn parse(s: &str) -> ParseResult<Expression> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let expression = parser.parse_binary(0)?;
+        if parser.position == tokens.len() {
+            Ok(expression)
+        } else {
+            Err(parser.bad_expression())
+        }
+    }
+
+    /// Gets the names of the parameters, defines, and macros used by this expression
+    pub fn names(&self) -> BTreeSet<&str> {
+        let mut names = BTreeSet::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        match *self {
+            Expression::Literal(_) => {},
+            Expression::Name(ref name) => {
+                names.insert(name.as_str());
+            },
+            Expression::Call(ref name, ref arguments) => {
+                names.insert(name.as_str());
+                for argument in arguments {
+                    argument.collect_names(names);
+                }
+            },
+            Expression::Cast(_, ref operand) | Expression::Unary(_, ref operand) => operand.collect_names(names),
+            Expression::Binary(_, ref lhs, ref rhs) => {
+                lhs.collect_names(names);
+                rhs.collect_names(names);
+            },
+        }
+    }
+}
+
+/// Formats the expression as C, with every operation parenthesized
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expression::Literal(ref value) => match *value {
+                ConstantValue::U32(v) => write!(f, "{}U", v),
+                ConstantValue::U64(v) => write!(f, "{}ULL", v),
+                ConstantValue::F32(v) => write!(f, "{:?}F", v),
+                ConstantValue::Str(ref s) => write!(f, "{:?}", s),
+                ref v => write!(f, "{}", v),
+            },
+            Expression::Name(ref name) => f.write_str(name),
+            Expression::Call(ref name, ref arguments) => {
+                write!(f, "{}(", name)?;
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", argument)?;
+                }
+                f.write_str(")")
+            },
+            Expression::Cast(ref ty, ref operand) => write!(f, "(({}){})", ty, operand),
+            Expression::Unary(operator, ref operand) => write!(f, "({}{})", operator.symbol(), operand),
+            Expression::Binary(operator, ref lhs, ref rhs) => write!(f, "({} {} {})", lhs, operator.symbol(), rhs),
+        }
+    }
+}
+
+/// A define whose body is a C expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Define {
+    pub name: String,
+    /// Parameter names, if this is a function-like macro
+    pub parameters: Option<Vec<String>>,
+    pub body: Expression,
+}
+
+/// Gets the one `#define` directive in the text of a define, with comments and line
+/// continuations removed. Text with other directives (i.e. `#ifdef`s) gives `None`.
+fn directive(text: &str) -> Option<String> {
+    let joined = text.replace("\\\n", " ");
+    let mut lines = joined.lines()
+        .map(|line| line.find("//").map(|i| &line[..i]).unwrap_or(line).trim())
+        .filter(|line| !line.is_empty());
+    match (lines.next(), lines.next()) {
+        (Some(line), None) if line.starts_with("#define") => Some(line.into()),
+        _ => None,
+    }
+}
+
+impl Define {
+    /// Parses the text of a define, if it's a single `#define` of a C expression.
+    ///
+    /// Defines of other things, like `VK_DEFINE_HANDLE` (which expands to a declaration), give
+    /// `None`.
+    pub fn parse(text: &str) -> Option<Define> {
+        let directive = directive(text)?;
+        let rest = directive["#define".len()..].trim_start();
+        let name_end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(name_end);
+        // Only a parenthesis right after the name starts a parameter list
+        let (parameters, body) = if rest.starts_with('(') {
+            match rest.find(')') {
+                Some(end) => {
+                    let parameters = rest[1..end].split(',')
+                        .map(|p| p.trim())
+                        .filter(|p| !p.is_empty())
+                        .map(Into::into)
+                        .collect();
+                    (Some(parameters), &rest[end + 1..])
+                },
+                None => return None,
+            }
+        } else {
+            (None, rest)
+        };
+        if name.is_empty() || body.trim().is_empty() {
+            return None;
+        }
+        Expression::parse(body).ok().map(|body| Define {
+            name: name.into(),
+            parameters,
+            body,
+        })
+    }
+}
+
+impl Registry {
+    /// Gets the defines whose bodies are C expressions, sorted by name
+    pub fn defines(&self) -> Vec<Define> {
+        let mut defines: Vec<Define> = self.types.values().filter_map(|info| match *info {
+            TypeInfo::Define { ref name, ref text } => Define::parse(text.as_str()).and_then(|d| if &d.name == name {
+                Some(d)
+            } else {
+                None
+            }),
+            _ => None,
+        }).collect();
+        defines.sort_by(|a, b| a.name.cmp(&b.name));
+        defines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ BinaryOperator, Define, Expression };
+    use super::Expression::*;
+    use ConstantValue;
+
+    fn name(s: &str) -> Box<Expression> {
+        Box::new(Name(s.into()))
+    }
+
+    fn literal(v: i32) -> Box<Expression> {
+        Box::new(Literal(ConstantValue::I32(v)))
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let parsed = Expression::parse("a | b << 2 & c").unwrap();
+        assert_eq!(parsed, Binary(BinaryOperator::Or, name("a"), Box::new(Binary(
+            BinaryOperator::And,
+            Box::new(Binary(BinaryOperator::ShiftLeft, name("b"), literal(2))),
+            name("c"),
+        ))));
+    }
+
+    #[test]
+    fn parse_casts_and_calls() {
+        let parsed = Expression::parse("((uint32_t)(version) >> 22U) & f(1, x)").unwrap();
+        assert_eq!(parsed.to_string(), "((((uint32_t)version) >> 22U) & f(1, x))");
+    }
+
+    #[test]
+    fn parse_object_like_define() {
+        let define = Define::parse("// Version of this file\n#define VK_HEADER_VERSION 67").unwrap();
+        assert_eq!(define, Define {
+            name: "VK_HEADER_VERSION".into(),
+            parameters: None,
+            body: Literal(ConstantValue::I32(67)),
+        });
+    }
+
+    #[test]
+    fn parse_function_like_define() {
+        let text = "#define VK_MAKE_VERSION(major, minor, patch) \\\n    ((((uint32_t)(major)) << 22U) | (((uint32_t)(minor)) << 12U) | ((uint32_t)(patch)))";
+        let define = Define::parse(text).unwrap();
+        assert_eq!(define.parameters, Some(vec!["major".into(), "minor".into(), "patch".into()]));
+        assert_eq!(define.body.names().into_iter().collect::<Vec<_>>(), vec!["major", "minor", "patch"]);
+    }
+
+    #[test]
+    fn non_expression_defines() {
+        assert_eq!(Define::parse("#define VK_DEFINE_HANDLE(object) typedef struct object##_T* object;"), None);
+        assert_eq!(Define::parse("//#define VK_API_VERSION VK_MAKE_VERSION(1, 0, 0)"), None);
+        assert_eq!(Define::parse("#ifndef VK_NULL_HANDLE\n#define VK_NULL_HANDLE 0\n#endif"), None);
+    }
+}
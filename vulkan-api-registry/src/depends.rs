@@ -0,0 +1,424 @@
+//! Parsing of `depends` expressions, and resolution of the extensions needed to satisfy them
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use ::{ ApiVersion, Dialect, ExtensionInfo, ExtensionType, ParseError, ParseResult, Registry };
+
+/// A boolean expression over version and extension names, as found in `depends` attributes.
+///
+/// `+` is AND, `,` is OR, and parentheses group sub-expressions. `+` binds tighter than `,`, so
+/// `A+B,C` means `(A+B),C`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Depends {
+    Name(String),
+    All(Vec<Depends>),
+    Any(Vec<Depends>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Name(&'a str),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> ParseResult<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut name_start: Option<usize> = None;
+    for (i, c) in s.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            if name_start.is_none() {
+                name_start = Some(i);
+            }
+            continue;
+        }
+        if let Some(start) = name_start.take() {
+            tokens.push(Token::Name(&s[start..i]));
+        }
+        match c {
+            '+' => tokens.push(Token::And),
+            ',' => tokens.push(Token::Or),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_whitespace() => {},
+            c => {
+                return Err(ParseError::Custom(format!("unexpected character '{}' in depends expression: {}", c, s).into()));
+            },
+        }
+    }
+    if let Some(start) = name_start {
+        tokens.push(Token::Name(&s[start..]));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a, 'b> {
+    tokens: &'b [Token<'a>],
+    position: usize,
+}
+
+impl<'a, 'b> Parser<'a, 'b> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.position)
+    }
+
+    fn bad_expression(&self) -> ParseError {
+        ParseError::Custom(format!("bad depends expression at token {}: {:?}", self.position, self.peek()).into())
+    }
+
+    /// Parses a list of operands separated by `operator`, flattening single operands
+    fn parse_list<F: Fn(&mut Self) -> ParseResult<Depends>, C: Fn(Vec<Depends>) -> Depends>(&mut self, operator: Token<'a>, operand: F, combine: C) -> ParseResult<Depends> {
+        let mut operands = vec![operand(self)?];
+        while self.peek() == Some(&operator) {
+            self.position += 1;
+            operands.push(operand(self)?);
+        }
+        if operands.len() == 1 {
+            Ok(operands.pop().unwrap())
+        } else {
+            Ok(combine(operands))
+        }
+    }
+
+    fn parse_any(&mut self) -> ParseResult<Depends> {
+        self.parse_list(Token::Or, Parser::parse_all, Depends::Any)
+    }
+
+    fn parse_all(&mut self) -> ParseResult<Depends> {
+        self.parse_list(Token::And, Parser::parse_atom, Depends::All)
+    }
+
+    fn parse_atom(&mut self) -> ParseResult<Depends> {
+        match self.peek().cloned() {
+            Some(Token::Name(name)) => {
+                self.position += 1;
+                Ok(Depends::Name(name.into()))
+            },
+            Some(Token::Open) => {
+                self.position += 1;
+                let inner = self.parse_any()?;
+                if self.peek() == Some(&Token::Close) {
+                    self.position += 1;
+                    Ok(inner)
+                } else {
+                    Err(self.bad_expression())
+                }
+            },
+            _ => Err(self.bad_expression()),
+        }
+    }
+}
+
+impl Depends {
+    /// Parses a `depends` expression
+    pub fn parse(s: &str) -> ParseResult<Depends> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let expression = parser.parse_any()?;
+        if parser.position == tokens.len() {
+            Ok(expression)
+        } else {
+            Err(parser.bad_expression())
+        }
+    }
+
+    /// Gets all of the names referenced by this expression
+    pub fn names(&self) -> BTreeSet<&str> {
+        let mut names = BTreeSet::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        match *self {
+            Depends::Name(ref name) => {
+                names.insert(name.as_str());
+            },
+            Depends::All(ref operands) | Depends::Any(ref operands) => {
+                for operand in operands {
+                    operand.collect_names(names);
+                }
+            },
+        }
+    }
+
+    /// Evaluates this expression, using `is_satisfied` to check each name
+    pub fn evaluate<F: Fn(&str) -> bool>(&self, is_satisfied: &F) -> bool {
+        match *self {
+            Depends::Name(ref name) => is_satisfied(name.as_str()),
+            Depends::All(ref operands) => operands.iter().all(|d| d.evaluate(is_satisfied)),
+            Depends::Any(ref operands) => operands.iter().any(|d| d.evaluate(is_satisfied)),
+        }
+    }
+}
+
+impl FromStr for Depends {
+    type Err = ParseError;
+    #[inline(always)]
+    fn from_str(s: &str) -> ParseResult<Depends> {
+        Depends::parse(s)
+    }
+}
+
+impl fmt::Display for Depends {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (operands, separator) = match *self {
+            Depends::Name(ref name) => return f.write_str(name),
+            Depends::All(ref operands) => (operands, "+"),
+            Depends::Any(ref operands) => (operands, ","),
+        };
+        for (i, operand) in operands.iter().enumerate() {
+            if i > 0 {
+                f.write_str(separator)?;
+            }
+            match (self, operand) {
+                // `+` binds tighter than `,`, so an OR inside of an AND needs parentheses
+                (&Depends::All(_), &Depends::Any(_)) => write!(f, "({})", operand)?,
+                _ => write!(f, "{}", operand)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ExtensionInfo {
+    /// Gets the dependencies of this extension as a single expression.
+    ///
+    /// This is the `depends` attribute if there is one. Older registries list dependencies with
+    /// the `requires` and `requiresCore` attributes instead, which are combined with AND. The
+    /// dialect is used to name the feature for `requiresCore`.
+    pub fn dependencies(&self, dialect: &Dialect) -> ParseResult<Option<Depends>> {
+        if let Some(ref depends) = self.depends {
+            return Depends::parse(depends.as_str()).map(Some);
+        }
+        let mut operands: Vec<Depends> = self.requires_core.iter()
+            .map(|v| Depends::Name(format!("{}{}_{}", dialect.version_prefix, v.major, v.minor)))
+            .collect();
+        operands.extend(self.requires.iter().map(|r| Depends::Name(r.clone())));
+        Ok(match operands.len() {
+            0 => None,
+            1 => operands.pop(),
+            _ => Some(Depends::All(operands)),
+        })
+    }
+}
+
+/// Explanation of why a set of extensions can't be enabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// The name isn't an extension or a feature in the registry
+    UnknownName(String),
+    /// The extension is disabled, or not supported by the registry's API
+    Unsupported(String),
+    /// The feature's version is newer than the target version
+    VersionTooLow {
+        feature: String,
+        version: ApiVersion,
+        target: ApiVersion,
+    },
+    /// The extension's dependencies couldn't be parsed
+    BadDependencies {
+        extension: String,
+        message: String,
+    },
+    /// None of the ways to satisfy an extension's dependencies work
+    Unsatisfied {
+        extension: String,
+        depends: Depends,
+        causes: Vec<ResolveError>,
+    },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ResolveError::*;
+        match *self {
+            UnknownName(ref name) => write!(f, "{} is not a known extension or version", name),
+            Unsupported(ref name) => write!(f, "{} is not supported", name),
+            VersionTooLow { ref feature, version, target } => {
+                write!(f, "{} needs version {}, but the target version is {}", feature, version, target)
+            },
+            BadDependencies { ref extension, ref message } => {
+                write!(f, "{} has bad dependencies: {}", extension, message)
+            },
+            Unsatisfied { ref extension, ref depends, ref causes } => {
+                write!(f, "{} depends on {}, which can't be satisfied (", extension, depends)?;
+                for (i, cause) in causes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    write!(f, "{}", cause)?;
+                }
+                f.write_str(")")
+            },
+        }
+    }
+}
+
+/// State for resolving extension dependencies against a target version
+struct Resolver<'a> {
+    registry: &'a Registry,
+    target: ApiVersion,
+}
+
+impl<'a> Resolver<'a> {
+    /// Adds the extensions needed to satisfy `depends` to `enabled` (dependencies first), returning
+    /// the causes of failure if it can't be satisfied
+    fn satisfy(&self, depends: &Depends, enabled: &mut Vec<&'a str>) -> Result<(), Vec<ResolveError>> {
+        match *depends {
+            Depends::Name(ref name) => self.enable(name.as_str(), enabled).map_err(|e| vec![e]),
+            Depends::All(ref operands) => {
+                for operand in operands {
+                    self.satisfy(operand, enabled)?;
+                }
+                Ok(())
+            },
+            Depends::Any(ref operands) => {
+                // Pick whichever alternative needs the fewest extra extensions
+                let mut best: Option<Vec<&'a str>> = None;
+                let mut causes = Vec::new();
+                for operand in operands {
+                    let mut candidate = enabled.clone();
+                    match self.satisfy(operand, &mut candidate) {
+                        Ok(()) => {
+                            if best.as_ref().map(|b| candidate.len() < b.len()).unwrap_or(true) {
+                                best = Some(candidate);
+                            }
+                        },
+                        Err(mut e) => causes.append(&mut e),
+                    }
+                }
+                match best {
+                    Some(best) => {
+                        *enabled = best;
+                        Ok(())
+                    },
+                    None => Err(causes),
+                }
+            },
+        }
+    }
+
+    /// Enables a single extension (or checks a single version) along with its dependencies
+    fn enable(&self, name: &str, enabled: &mut Vec<&'a str>) -> Result<(), ResolveError> {
+        if let Some(feature) = self.registry.features.iter().find(|f| f.name == name) {
+            return if feature.number <= self.target {
+                Ok(())
+            } else {
+                Err(ResolveError::VersionTooLow {
+                    feature: feature.name.clone(),
+                    version: feature.number,
+                    target: self.target,
+                })
+            };
+        }
+        let extension = self.registry.extension(name).ok_or_else(|| ResolveError::UnknownName(name.into()))?;
+        if enabled.contains(&extension.name.as_str()) {
+            return Ok(());
+        }
+        if let ExtensionType::Disabled = extension.ty {
+            return Err(ResolveError::Unsupported(name.into()));
+        }
+        let dependencies = extension.dependencies(&self.registry.dialect).map_err(|e| ResolveError::BadDependencies {
+            extension: name.into(),
+            message: format!("{:?}", e),
+        })?;
+        if let Some(depends) = dependencies {
+            // Placeholder so that dependency cycles terminate. It's moved to after the
+            // dependencies once they've been enabled.
+            enabled.push(extension.name.as_str());
+            let result = self.satisfy(&depends, enabled);
+            enabled.retain(|&e| e != extension.name.as_str());
+            result.map_err(|causes| ResolveError::Unsatisfied {
+                extension: name.into(),
+                depends,
+                causes,
+            })?;
+        }
+        enabled.push(extension.name.as_str());
+        Ok(())
+    }
+}
+
+impl Registry {
+    /// Works out the full list of extensions that need to be enabled to use the `desired`
+    /// extensions on a given API version.
+    ///
+    /// Dependencies that are satisfied by the target version aren't added, and where there are
+    /// alternatives, the one that needs the fewest extra extensions is used. Extensions come
+    /// after the extensions they depend on.
+    pub fn resolve_extensions<'a, It: IntoIterator<Item=&'a str>>(&self, target: ApiVersion, desired: It) -> Result<Vec<String>, ResolveError> {
+        let resolver = Resolver {
+            registry: self,
+            target,
+        };
+        let mut enabled = Vec::new();
+        for name in desired {
+            resolver.enable(name, &mut enabled)?;
+        }
+        Ok(enabled.into_iter().map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Depends;
+    use super::Depends::*;
+
+    fn name(s: &str) -> Depends {
+        Name(s.into())
+    }
+
+    #[test]
+    fn parse_single_name() {
+        assert_eq!(Depends::parse("VK_KHR_surface").unwrap(), name("VK_KHR_surface"));
+    }
+
+    #[test]
+    fn parse_and_binds_tighter_than_or() {
+        let parsed = Depends::parse("VK_KHR_get_physical_device_properties2+VK_KHR_storage_buffer_storage_class,VK_VERSION_1_1").unwrap();
+        assert_eq!(parsed, Any(vec![
+            All(vec![name("VK_KHR_get_physical_device_properties2"), name("VK_KHR_storage_buffer_storage_class")]),
+            name("VK_VERSION_1_1"),
+        ]));
+    }
+
+    #[test]
+    fn parse_parentheses() {
+        let parsed = Depends::parse("(VK_KHR_a,VK_VERSION_1_1)+VK_KHR_b").unwrap();
+        assert_eq!(parsed, All(vec![
+            Any(vec![name("VK_KHR_a"), name("VK_VERSION_1_1")]),
+            name("VK_KHR_b"),
+        ]));
+        assert_eq!(Depends::parse("((VK_KHR_a))").unwrap(), name("VK_KHR_a"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        for s in &["", "VK_KHR_a+", ",VK_KHR_a", "(VK_KHR_a", "VK_KHR_a)", "VK_KHR_a VK_KHR_b", "VK_KHR_a|VK_KHR_b"] {
+            assert!(Depends::parse(s).is_err(), "{:?} should not parse", s);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in &["VK_KHR_a", "VK_KHR_a+VK_KHR_b,VK_VERSION_1_1", "(VK_KHR_a,VK_VERSION_1_1)+VK_KHR_b"] {
+            assert_eq!(&Depends::parse(s).unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn evaluate() {
+        let depends = Depends::parse("VK_KHR_a+VK_KHR_b,VK_VERSION_1_1").unwrap();
+        assert!(depends.evaluate(&|n: &str| n == "VK_VERSION_1_1"));
+        assert!(depends.evaluate(&|n: &str| n == "VK_KHR_a" || n == "VK_KHR_b"));
+        assert!(!depends.evaluate(&|n: &str| n == "VK_KHR_a"));
+        assert_eq!(depends.names().into_iter().collect::<Vec<_>>(), vec!["VK_KHR_a", "VK_KHR_b", "VK_VERSION_1_1"]);
+    }
+}
@@ -0,0 +1,61 @@
+//! The parts of parsing that differ between the Khronos registries that share the vk.xml schema
+
+use ::HandleType;
+
+/// Per-API details needed to parse a registry, like the macros that it uses to define handles.
+///
+/// vk.xml, OpenXR's xr.xml and the other registries in the same schema family only differ in
+/// these details, so custom dialects can be made for registries that aren't covered here.
+#[derive(Debug, Clone)]
+pub struct Dialect {
+    /// Name of the API that the registry is for (i.e. `vulkan`)
+    pub api: &'static str,
+    /// Prefix of the names of the registry's features (i.e. `VK_VERSION_`)
+    pub version_prefix: &'static str,
+    /// Prefix of the API's macros, like the `VK` of `VKAPI_PTR`
+    pub macro_prefix: &'static str,
+    /// Macros used in `<type category="handle">` elements, along with the kind of handle they
+    /// define
+    pub handle_macros: &'static [(&'static str, HandleType)],
+}
+
+impl Dialect {
+    /// The dialect of vk.xml
+    pub fn vulkan() -> Dialect {
+        Dialect {
+            api: "vulkan",
+            version_prefix: "VK_VERSION_",
+            macro_prefix: "VK",
+            handle_macros: &[
+                ("VK_DEFINE_HANDLE", HandleType::Normal),
+                ("VK_DEFINE_NON_DISPATCHABLE_HANDLE", HandleType::NonDispatchable),
+            ],
+        }
+    }
+
+    /// The dialect of OpenXR's xr.xml
+    pub fn openxr() -> Dialect {
+        Dialect {
+            api: "openxr",
+            version_prefix: "XR_VERSION_",
+            macro_prefix: "XR",
+            // OpenXR handles are always 64 bits, and aren't used for dispatch
+            handle_macros: &[
+                ("XR_DEFINE_HANDLE", HandleType::NonDispatchable),
+            ],
+        }
+    }
+
+    /// Gets the kind of handle that a handle macro defines, if it's a known macro
+    pub fn handle_type(&self, handle_macro: &str) -> Option<HandleType> {
+        self.handle_macros.iter()
+            .find(|&&(m, _)| m == handle_macro)
+            .map(|&(_, ty)| ty)
+    }
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect::vulkan()
+    }
+}
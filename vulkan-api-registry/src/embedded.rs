@@ -0,0 +1,8 @@
+//! A pinned copy of the registry, for builds that don't have the Vulkan-Docs submodule checked
+//! out. `registry/update.sh` refreshes it from the Vulkan-Docs tag pinned in `registry/VERSION`.
+
+/// The pinned copy of `vk.xml`
+pub const VK_XML: &str = include_str!("../registry/vk.xml");
+
+/// The pinned copy of `video.xml`, which defines the std codec types used by Vulkan Video
+pub const VIDEO_XML: &str = include_str!("../registry/video.xml");
@@ -0,0 +1,64 @@
+//! Collecting the values of an enum from everywhere that they're defined.
+
+use std::collections::HashSet;
+use ::{ EnumExtensionStrategy, EnumValue, Registry, Requirement };
+
+/// Checks if an enum value is the name of another value, rather than a number
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+}
+
+impl Registry {
+    /// Gets the values of an enum (or the bits of a bitmask), including the ones added by features
+    /// and extensions.
+    ///
+    /// Values come in the order that they're first defined, except that aliases all come after
+    /// the values they could be aliasing. Values that extensions give as an offset are worked out,
+    /// and values that name another value are turned in to `EnumValue::Alias`.
+    pub fn enum_values<'a>(&'a self, name: &str) -> Vec<(&'a str, EnumValue)> {
+        let mut values: Vec<(&'a str, EnumValue)> = Vec::new();
+        let mut names: HashSet<&'a str> = HashSet::new();
+        {
+            let mut push = |value_name: &'a str, value: EnumValue| {
+                let value = match value {
+                    EnumValue::StrValue(ref s) if is_identifier(s) => EnumValue::Alias(s.clone()),
+                    v => v,
+                };
+                if names.insert(value_name) {
+                    values.push((value_name, value));
+                }
+            };
+            for enums in self.enums.iter().filter(|e| e.name.as_ref().map(|n| n.as_str()) == Some(name)) {
+                for &(ref value_name, ref value) in enums.values.iter() {
+                    push(value_name.as_str(), value.clone());
+                }
+            }
+            let feature_requirements = self.features.iter().map(|f| (None, f.requirements()));
+            let extension_requirements = self.extensions.iter().map(|e| (e.number, e.requirements()));
+            for (number, requirements) in feature_requirements.chain(extension_requirements) {
+                for requirement in requirements {
+                    match requirement {
+                        &Requirement::EnumExtension { name: ref value_name, ref extends, ref value } if extends == name => {
+                            let value = match value {
+                                &EnumExtensionStrategy::Value(ref v) => Some(v.clone()),
+                                &EnumExtensionStrategy::Alias(ref alias) => Some(EnumValue::Alias(alias.clone())),
+                                o => o.value(number).map(|v| EnumValue::Value(v as isize)),
+                            };
+                            if let Some(value) = value {
+                                push(value_name.as_str(), value);
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+            }
+        }
+        let (mut ret, aliases): (Vec<_>, Vec<_>) = values.into_iter()
+            .partition(|&(_, ref value)| match value {
+                &EnumValue::Alias(_) => false,
+                _ => true,
+            });
+        ret.extend(aliases);
+        ret
+    }
+}
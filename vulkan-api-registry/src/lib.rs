@@ -3,20 +3,42 @@ extern crate libc;
 extern crate regex;
 extern crate xml;
 
+mod cheader;
+mod constants;
+mod defines;
+mod depends;
+mod dialect;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+mod enums;
 mod iter_util;
-mod xast;
+mod promotion;
+mod providers;
+mod validate;
+mod writer;
+pub mod xast;
 mod xml_iter;
 
+pub use constants::ConstantValue;
+pub use defines::{ BinaryOperator, Define, Expression, UnaryOperator };
+pub use depends::{ Depends, ResolveError };
+pub use dialect::Dialect;
+pub use promotion::Deprecation;
+pub use providers::{ Provider, ProviderIndex };
+pub use validate::ValidationError;
+pub use writer::{ WriteError, WriteResult };
+
 use iter_util::FromNextFn;
 use regex::Regex;
 use std::borrow::Cow;
-use std::collections::{ BTreeSet, HashMap, LinkedList };
+use std::collections::{ BTreeMap, BTreeSet, HashMap, HashSet, LinkedList };
 use std::iter::FromIterator;
-use std::{ fmt };
+use std::path::Path;
+use std::{ fmt, fs, io };
 use xml::attribute::OwnedAttribute;
 use xml::reader::XmlEvent;
 use xml::reader::Result as XmlResult;
-use xml_iter::XmlContents;
+use xml_iter::{ ApiFilter, XmlContents };
 
 #[derive(Debug)]
 pub enum ParseError {
@@ -24,6 +46,7 @@ pub enum ParseError {
     Custom(Cow<'static, str>),
     Regex(regex::Error),
     Xml(xml::reader::Error),
+    Io(io::Error),
     Impossible,
 }
 
@@ -33,10 +56,40 @@ pub trait XmlParse: Sized {
     fn parse<It: Iterator<Item=XmlResult<XmlEvent>>>(events: It, name: String, attributes: Vec<OwnedAttribute>) -> ParseResult<Self>;
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct TyperefInfo {
     pub ty: String,
     pub constness: Vec<bool>,
+    /// Sizes of each array dimension (i.e. `["3", "4"]` for `float matrix[3][4]`). Sizes can be
+    /// the names of API constants.
+    pub array: Vec<String>,
+}
+
+/// Gets the text of a member or parameter that comes after its name (i.e. `[3][4]` or `:24`),
+/// including the contents of any `<enum>` used as an array size
+fn declarator_suffix(node: &xast::Node) -> String {
+    let mut suffix = String::new();
+    let after_name = node.contents.iter().skip_while(|c| match c {
+        &xast::Content::Child(ref child) => child.name != "name",
+        _ => true,
+    }).skip(1);
+    for content in after_name {
+        match content {
+            &xast::Content::Text(ref s) => suffix.push_str(s.as_str()),
+            &xast::Content::Child(ref child) if child.name == "enum" => suffix.push_str(child.concat_text().as_str()),
+            _ => {},
+        }
+    }
+    suffix
+}
+
+fn parse_array_sizes(suffix: &str) -> Vec<String> {
+    lazy_static! {
+        static ref ARRAY_SIZE_PATTERN: Regex = Regex::new(r"\[\s*([^\]\s]+)\s*\]").unwrap();
+    }
+    ARRAY_SIZE_PATTERN.captures_iter(suffix)
+        .map(|caps| String::from(&caps[1]))
+        .collect()
 }
 
 fn parse_constness<C: FromIterator<bool>>(ptr_info: &str) -> C {
@@ -65,6 +118,7 @@ impl TyperefInfo {
         Ok(TyperefInfo {
             ty: ty.into(),
             constness: ty_constness,
+            array: parse_array_sizes(declarator_suffix(node).as_str()),
         })
     }
 }
@@ -85,15 +139,54 @@ impl fmt::Display for TyperefInfo {
 
 impl fmt::Debug for TyperefInfo {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "\"{}\"", self)
+        try!(write!(f, "\"{}\"", self));
+        for size in self.array.iter() {
+            try!(write!(f, "[{}]", size));
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum EnumValue {
     Bitpos(u8),
     Value(isize),
     StrValue(String),
+    Alias(String),
+}
+
+impl EnumValue {
+    /// Parses the value of an `<enum>` element from its `value`, `bitpos`, or `alias` attribute
+    pub fn parse_node(node: &xast::Node) -> Option<ParseResult<EnumValue>> {
+        if let Some(v) = node.get_attribute("value") {
+            Some(Ok(v.parse::<isize>()
+                .map(EnumValue::Value)
+                .unwrap_or_else(|_| EnumValue::StrValue(v.into()))))
+        } else if let Some(bp) = node.get_attribute("bitpos") {
+            Some(bp.parse::<u8>()
+                .map(EnumValue::Bitpos)
+                .map_err(|_| ParseError::Custom("bitpos didn't parse".into())))
+        } else {
+            node.get_attribute("alias").map(|alias| Ok(EnumValue::Alias(alias.into())))
+        }
+    }
+
+    /// Gets the integer value of this enumerant, if it has one (bit positions are turned in to
+    /// their mask value)
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            &EnumValue::Bitpos(bp) => Some(1i64.wrapping_shl(bp as u32)),
+            &EnumValue::Value(v) => Some(v as i64),
+            &EnumValue::StrValue(ref s) => {
+                if s.starts_with("0x") || s.starts_with("0X") {
+                    u64::from_str_radix(&s[2..], 16).ok().map(|v| v as i64)
+                } else {
+                    s.parse::<i64>().ok()
+                }
+            },
+            &EnumValue::Alias(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -102,7 +195,7 @@ pub enum HandleType {
     NonDispatchable,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StructMember {
     pub name: String,
     pub ty: TyperefInfo,
@@ -112,6 +205,8 @@ pub struct StructMember {
     pub altlen: Option<String>,
     pub optional: bool,
     pub noautovalidity: bool,
+    /// Width in bits, if this member is a bitfield
+    pub bitfield: Option<u8>,
 }
 
 impl StructMember {
@@ -131,6 +226,13 @@ impl StructMember {
             .and_then(|s| s.parse::<bool>().ok())
             .unwrap_or(false);
         let ty = TyperefInfo::parse_node(&node);
+        lazy_static! {
+            static ref BITFIELD_PATTERN: Regex = Regex::new(r"^\s*:\s*(\d+)").unwrap();
+        }
+        let bitfield = match BITFIELD_PATTERN.captures(declarator_suffix(node).as_str()) {
+            Some(caps) => Some(try!(caps[1].parse::<u8>().map_err(|_| ParseError::Custom("bitfield width didn't parse".into())))),
+            None => None,
+        };
         Ok(StructMember {
             name: try!(name.map(Into::into)),
             ty: try!(ty),
@@ -140,11 +242,12 @@ impl StructMember {
             externsync: externsync,
             optional: optional,
             noautovalidity: noautovalidity,
+            bitfield: bitfield,
         })
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TypeInfo {
     Basetype {
         name: String,
@@ -155,7 +258,11 @@ pub enum TypeInfo {
         ty: String,
         requires: LinkedList<String>,
     },
-    Define(String), // TODO: unused rn for rust
+    Define {
+        name: String,
+        /// All of the define's text, including comments and any preprocessor conditionals
+        text: String,
+    },
     Enum {
         name: String,
         values: LinkedList<(String, EnumValue)>,
@@ -166,9 +273,8 @@ pub enum TypeInfo {
     },
     Funcpointer {
         name: String,
-        // TODO: bring these back
-        //arguments: LinkedList<(String, TyperefInfo)>,
-        //return_type: TyperefInfo,
+        arguments: LinkedList<(String, TyperefInfo)>,
+        return_type: TyperefInfo,
     },
     Group, // TODO: unused rn
     Handle {
@@ -186,6 +292,10 @@ pub enum TypeInfo {
         members: LinkedList<StructMember>,
     },
     Include(String),
+    Alias {
+        name: String,
+        alias: String,
+    },
     Uncategorized {
         name: String,
         node: xast::Node,
@@ -208,19 +318,25 @@ impl EnumsType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct EnumsInfo {
     pub name: Option<String>,
     pub ty: Option<EnumsType>,
     pub vendor: Option<String>,
+    pub values: LinkedList<(String, EnumValue)>,
+    /// C types of the values that declare one with a `type` attribute (only API constants do)
+    pub value_types: BTreeMap<String, String>,
 }
 
 impl XmlParse for EnumsInfo {
-    fn parse<It: Iterator<Item=XmlResult<XmlEvent>>>(events: It, _name: String, attributes: Vec<OwnedAttribute>) -> ParseResult<EnumsInfo> {
+    fn parse<It: Iterator<Item=XmlResult<XmlEvent>>>(events: It, name: String, attributes: Vec<OwnedAttribute>) -> ParseResult<EnumsInfo> {
+        let node: xast::Node = try!(XmlParse::parse(events, name, attributes.clone()));
         let mut info = EnumsInfo {
             name: None,
             ty: None,
             vendor: None,
+            values: LinkedList::new(),
+            value_types: BTreeMap::new(),
         };
         for OwnedAttribute { name, value, .. } in attributes {
             match name.local_name.as_str() {
@@ -236,37 +352,86 @@ impl XmlParse for EnumsInfo {
                 _ => {},
             }
         }
-        for _ in XmlContents::new_inside(events) {}
+        for enum_node in node.children_named("enum") {
+            let name = try! {
+                enum_node.get_attribute("name")
+                    .map(|s| Ok(s))
+                    .unwrap_or(Err(ParseError::Custom("enum did not have a name".into())))
+            };
+            let value = try! {
+                EnumValue::parse_node(enum_node)
+                    .unwrap_or_else(|| Err(ParseError::Custom(format!("enum {} did not have a value", name).into())))
+            };
+            if let Some(ty) = enum_node.get_attribute("type") {
+                info.value_types.insert(name.into(), ty.into());
+            }
+            info.values.push_back((name.into(), value));
+        }
         Ok(info)
     }
 }
 
 fn parse_members(node: &xast::Node) -> ParseResult<LinkedList<StructMember>> {
-    node.contents.iter().filter_map(|c| match c {
-        &xast::Content::Child(ref child) => {
-            if child.name == "member" {
-                Some(child)
-            } else {
-                None
-            }
-        },
-        _ => None
-    }).map(StructMember::parse_node).fold(Ok(LinkedList::new()), |l, m| l.and_then(move |mut l| {
+    node.children_named("member").map(StructMember::parse_node).fold(Ok(LinkedList::new()), |l, m| l.and_then(move |mut l| {
         let m = try!(m);
         l.push_back(m);
         Ok(l)
     }))
 }
 
+/// Parses a C declaration like `const char* const*` or `const char* pName` into a type reference,
+/// after the name (if any) has been removed
+fn parse_c_typeref(declaration: &str) -> Option<TyperefInfo> {
+    lazy_static! {
+        static ref IDENTIFIER_PATTERN: Regex = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap();
+    }
+    IDENTIFIER_PATTERN.find_iter(declaration)
+        .filter(|m| m.as_str() != "const" && m.as_str() != "struct")
+        .last()
+        .map(|ty| TyperefInfo {
+            ty: ty.as_str().into(),
+            constness: parse_constness(format!("{} {}", &declaration[..ty.start()], &declaration[ty.end()..]).as_str()),
+            array: Vec::new(),
+        })
+}
+
 fn parse_funcpointer(node: &xast::Node) -> ParseResult<TypeInfo> {
-    // TODO: Finish this by actually rendering all of the funcpointers
-    let name = try! {
-        node.get_attribute_or_child("name")
-            .map(|s| Ok(s))
-            .unwrap_or(Err(ParseError::Custom("funcpointer did not have a name".into())))
+    lazy_static! {
+        // i.e. `typedef void* (VKAPI_PTR *PFN_vkAllocationFunction)(void* pUserData, size_t size);`
+        static ref FUNCPOINTER_PATTERN: Regex = Regex::new(r"(?s)^\s*typedef\s+(.+?)\s*\(\s*\w*\s*\*\s*(\w+)\s*\)\s*\((.*)\)\s*;\s*$").unwrap();
+        static ref ARGUMENT_NAME_PATTERN: Regex = Regex::new(r"(\w+)\s*$").unwrap();
+    }
+    let text = node.text();
+    let caps = try! {
+        FUNCPOINTER_PATTERN.captures(text.as_str())
+            .map(|c| Ok(c))
+            .unwrap_or_else(|| Err(ParseError::Custom(format!("funcpointer didn't parse: {}", &text).into())))
     };
+    let return_type = try! {
+        parse_c_typeref(&caps[1])
+            .map(|ty| Ok(ty))
+            .unwrap_or_else(|| Err(ParseError::Custom(format!("funcpointer {} had no return type", &caps[2]).into())))
+    };
+    let mut arguments = LinkedList::new();
+    let argument_list = caps[3].trim();
+    if argument_list != "void" && argument_list.len() > 0 {
+        for argument in argument_list.split(",") {
+            let parsed = ARGUMENT_NAME_PATTERN.captures(argument).and_then(|name| {
+                let declaration = &argument[..name.get(1).unwrap().start()];
+                parse_c_typeref(declaration).map(|ty| (String::from(&name[1]), ty))
+            });
+            match parsed {
+                Some(argument) => arguments.push_back(argument),
+                None => {
+                    return Err(ParseError::Custom(format!("funcpointer {} had a bad argument: {}", &caps[2], argument).into()));
+                },
+            }
+        }
+    }
     Ok(TypeInfo::Funcpointer {
-        name: name.into(),
+        name: caps[2].into(),
+        arguments: arguments,
+        return_type: return_type,
     })
 }
 
@@ -276,7 +441,7 @@ impl TypeInfo {
         let s = match self {
             &Basetype { ref name, .. } => name,
             &Bitmask { ref name, .. } => name,
-            &Define(ref name) => name,
+            &Define { ref name, .. } => name,
             &Enum { ref name, .. } => name,
             &Constant { ref name, .. } => name,
             &Funcpointer { ref name, .. } => name,
@@ -285,17 +450,24 @@ impl TypeInfo {
             &Struct { ref name, .. } => name,
             &Union { ref name, .. } => name,
             &Include(ref name) => name,
+            &Alias { ref name, .. } => name,
             &Uncategorized { ref name, .. } => name,
         };
         s.as_str()
     }
-    pub fn parse_type<It: Iterator<Item=XmlResult<XmlEvent>>>(events: It, name: String, attributes: Vec<OwnedAttribute>) -> ParseResult<TypeInfo> {
+    pub fn parse_type<It: Iterator<Item=XmlResult<XmlEvent>>>(events: It, name: String, attributes: Vec<OwnedAttribute>, dialect: &Dialect) -> ParseResult<TypeInfo> {
         let node: xast::Node = try!(XmlParse::parse(events, name, attributes));
         let name = try! {
             node.get_attribute_or_child("name")
                 .map(|s| Ok(s))
                 .unwrap_or_else(|| Err(ParseError::Custom("Type did not have a name".into())))
         };
+        if let Some(alias) = node.get_attribute("alias") {
+            return Ok(TypeInfo::Alias {
+                name: name.into(),
+                alias: alias.into(),
+            });
+        }
         let info = match node.get_attribute("category") {
             Some("basetype") => TypeInfo::Basetype {
                 name: name.into(),
@@ -313,10 +485,16 @@ impl TypeInfo {
                             .map(|ty| Ok(ty.into()))
                             .unwrap_or(Err(ParseError::Custom("bitmask did not have a type tag".into())))
                     },
-                    requires: csv_attribute(&node, "requires"),
+                    // 64-bit bitmasks name their bits with `bitvalues` rather than `requires`
+                    requires: node.get_attribute("bitvalues")
+                        .map(|s| s.split(",").map(Into::into).collect())
+                        .unwrap_or_else(|| csv_attribute(&node, "requires")),
                 }
             }
-            Some("define") => TypeInfo::Define(name.into()),
+            Some("define") => TypeInfo::Define {
+                name: name.into(),
+                text: node.text(),
+            },
             Some("enum") => TypeInfo::Enum {
                 name: name.into(),
                 values: LinkedList::new()
@@ -326,7 +504,7 @@ impl TypeInfo {
                 TypeInfo::Struct {
                     name: name.into(),
                     members: try!(members),
-                    extends: LinkedList::new(),
+                    extends: csv_attribute(&node, "structextends"),
                 }
             },
             Some("union") => {
@@ -343,12 +521,10 @@ impl TypeInfo {
                     node.get_child("type")
                         .map(|c| Ok(c.concat_text()))
                         .unwrap_or_else(|| Err(ParseError::Custom("No type tag for handle".into())))
-                        .map(|ty| {
-                            if ty == "VK_DEFINE_HANDLE" {
-                                HandleType::Normal
-                            } else {
-                                HandleType::NonDispatchable
-                            }
+                        .and_then(|ty| {
+                            dialect.handle_type(ty.as_str())
+                                .map(|ty| Ok(ty))
+                                .unwrap_or_else(|| Err(ParseError::Custom(format!("Unknown handle macro: {}", ty).into())))
                         })
                 };
                 TypeInfo::Handle {
@@ -368,7 +544,7 @@ impl TypeInfo {
         };
         Ok(info)
     }
-    pub fn parse_next_type<It: Iterator<Item=XmlResult<XmlEvent>>>(mut events: It) -> Option<ParseResult<TypeInfo>> {
+    pub fn parse_next_type<It: Iterator<Item=XmlResult<XmlEvent>>>(mut events: It, dialect: &Dialect) -> Option<ParseResult<TypeInfo>> {
         // TODO: this should be implementable with tail call recursion
         let next_event = {
             let mut events = events.by_ref().skip_while(|evt| match evt {
@@ -382,7 +558,7 @@ impl TypeInfo {
             events.next()
         };
         next_event.map(|r| r.map_err(|e| ParseError::Xml(e)).and_then(|evt| match evt {
-            XmlEvent::StartElement { name, attributes, .. } => TypeInfo::parse_type(&mut events, name.local_name, attributes),
+            XmlEvent::StartElement { name, attributes, .. } => TypeInfo::parse_type(&mut events, name.local_name, attributes, dialect),
             _ => unreachable!(),
         }))
     }
@@ -408,11 +584,14 @@ pub enum CommandPipelineType {
     Graphics,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParameterInfo {
     pub name: String,
     pub ty: TyperefInfo,
     pub optional: bool,
+    /// Length of the array that this parameter points to (i.e. the name of another parameter, or
+    /// `null-terminated`)
+    pub len: Option<String>,
 }
 
 impl ParameterInfo {
@@ -428,11 +607,12 @@ impl ParameterInfo {
             name: try!(name).into(),
             ty: try!(ty),
             optional: optional,
+            len: node.get_attribute("len").map(Into::into),
         })
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CommandInfo {
     pub name: String,
     pub queues: LinkedList<String>,
@@ -443,6 +623,7 @@ pub struct CommandInfo {
     pub pipeline: Option<CommandPipelineType>,
     pub return_type: TyperefInfo,
     pub params: LinkedList<ParameterInfo>,
+    pub alias: Option<String>,
 }
 
 impl CommandInfo {
@@ -474,6 +655,31 @@ fn csv_attribute<C: FromIterator<String> + Default>(node: &xast::Node, attribute
 impl XmlParse for CommandInfo {
     fn parse<It: Iterator<Item=XmlResult<XmlEvent>>>(events: It, name: String, attributes: Vec<OwnedAttribute>) -> ParseResult<CommandInfo> {
         let node: xast::Node = try!(XmlParse::parse(events, name, attributes));
+        if let Some(alias) = node.get_attribute("alias") {
+            // Aliases only carry a name, so the rest gets filled in from the aliased command once
+            // the whole registry has been read
+            let name = try! {
+                node.get_attribute("name")
+                    .map(|s| Ok(s))
+                    .unwrap_or(Err(ParseError::Custom("command alias did not have a name".into())))
+            };
+            return Ok(CommandInfo {
+                name: name.into(),
+                queues: LinkedList::new(),
+                successcodes: LinkedList::new(),
+                errorcodes: LinkedList::new(),
+                renderpass: None,
+                cmdbufferlevel: None,
+                pipeline: None,
+                return_type: TyperefInfo {
+                    ty: String::new(),
+                    constness: Vec::new(),
+                    array: Vec::new(),
+                },
+                params: LinkedList::new(),
+                alias: Some(alias.into()),
+            });
+        }
         let proto = try! {
             node.get_child("proto")
                 .map(|n| Ok(n))
@@ -485,12 +691,7 @@ impl XmlParse for CommandInfo {
                 .map(|s| Ok(s))
                 .unwrap_or(Err(ParseError::Custom("command did not have a name".into())))
         };
-        let params = node.contents.iter().filter_map(|e| match e {
-            &xast::Content::Child(ref c) if c.name == "param" => {
-                Some(c)
-            },
-            _ => None,
-        }).map(ParameterInfo::parse_node).fold(Ok(LinkedList::new()), |l, p| l.and_then(move |mut l| {
+        let params = node.children_named("param").map(ParameterInfo::parse_node).fold(Ok(LinkedList::new()), |l, p| l.and_then(move |mut l| {
             let p = try!(p);
             l.push_back(p);
             Ok(l)
@@ -546,20 +747,51 @@ impl XmlParse for CommandInfo {
             pipeline: pipeline,
             return_type: return_type,
             params: try!(params),
+            alias: None,
         })
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum EnumExtensionStrategy {
     Value(EnumValue),
+    Alias(String),
     Offset {
         offset: isize,
         negated: bool,
+        extnumber: Option<isize>,
     },
 }
 
-#[derive(Debug, Clone)]
+/// Base value for enumerants added by extensions
+const EXTENSION_ENUM_BASE: i64 = 1000000000;
+
+/// Size of the block of enumerant values reserved for each extension
+const EXTENSION_ENUM_BLOCK_SIZE: i64 = 1000;
+
+impl EnumExtensionStrategy {
+    /// Computes the integer value of an enumerant added by the extension with the given number.
+    ///
+    /// Returns `None` for aliases and values that aren't integer literals.
+    pub fn value(&self, extension_number: Option<isize>) -> Option<i64> {
+        match self {
+            &EnumExtensionStrategy::Value(ref v) => v.as_integer(),
+            &EnumExtensionStrategy::Alias(_) => None,
+            &EnumExtensionStrategy::Offset { offset, negated, extnumber } => {
+                extnumber.or(extension_number).map(|number| {
+                    let value = EXTENSION_ENUM_BASE + (number as i64 - 1) * EXTENSION_ENUM_BLOCK_SIZE + offset as i64;
+                    if negated {
+                        -value
+                    } else {
+                        value
+                    }
+                })
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Requirement {
     Type(String),
     Command(String),
@@ -596,12 +828,14 @@ impl Requirement {
                                 .map(|offset| EnumExtensionStrategy::Offset {
                                     offset: offset,
                                     negated: get_attribute("dir").ok().map(|s| s.as_str()) == Some("-"),
+                                    extnumber: get_attribute("extnumber").ok().and_then(|n| n.parse::<isize>().ok()),
                                 });
                             let value_strategy = get_attribute("value").map(|v| EnumExtensionStrategy::Value(EnumValue::StrValue(v.clone())));
                             let bitpos_strategy = get_attribute("bitpos")
                                 .and_then(|bp| bp.parse::<u8>().map_err(|_| ParseError::Custom("bitpos didn't parse".into())))
                                 .map(|bp| EnumExtensionStrategy::Value(EnumValue::Bitpos(bp)));
-                            offset_strategy.or(value_strategy).or(bitpos_strategy)
+                            let alias_strategy = get_attribute("alias").map(|a| EnumExtensionStrategy::Alias(a.clone()));
+                            offset_strategy.or(value_strategy).or(bitpos_strategy).or(alias_strategy)
                                 .map(|strategy| Requirement::EnumExtension {
                                     name: name.clone(),
                                     extends: extends.clone(),
@@ -615,7 +849,8 @@ impl Requirement {
                                         .and_then(|bp| bp.parse::<u8>().map_err(|_| ParseError::Custom("bitpos didn't parse".into())))
                                         .map(|bp| EnumValue::Bitpos(bp))
                                         .ok();
-                                    value.or(bitpos)
+                                    let alias = get_attribute("alias").map(|a| EnumValue::Alias(a.clone())).ok();
+                                    value.or(bitpos).or(alias)
                                 };
                                 if let Some(value) = value {
                                     Ok(Requirement::Constant {
@@ -639,15 +874,117 @@ impl Requirement {
     }
 }
 
-#[derive(Debug, Clone)]
+/// A `<require>` block of a feature or extension
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequireBlock {
+    /// Condition under which the requirements apply (i.e. `VK_VERSION_1_1+VK_KHR_surface`), if any
+    pub depends: Option<String>,
+    pub requirements: LinkedList<Requirement>,
+}
+
+impl RequireBlock {
+    pub fn parse_next_require_block<It: Iterator<Item=XmlResult<XmlEvent>>>(mut events: It) -> Option<ParseResult<RequireBlock>> {
+        let next_event = {
+            let mut events = events.by_ref().skip_while(|evt| match evt {
+                &Err(_) => false,
+                &Ok(XmlEvent::StartElement { ref name, .. }) => name.local_name != "require",
+                &Ok(_) => true,
+            });
+            events.next()
+        };
+        next_event.map(|r| r.map_err(|e| ParseError::Xml(e)).and_then(|evt| match evt {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let node = xast::Node {
+                    name: name.local_name,
+                    attributes: attributes,
+                    contents: LinkedList::new(),
+                };
+                // Older registries spell out the dependency with separate `feature` and
+                // `extension` attributes instead of a `depends` expression
+                let depends = node.get_attribute("depends").map(String::from).or_else(|| {
+                    let conditions: Vec<&str> = node.get_attribute("feature").into_iter()
+                        .chain(node.get_attribute("extension"))
+                        .collect();
+                    if conditions.len() > 0 {
+                        Some(conditions.join("+"))
+                    } else {
+                        None
+                    }
+                });
+                let mut events = XmlContents::new_inside(events);
+                let requirements = FromNextFn::new(|| Requirement::parse_next_requirement(&mut events))
+                    .fold(Ok(LinkedList::new()), |l, r| l.and_then(move |mut l| {
+                        let r = try!(r);
+                        l.push_back(r);
+                        Ok(l)
+                    }));
+                Ok(RequireBlock {
+                    depends: depends,
+                    requirements: try!(requirements),
+                })
+            },
+            _ => unreachable!(),
+        }))
+    }
+}
+
+fn parse_require_blocks<It: Iterator<Item=XmlResult<XmlEvent>>>(mut events: It) -> ParseResult<LinkedList<RequireBlock>> {
+    FromNextFn::new(|| RequireBlock::parse_next_require_block(&mut events))
+        .fold(Ok(LinkedList::new()), |l, b| l.and_then(move |mut l| {
+            let b = try!(b);
+            l.push_back(b);
+            Ok(l)
+        }))
+}
+
+/// Version number of an API (i.e. `1.0`), as found on features
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl ApiVersion {
+    #[inline]
+    pub fn new(major: u32, minor: u32) -> ApiVersion {
+        ApiVersion {
+            major: major,
+            minor: minor,
+        }
+    }
+}
+
+impl std::str::FromStr for ApiVersion {
+    type Err = ParseError;
+    fn from_str(s: &str) -> ParseResult<ApiVersion> {
+        let mut parts = s.splitn(2, ".").map(|p| p.parse::<u32>());
+        match (parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => Ok(ApiVersion::new(major, minor)),
+            _ => Err(ParseError::Custom(format!("bad version number: {}", s).into())),
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct FeatureInfo {
     pub name: String,
     pub api: String,
-    pub number: String,
-    pub requirements: LinkedList<Requirement>,
+    pub number: ApiVersion,
+    pub require_blocks: LinkedList<RequireBlock>,
 }
 
 impl FeatureInfo {
+    /// Iterates over the requirements of all of this feature's `<require>` blocks
+    pub fn requirements<'a>(&'a self) -> Box<Iterator<Item=&'a Requirement> + 'a> {
+        Box::new(self.require_blocks.iter().flat_map(|block| block.requirements.iter()))
+    }
+
     pub fn parse_next_feature<It: Iterator<Item=XmlResult<XmlEvent>>>(mut events: It) -> Option<ParseResult<FeatureInfo>> {
         // This should have been implementable by tail call recursion
         let next_event = {
@@ -670,20 +1007,12 @@ impl FeatureInfo {
                         .map(|s| Ok(String::from(s)))
                         .unwrap_or_else(|| Err(ParseError::Custom(format!("feature didn't have attribute: {}", name).into())))
                 };
-                let mut events = XmlContents::new_inside(events);
-                let features = FromNextFn::new(|| Requirement::parse_next_requirement(&mut events))
-                    .fold(Ok(LinkedList::new()), |l, f| {
-                        let f = try!(f);
-                        l.map(move |mut l| {
-                            l.push_back(f);
-                            l
-                        })
-                    });
+                let require_blocks = parse_require_blocks(XmlContents::new_inside(events));
                 Ok(FeatureInfo {
                     name: try!(manditory_attribute("name")),
                     api: try!(manditory_attribute("api")),
-                    number: try!(manditory_attribute("number")),
-                    requirements: try!(features),
+                    number: try!(manditory_attribute("number").and_then(|n| n.parse())),
+                    require_blocks: try!(require_blocks),
                 })
             },
             _ => unreachable!(),
@@ -702,25 +1031,64 @@ pub enum ExtensionType {
     },
 }
 
+/// Extension types are equal if their `supported` patterns are spelled the same way
+impl PartialEq for ExtensionType {
+    fn eq(&self, other: &ExtensionType) -> bool {
+        match (self, other) {
+            (&ExtensionType::Disabled, &ExtensionType::Disabled) => true,
+            (&ExtensionType::Device { supported: ref a }, &ExtensionType::Device { supported: ref b }) => a.as_str() == b.as_str(),
+            (&ExtensionType::Instance { supported: ref a }, &ExtensionType::Instance { supported: ref b }) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
 impl Default for ExtensionType {
     fn default() -> ExtensionType {
         ExtensionType::Disabled
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExtensionInfo {
     pub name: String,
-    pub number: isize,
+    /// Registered extension number. The std codec extensions in `video.xml` don't have one.
+    pub number: Option<isize>,
     pub author: Option<String>,
     pub contact: Option<String>,
     pub ty: ExtensionType,
     pub requires: LinkedList<String>, // optional. empty if not present
+    /// Minimum core version needed by this extension, from older registries
+    pub requires_core: Option<ApiVersion>,
+    /// Boolean expression of the versions and extensions this extension depends on
+    pub depends: Option<String>,
     pub protect: Option<String>,
-    pub requirements: LinkedList<Requirement>,
+    pub platform: Option<String>,
+    /// APIs that support this extension (i.e. `vulkan`, or `disabled`)
+    pub supported: LinkedList<String>,
+    /// APIs that have ratified this extension
+    pub ratified: LinkedList<String>,
+    pub promotedto: Option<String>,
+    /// Extension that replaces this one. Empty if this extension is deprecated with no replacement.
+    pub deprecatedby: Option<String>,
+    /// Extension that replaces this one. Empty if this extension is obsoleted with no replacement.
+    pub obsoletedby: Option<String>,
+    pub provisional: bool,
+    pub specialuse: LinkedList<String>,
+    pub require_blocks: LinkedList<RequireBlock>,
 }
 
 impl ExtensionInfo {
+    /// Iterates over the requirements of all of this extension's `<require>` blocks
+    pub fn requirements<'a>(&'a self) -> Box<Iterator<Item=&'a Requirement> + 'a> {
+        Box::new(self.require_blocks.iter().flat_map(|block| block.requirements.iter()))
+    }
+
+    /// Checks if this extension is supported by the given API (i.e. `vulkan` or `vulkansc`)
+    pub fn is_supported_by(&self, api: &str) -> bool {
+        self.supported.iter().any(|s| s == api)
+    }
+
     pub fn parse_next_extension<It: Iterator<Item=XmlResult<XmlEvent>>>(mut events: It) -> Option<ParseResult<ExtensionInfo>> {
         // TODO: this should be implementable with tail-call recursion
         let next_event = {
@@ -745,9 +1113,7 @@ impl ExtensionInfo {
                         .unwrap_or_else(|| Err(ParseError::Custom(format!("feature didn't have attribute: {}", name).into())))
                 };
                 let number = node.get_attribute("number")
-                    .map(|n| Ok(n))
-                    .unwrap_or(Err(ParseError::Custom("no extension number found".into())))
-                    .and_then(|n| n.parse::<isize>().map_err(|_| ParseError::Custom("extension number couldn't be parsed".into())));
+                    .map(|n| n.parse::<isize>().map_err(|_| ParseError::Custom("extension number couldn't be parsed".into())));
                 let ty: ParseResult<ExtensionType> = node.get_attribute("type").map(|ty| {
                     manditory_attribute("supported")
                         .and_then(|ref pat| Regex::new(pat.as_str()).map_err(|e| ParseError::Regex(e)))
@@ -764,21 +1130,33 @@ impl ExtensionInfo {
                 let requires: LinkedList<String> = node.get_attribute("requires")
                     .map(|s| s.split(",").map(Into::into).collect())
                     .unwrap_or_else(|| LinkedList::new());
-                let requirements = FromNextFn::new(|| Requirement::parse_next_requirement(&mut events))
-                    .fold(Ok(LinkedList::new()), |l, r| l.and_then(move |mut l| {
-                        let r = try!(r);
-                        l.push_back(r);
-                        Ok(l)
-                    }));
+                let requires_core = node.get_attribute("requiresCore").map(|v| v.parse::<ApiVersion>());
+                let require_blocks = parse_require_blocks(&mut events);
                 Ok(ExtensionInfo {
                     name: try!(manditory_attribute("name")),
-                    number: try!(number),
+                    number: match number {
+                        Some(n) => Some(try!(n)),
+                        None => None,
+                    },
                     author: manditory_attribute("author").ok(),
                     contact: manditory_attribute("contact").ok(),
                     ty: try!(ty),
                     requires: requires,
+                    requires_core: match requires_core {
+                        Some(v) => Some(try!(v)),
+                        None => None,
+                    },
+                    depends: manditory_attribute("depends").ok(),
                     protect: manditory_attribute("protect").ok(),
-                    requirements: try!(requirements),
+                    platform: manditory_attribute("platform").ok(),
+                    supported: csv_attribute(&node, "supported"),
+                    ratified: csv_attribute(&node, "ratified"),
+                    promotedto: manditory_attribute("promotedto").ok(),
+                    deprecatedby: manditory_attribute("deprecatedby").ok(),
+                    obsoletedby: manditory_attribute("obsoletedby").ok(),
+                    provisional: node.get_attribute("provisional") == Some("true"),
+                    specialuse: csv_attribute(&node, "specialuse"),
+                    require_blocks: try!(require_blocks),
                 })
             },
             _ => unreachable!(),
@@ -786,6 +1164,21 @@ impl ExtensionInfo {
     }
 }
 
+/// Follows a chain of command aliases until it reaches a command that has its own prototype
+fn resolve_command_alias<'a>(commands: &'a HashMap<String, CommandInfo>, name: &str) -> Option<&'a CommandInfo> {
+    let mut cmd = commands.get(name);
+    // Bound the number of hops so that a cycle can't loop forever
+    for _ in 0..commands.len() {
+        match cmd {
+            Some(&CommandInfo { alias: Some(ref alias), .. }) => {
+                cmd = commands.get(alias.as_str());
+            },
+            _ => return cmd,
+        }
+    }
+    None
+}
+
 #[derive(Debug)]
 pub struct Registry {
     pub types: HashMap<String, TypeInfo>,
@@ -793,90 +1186,309 @@ pub struct Registry {
     pub features: LinkedList<FeatureInfo>,
     pub extensions: LinkedList<ExtensionInfo>,
     pub enums: LinkedList<EnumsInfo>,
+    pub dialect: Dialect,
 }
 
-impl Registry {
-    pub fn new<It: Iterator<Item=XmlResult<XmlEvent>>, FeatureF: FnMut(&str) -> bool, ExtensionF: FnMut(&str) -> bool>(mut events: It, mut should_include_feature: FeatureF, mut should_include_extension: ExtensionF) -> ParseResult<Registry> {
-        let mut types: Option<HashMap<String, TypeInfo>> = None;
-        let mut commands: HashMap<String, CommandInfo> = HashMap::new();
-        let mut features: LinkedList<FeatureInfo> = LinkedList::new();
-        let mut extensions: LinkedList<ExtensionInfo> = LinkedList::new();
-        let mut enums: LinkedList<EnumsInfo> = LinkedList::new();
-
-        {
-            loop {
-                let next_event = events.next();
-                match next_event {
-                    Some(Ok(XmlEvent::StartElement { name, attributes, namespace })) => {
-                        match name.local_name.as_str() {
-                            "types" => {
-                                let mut events = XmlContents::new_inside(&mut events);
-                                let new_types = try!(FromNextFn::new(|| TypeInfo::parse_next_type(&mut events))
-                                    .fold(Ok(HashMap::new()), |r, t| r.and_then(move |mut m| {
-                                        let t = try!(t);
-                                        m.insert(t.name().into(), t);
-                                        Ok(m)
-                                    })));
-                                types = Some(new_types);
-                            },
-                            "feature" => {
-                                let start_event = XmlEvent::StartElement {
-                                    name: name,
-                                    attributes: attributes,
-                                    namespace: namespace,
-                                };
-                                let mut events = std::iter::once(Ok(start_event))
-                                    .chain(&mut events);
-                                let feature = FeatureInfo::parse_next_feature(&mut events)
-                                    .unwrap_or(Err(ParseError::Impossible));
-                                features.push_back(try!(feature));
-                            },
-                            "extensions" => {
-                                let mut events = XmlContents::new_inside(&mut events);
-                                let new_extensions = try! {
-                                    FromNextFn::new(|| ExtensionInfo::parse_next_extension(&mut events))
-                                        .fold(Ok(LinkedList::new()), |l, e| l.and_then(move |mut l| {
-                                            let e = try!(e);
-                                            l.push_back(e);
-                                            Ok(l)
-                                        }))
-                                };
-                                let mut append_extensions = |mut l: LinkedList<ExtensionInfo>| {
-                                    extensions.append(&mut l);
-                                };
-                                append_extensions(new_extensions);
-                            },
-                            "commands" => {
-                                let mut events = XmlContents::new_inside(&mut events);
-                                for new_command in FromNextFn::new(|| CommandInfo::parse_next_command(&mut events)) {
-                                    let cmd = try!(new_command);
-                                    commands.insert(cmd.name.clone(), cmd);
+/// Collects the contents of one or more registry sources before they're checked and turned in to a
+/// `Registry`
+struct RegistryParser<'a> {
+    dialect: &'a Dialect,
+    api: Option<&'a str>,
+    found_types: bool,
+    types: HashMap<String, TypeInfo>,
+    commands: HashMap<String, CommandInfo>,
+    features: LinkedList<FeatureInfo>,
+    extensions: LinkedList<ExtensionInfo>,
+    enums: LinkedList<EnumsInfo>,
+    /// Names of the types and commands that have definitions specific to an API
+    api_variants: HashSet<String>,
+}
+
+fn duplicate_definition(kind: &str, name: &str) -> ParseError {
+    ParseError::Custom(format!("{} {} is defined more than once, differently", kind, name).into())
+}
+
+impl<'a> RegistryParser<'a> {
+    fn new(dialect: &'a Dialect, api: Option<&'a str>) -> RegistryParser<'a> {
+        RegistryParser {
+            dialect: dialect,
+            api: api,
+            found_types: false,
+            types: HashMap::new(),
+            commands: HashMap::new(),
+            features: LinkedList::new(),
+            extensions: LinkedList::new(),
+            enums: LinkedList::new(),
+            api_variants: HashSet::new(),
+        }
+    }
+
+    /// Checks if a definition should replace an earlier one with the same name.
+    ///
+    /// Repeating a definition exactly is fine, and any other redefinition is an error. The one
+    /// exception is the variants of a type or command for different APIs (elements with an `api`
+    /// attribute), which all get through without an API selection, so the last one wins.
+    fn replaces<T: PartialEq>(&self, kind: &str, name: &str, old: &T, new: &T) -> ParseResult<bool> {
+        if old == new {
+            Ok(false)
+        } else if self.api_variants.contains(name) {
+            Ok(true)
+        } else {
+            Err(duplicate_definition(kind, name))
+        }
+    }
+
+    fn add_type(&mut self, info: TypeInfo, api_specific: bool) -> ParseResult<()> {
+        if api_specific && self.api.is_none() {
+            self.api_variants.insert(info.name().into());
+        }
+        // Uncategorized types are placeholders for types defined elsewhere (i.e. in video.xml or a
+        // C header), so any other definition takes their place
+        let replace = match self.types.get(info.name()) {
+            None => true,
+            Some(_) if is_placeholder(&info) => false,
+            Some(old) if is_placeholder(old) => true,
+            Some(old) => try!(self.replaces("type", info.name(), old, &info)),
+        };
+        if replace {
+            self.types.insert(info.name().into(), info);
+        }
+        Ok(())
+    }
+
+    fn add_command(&mut self, command: CommandInfo, api_specific: bool) -> ParseResult<()> {
+        if api_specific && self.api.is_none() {
+            self.api_variants.insert(command.name.clone());
+        }
+        let replace = match self.commands.get(command.name.as_str()) {
+            None => true,
+            Some(old) => try!(self.replaces("command", command.name.as_str(), old, &command)),
+        };
+        if replace {
+            self.commands.insert(command.name.clone(), command);
+        }
+        Ok(())
+    }
+
+    fn add_feature(&mut self, feature: FeatureInfo) -> ParseResult<()> {
+        let position = self.features.iter().position(|f| f.name == feature.name);
+        let replace = match position.and_then(|i| self.features.iter().nth(i)) {
+            None => true,
+            Some(old) => try!(self.replaces("feature", feature.name.as_str(), old, &feature)),
+        };
+        if replace {
+            replace_or_push(&mut self.features, position, feature);
+        }
+        Ok(())
+    }
+
+    fn add_extension(&mut self, extension: ExtensionInfo) -> ParseResult<()> {
+        let position = self.extensions.iter().position(|e| e.name == extension.name);
+        let replace = match position.and_then(|i| self.extensions.iter().nth(i)) {
+            None => true,
+            Some(old) => try!(self.replaces("extension", extension.name.as_str(), old, &extension)),
+        };
+        if replace {
+            replace_or_push(&mut self.extensions, position, extension);
+        }
+        Ok(())
+    }
+
+    fn add_enums(&mut self, info: EnumsInfo) -> ParseResult<()> {
+        let position = match info.name {
+            Some(ref name) => self.enums.iter().position(|e| e.name.as_ref() == Some(name)),
+            None => None,
+        };
+        let replace = match position.and_then(|i| self.enums.iter().nth(i)) {
+            None => true,
+            Some(old) => try!(self.replaces("enums", info.name.as_ref().unwrap().as_str(), old, &info)),
+        };
+        if replace {
+            replace_or_push(&mut self.enums, position, info);
+        }
+        Ok(())
+    }
+
+    /// Adds everything from one registry source
+    fn parse<It: Iterator<Item=XmlResult<XmlEvent>>>(&mut self, events: It) -> ParseResult<()> {
+        let dialect = self.dialect;
+        let api = self.api;
+        let mut events = ApiFilter::new(events, api);
+        loop {
+            let next_event = events.next();
+            match next_event {
+                Some(Ok(XmlEvent::StartElement { name, attributes, namespace })) => {
+                    match name.local_name.as_str() {
+                        "types" => {
+                            events.take_passed_api_specific();
+                            let mut events = XmlContents::new_inside(&mut events);
+                            while let Some(new_type) = TypeInfo::parse_next_type(&mut events, dialect) {
+                                let api_specific = events.inner_mut().take_passed_api_specific();
+                                try!(self.add_type(try!(new_type), api_specific));
+                            }
+                            self.found_types = true;
+                        },
+                        "feature" => {
+                            let start_event = XmlEvent::StartElement {
+                                name: name,
+                                attributes: attributes,
+                                namespace: namespace,
+                            };
+                            let mut events = std::iter::once(Ok(start_event))
+                                .chain(&mut events);
+                            let feature = FeatureInfo::parse_next_feature(&mut events)
+                                .unwrap_or(Err(ParseError::Impossible));
+                            try!(self.add_feature(try!(feature)));
+                        },
+                        "extensions" => {
+                            let mut events = XmlContents::new_inside(&mut events);
+                            for new_extension in FromNextFn::new(|| ExtensionInfo::parse_next_extension(&mut events)) {
+                                let extension = try!(new_extension);
+                                if api.map(|api| extension.is_supported_by(api)).unwrap_or(true) {
+                                    try!(self.add_extension(extension));
                                 }
-                            },
-                            "enums" => {
-                                let info: EnumsInfo = try!(XmlParse::parse(&mut events, String::from("enums"), attributes));
-                                enums.push_back(info);
                             }
-                            _ => {},
+                        },
+                        "commands" => {
+                            events.take_passed_api_specific();
+                            let mut events = XmlContents::new_inside(&mut events);
+                            while let Some(new_command) = CommandInfo::parse_next_command(&mut events) {
+                                let api_specific = events.inner_mut().take_passed_api_specific();
+                                try!(self.add_command(try!(new_command), api_specific));
+                            }
+                        },
+                        "enums" => {
+                            let info: EnumsInfo = try!(XmlParse::parse(&mut events, String::from("enums"), attributes));
+                            try!(self.add_enums(info));
                         }
-                    },
-                    Some(Ok(_)) => continue,
-                    Some(Err(e)) => {
-                        return Err(ParseError::Xml(e));
-                    },
-                    None => break, // If we have no more events, then we're done.
-                }
+                        _ => {},
+                    }
+                },
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    return Err(ParseError::Xml(e));
+                },
+                None => return Ok(()), // If we have no more events, then we're done.
             }
         }
-        types
-            .map(|t| Ok(t))
-            .unwrap_or_else(|| Err(ParseError::Custom("No <types> tag was found in the registry".into())))
-            .map(move |types| Registry {
-                types: types,
-                commands: commands,
-                extensions: extensions,
-                features: features,
-                enums: enums,
-            })
+    }
+
+    fn finish(mut self) -> ParseResult<Registry> {
+        if !self.found_types {
+            return Err(ParseError::Custom("No <types> tag was found in the registry".into()));
+        }
+        let aliased_commands: Vec<(String, String)> = self.commands.values()
+            .filter_map(|cmd| cmd.alias.as_ref().map(|alias| (cmd.name.clone(), alias.clone())))
+            .collect();
+        for (name, alias) in aliased_commands {
+            let target = try! {
+                resolve_command_alias(&self.commands, alias.as_str())
+                    .map(|c| Ok(c.clone()))
+                    .unwrap_or_else(|| Err(ParseError::Custom(format!("command {} is an alias of unknown command {}", &name, &alias).into())))
+            };
+            self.commands.insert(name.clone(), CommandInfo {
+                name: name,
+                alias: Some(alias),
+                ..target
+            });
+        }
+        Ok(Registry {
+            types: self.types,
+            commands: self.commands,
+            extensions: self.extensions,
+            features: self.features,
+            enums: self.enums,
+            dialect: self.dialect.clone(),
+        })
+    }
+}
+
+#[inline(always)]
+fn is_placeholder(info: &TypeInfo) -> bool {
+    match info {
+        &TypeInfo::Uncategorized { .. } => true,
+        _ => false,
+    }
+}
+
+/// Replaces the element at `position` if there is one, and adds the item to the end otherwise
+fn replace_or_push<T>(list: &mut LinkedList<T>, position: Option<usize>, item: T) {
+    match position.and_then(|i| list.iter_mut().nth(i)) {
+        Some(old) => {
+            *old = item;
+            return;
+        },
+        None => {},
+    }
+    list.push_back(item);
+}
+
+impl Registry {
+    /// Parses a Vulkan registry (vk.xml) from a stream of XML events.
+    ///
+    /// If `api` is given (i.e. `Some("vulkan")` or `Some("vulkansc")`), then any element whose `api`
+    /// attribute doesn't include it is dropped, as are extensions that aren't `supported` by it.
+    pub fn new<It: Iterator<Item=XmlResult<XmlEvent>>, FeatureF: FnMut(&str) -> bool, ExtensionF: FnMut(&str) -> bool>(events: It, api: Option<&str>, should_include_feature: FeatureF, should_include_extension: ExtensionF) -> ParseResult<Registry> {
+        Registry::with_dialect(events, &Dialect::vulkan(), api, should_include_feature, should_include_extension)
+    }
+
+    /// Parses a registry in any dialect of the vk.xml schema (i.e. OpenXR's xr.xml) from a stream
+    /// of XML events.
+    ///
+    /// `api` works the same way as it does for `Registry::new`.
+    pub fn with_dialect<It: Iterator<Item=XmlResult<XmlEvent>>, FeatureF: FnMut(&str) -> bool, ExtensionF: FnMut(&str) -> bool>(events: It, dialect: &Dialect, api: Option<&str>, should_include_feature: FeatureF, should_include_extension: ExtensionF) -> ParseResult<Registry> {
+        Registry::from_sources(std::iter::once(events), dialect, api, should_include_feature, should_include_extension)
+    }
+
+    /// Parses several registry sources in to one registry, i.e. `vk.xml`, `video.xml`, and an
+    /// overlay with a local extension.
+    ///
+    /// Types, commands, enums, features, and extensions from every source (and from repeated
+    /// blocks within a source) are merged. Defining something again exactly the same way is
+    /// allowed, and placeholders for types that are defined elsewhere (like the `StdVideo*` types
+    /// in vk.xml) are replaced by the real definition. Any other redefinition is an error, except
+    /// between the variants of a type or command for different APIs when `api` isn't given. Only
+    /// one source has to have a `<types>` block.
+    ///
+    /// Features and extensions whose names `should_include_feature` and
+    /// `should_include_extension` reject are left out.
+    pub fn from_sources<S: IntoIterator<Item=It>, It: Iterator<Item=XmlResult<XmlEvent>>, FeatureF: FnMut(&str) -> bool, ExtensionF: FnMut(&str) -> bool>(sources: S, dialect: &Dialect, api: Option<&str>, mut should_include_feature: FeatureF, mut should_include_extension: ExtensionF) -> ParseResult<Registry> {
+        let mut parser = RegistryParser::new(dialect, api);
+        for events in sources {
+            try!(parser.parse(events));
+        }
+        let mut registry = try!(parser.finish());
+        registry.features = registry.features.into_iter().filter(|f| should_include_feature(f.name.as_str())).collect();
+        registry.extensions = registry.extensions.into_iter().filter(|e| should_include_extension(e.name.as_str())).collect();
+        Ok(registry)
+    }
+
+    /// Parses a Vulkan registry from a string holding the whole document, including every feature
+    /// and extension.
+    pub fn from_str(xml: &str, api: Option<&str>) -> ParseResult<Registry> {
+        Registry::from_reader(xml.as_bytes(), api)
+    }
+
+    /// Parses a Vulkan registry from anything that can be read, including every feature and
+    /// extension.
+    pub fn from_reader<R: io::Read>(reader: R, api: Option<&str>) -> ParseResult<Registry> {
+        let include_all = |_: &str| true;
+        Registry::new(xml::reader::EventReader::new(reader).into_iter(), api, &include_all, &include_all)
+    }
+
+    /// Parses a Vulkan registry from a file (i.e. `Vulkan-Docs/xml/vk.xml`), including every
+    /// feature and extension.
+    pub fn from_path<P: AsRef<Path>>(path: P, api: Option<&str>) -> ParseResult<Registry> {
+        let file = try!(fs::File::open(path).map_err(ParseError::Io));
+        Registry::from_reader(io::BufReader::new(file), api)
+    }
+
+    /// Parses the copy of vk.xml and video.xml that is built in to this crate.
+    #[cfg(feature = "embedded")]
+    pub fn embedded(api: Option<&str>) -> ParseResult<Registry> {
+        let include_all = |_: &str| true;
+        let sources = [embedded::VK_XML, embedded::VIDEO_XML];
+        let sources = sources.iter().map(|xml| xml::reader::EventReader::new(xml.as_bytes()).into_iter());
+        Registry::from_sources(sources, &Dialect::vulkan(), api, &include_all, &include_all)
     }
 }
@@ -0,0 +1,82 @@
+//! Queries over the promotion and deprecation metadata of extensions
+
+use std::fmt;
+use ::{ ApiVersion, ExtensionInfo, Registry };
+
+/// Why an extension shouldn't be used anymore
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deprecation {
+    /// Deprecated, optionally in favor of another extension or core version
+    DeprecatedBy(Option<String>),
+    /// Obsoleted, optionally by another extension or core version
+    ObsoletedBy(Option<String>),
+}
+
+fn replacement(name: &str) -> Option<String> {
+    if name.len() > 0 {
+        Some(name.into())
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (what, replacement) = match self {
+            &Deprecation::DeprecatedBy(ref r) => ("deprecated", r),
+            &Deprecation::ObsoletedBy(ref r) => ("obsoleted", r),
+        };
+        match replacement {
+            &Some(ref r) => write!(f, "{} by {}", what, r),
+            &None => write!(f, "{} without replacement", what),
+        }
+    }
+}
+
+impl ExtensionInfo {
+    /// Gets the reason that this extension shouldn't be used anymore, if there is one.
+    ///
+    /// Being obsoleted takes precedence over being deprecated.
+    pub fn deprecation(&self) -> Option<Deprecation> {
+        let obsoleted = self.obsoletedby.as_ref().map(|s| Deprecation::ObsoletedBy(replacement(s)));
+        let deprecated = self.deprecatedby.as_ref().map(|s| Deprecation::DeprecatedBy(replacement(s)));
+        obsoleted.or(deprecated)
+    }
+}
+
+impl Registry {
+    /// Looks up an extension by name
+    pub fn extension(&self, name: &str) -> Option<&ExtensionInfo> {
+        self.extensions.iter().find(|e| e.name == name)
+    }
+
+    /// Follows `promotedto` links from an extension, returning every extension or feature that it
+    /// was (transitively) promoted to, in order.
+    ///
+    /// `VK_KHR_maintenance1` gives `["VK_VERSION_1_1"]`.
+    pub fn promotion_chain(&self, extension: &str) -> Vec<&str> {
+        let mut chain: Vec<&str> = Vec::new();
+        let mut current = self.extension(extension);
+        while let Some(promoted) = current.and_then(|e| e.promotedto.as_ref()) {
+            // Guard against cycles in bad registries
+            if promoted == extension || chain.contains(&promoted.as_str()) {
+                break;
+            }
+            chain.push(promoted.as_str());
+            current = self.extension(promoted.as_str());
+        }
+        chain
+    }
+
+    /// Gets the core version that an extension was eventually promoted to, if any
+    pub fn promoted_version(&self, extension: &str) -> Option<ApiVersion> {
+        self.promotion_chain(extension).last()
+            .and_then(|&name| self.features.iter().find(|f| f.name == name))
+            .map(|f| f.number)
+    }
+
+    /// Gets the reason that an extension shouldn't be used anymore, if it exists and has one
+    pub fn deprecation(&self, extension: &str) -> Option<Deprecation> {
+        self.extension(extension).and_then(|e| e.deprecation())
+    }
+}
@@ -0,0 +1,132 @@
+//! Reverse index from the symbols in a registry to the features and extensions that provide them
+
+use std::collections::{ BTreeSet, HashMap, LinkedList };
+use ::{ ApiVersion, Registry, RequireBlock, Requirement };
+
+/// Something that makes a symbol available
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Provider {
+    /// The symbol is part of the core API as of this version
+    Version {
+        version: ApiVersion,
+        depends: Option<String>,
+    },
+    /// The symbol is provided by the named extension
+    Extension {
+        name: String,
+        depends: Option<String>,
+    },
+}
+
+impl Provider {
+    /// Gets the `depends` condition (if any) that must also hold for this provider to actually
+    /// provide the symbol
+    pub fn depends(&self) -> Option<&str> {
+        match self {
+            &Provider::Version { ref depends, .. } | &Provider::Extension { ref depends, .. } => {
+                depends.as_ref().map(|s| s.as_str())
+            },
+        }
+    }
+
+    /// Checks if this is an unconditional core version provider
+    pub fn is_core(&self) -> bool {
+        match self {
+            &Provider::Version { depends: None, .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Gets the name of the symbol that a requirement makes available
+fn requirement_symbol(requirement: &Requirement) -> &str {
+    match requirement {
+        &Requirement::Type(ref name) => name,
+        &Requirement::Command(ref name) => name,
+        &Requirement::Enum(ref name) => name,
+        &Requirement::Constant { ref name, .. } => name,
+        &Requirement::EnumExtension { ref name, .. } => name,
+    }
+}
+
+/// An index from the symbols in a registry to the versions and extensions that provide them, which
+/// is built once by `Registry::providers` to look up any number of symbols
+#[derive(Debug, Clone, Default)]
+pub struct ProviderIndex<'a> {
+    symbols: HashMap<&'a str, BTreeSet<Provider>>,
+}
+
+impl<'a> ProviderIndex<'a> {
+    /// Gets the providers of a symbol, if it's required by any feature or extension
+    #[inline]
+    pub fn get(&self, symbol: &str) -> Option<&BTreeSet<Provider>> {
+        self.symbols.get(symbol)
+    }
+
+    /// Gets the set of versions and extensions that provide a symbol
+    pub fn providers_of(&self, symbol: &str) -> BTreeSet<Provider> {
+        self.get(symbol).cloned().unwrap_or_else(BTreeSet::new)
+    }
+
+    /// Gets the lowest core version that unconditionally provides a symbol, if it's in the core
+    /// API at all
+    pub fn minimum_version(&self, symbol: &str) -> Option<ApiVersion> {
+        self.get(symbol).into_iter()
+            .flat_map(|providers| providers.iter())
+            .filter_map(|p| match p {
+                &Provider::Version { version, depends: None } => Some(version),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+fn index_blocks<'a, F: Fn(Option<String>) -> Provider>(index: &mut HashMap<&'a str, BTreeSet<Provider>>, blocks: &'a LinkedList<RequireBlock>, provider: F) {
+    for block in blocks {
+        for requirement in block.requirements.iter() {
+            index.entry(requirement_symbol(requirement))
+                .or_insert_with(BTreeSet::new)
+                .insert(provider(block.depends.clone()));
+        }
+    }
+}
+
+impl Registry {
+    /// Builds an index from every symbol that is required by a feature or extension to the set of
+    /// versions and extensions providing it.
+    ///
+    /// Version providers sort before extension providers, and lower versions before higher ones.
+    pub fn providers<'a>(&'a self) -> ProviderIndex<'a> {
+        let mut index = HashMap::new();
+        for feature in self.features.iter() {
+            index_blocks(&mut index, &feature.require_blocks, |depends| Provider::Version {
+                version: feature.number,
+                depends: depends,
+            });
+        }
+        for extension in self.extensions.iter() {
+            index_blocks(&mut index, &extension.require_blocks, |depends| Provider::Extension {
+                name: extension.name.clone(),
+                depends: depends,
+            });
+        }
+        ProviderIndex {
+            symbols: index,
+        }
+    }
+
+    /// Gets the set of versions and extensions that provide a single symbol.
+    ///
+    /// This builds the whole index, so `providers` should be used to look up more than one symbol.
+    pub fn providers_of(&self, symbol: &str) -> BTreeSet<Provider> {
+        self.providers().symbols.remove(symbol).unwrap_or_else(BTreeSet::new)
+    }
+
+    /// Gets the lowest core version that unconditionally provides a single symbol, if it's in the
+    /// core API at all.
+    ///
+    /// This builds the whole index, so `providers` should be used to look up more than one symbol.
+    pub fn minimum_version(&self, symbol: &str) -> Option<ApiVersion> {
+        self.providers().minimum_version(symbol)
+    }
+}
@@ -0,0 +1,239 @@
+//! Consistency checks for a parsed `Registry`
+
+use std::collections::{ HashMap, HashSet };
+use std::fmt;
+use ::{ Registry, Requirement, TypeInfo };
+
+/// A structural problem found in a registry by `Registry::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A struct member or command parameter refers to a type that isn't defined
+    UndefinedType {
+        context: String,
+        ty: String,
+    },
+    /// A struct member's `len` attribute names a member that doesn't exist
+    UnknownLenMember {
+        structure: String,
+        member: String,
+        len: String,
+    },
+    /// A bitmask `requires` an enum that isn't defined
+    MissingBitmaskEnum {
+        bitmask: String,
+        requires: String,
+    },
+    /// A handle's parent isn't a known handle
+    UnknownHandleParent {
+        handle: String,
+        parent: String,
+    },
+    /// An extension requires an extension (or feature) that isn't defined
+    UnknownRequiredExtension {
+        extension: String,
+        requires: String,
+    },
+    /// Two distinct enumerants of the same enum have the same value
+    DuplicateEnumValue {
+        enumeration: String,
+        name: String,
+        existing: String,
+        value: i64,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ValidationError::*;
+        match self {
+            &UndefinedType { ref context, ref ty } => {
+                write!(f, "{} has undefined type {}", context, ty)
+            },
+            &UnknownLenMember { ref structure, ref member, ref len } => {
+                write!(f, "{}::{} has len {} which isn't a member of {}", structure, member, len, structure)
+            },
+            &MissingBitmaskEnum { ref bitmask, ref requires } => {
+                write!(f, "bitmask {} requires missing enum {}", bitmask, requires)
+            },
+            &UnknownHandleParent { ref handle, ref parent } => {
+                write!(f, "handle {} has unknown parent {}", handle, parent)
+            },
+            &UnknownRequiredExtension { ref extension, ref requires } => {
+                write!(f, "extension {} requires unknown extension {}", extension, requires)
+            },
+            &DuplicateEnumValue { ref enumeration, ref name, ref existing, value } => {
+                write!(f, "{}: {} has the same value ({}) as {}", enumeration, name, value, existing)
+            },
+        }
+    }
+}
+
+/// Gets the names of the members referred to by a `len` attribute, skipping over the special
+/// `null-terminated` and latexmath lengths
+fn len_members(len: &str) -> Vec<&str> {
+    len.split(",")
+        .filter(|l| *l != "null-terminated" && *l != "1" && !l.starts_with("latexmath:"))
+        // `pAllocateInfo->descriptorSetCount` refers to the `pAllocateInfo` member
+        .map(|l| l.split("->").next().unwrap())
+        .collect()
+}
+
+/// Keeps track of the enumerant values that have been seen for each enum
+struct EnumValues<'a> {
+    values: HashMap<&'a str, HashMap<i64, &'a str>>,
+    names: HashSet<(&'a str, &'a str)>,
+}
+
+impl<'a> EnumValues<'a> {
+    fn new() -> EnumValues<'a> {
+        EnumValues {
+            values: HashMap::new(),
+            names: HashSet::new(),
+        }
+    }
+
+    fn insert(&mut self, enumeration: &'a str, name: &'a str, value: Option<i64>, errors: &mut Vec<ValidationError>) {
+        // The same enumerant can be required from more than one place, so only the first one
+        // counts
+        if !self.names.insert((enumeration, name)) {
+            return;
+        }
+        let value = match value {
+            Some(v) => v,
+            None => return,
+        };
+        let values = self.values.entry(enumeration).or_insert_with(HashMap::new);
+        if let Some(&existing) = values.get(&value) {
+            errors.push(ValidationError::DuplicateEnumValue {
+                enumeration: enumeration.into(),
+                name: name.into(),
+                existing: existing.into(),
+                value: value,
+            });
+        } else {
+            values.insert(value, name);
+        }
+    }
+}
+
+impl Registry {
+    fn has_type(&self, name: &str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    fn has_handle(&self, name: &str) -> bool {
+        match self.types.get(name) {
+            Some(&TypeInfo::Handle { .. }) | Some(&TypeInfo::Alias { .. }) => true,
+            _ => false,
+        }
+    }
+
+    fn has_enum(&self, name: &str) -> bool {
+        self.types.contains_key(name) || self.enums.iter().any(|e| e.name.as_ref().map(|s| s.as_str()) == Some(name))
+    }
+
+    /// Checks the registry for structural problems, like references to types that don't exist.
+    ///
+    /// An empty list means that no problems were found.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        let mut types: Vec<(&String, &TypeInfo)> = self.types.iter().collect();
+        types.sort_by_key(|&(name, _)| name);
+        for (_, info) in types {
+            match info {
+                &TypeInfo::Struct { ref name, ref members, .. } | &TypeInfo::Union { ref name, ref members } => {
+                    for member in members {
+                        if !self.has_type(member.ty.ty.as_str()) {
+                            errors.push(ValidationError::UndefinedType {
+                                context: format!("member {}::{}", name, &member.name),
+                                ty: member.ty.ty.clone(),
+                            });
+                        }
+                        let lens = member.len.as_ref().map(|l| len_members(l.as_str())).unwrap_or_else(Vec::new);
+                        for len in lens {
+                            if !members.iter().any(|m| m.name == len) {
+                                errors.push(ValidationError::UnknownLenMember {
+                                    structure: name.clone(),
+                                    member: member.name.clone(),
+                                    len: len.into(),
+                                });
+                            }
+                        }
+                    }
+                },
+                &TypeInfo::Bitmask { ref name, ref requires, .. } => {
+                    for required in requires.iter().filter(|r| !self.has_enum(r.as_str())) {
+                        errors.push(ValidationError::MissingBitmaskEnum {
+                            bitmask: name.clone(),
+                            requires: required.clone(),
+                        });
+                    }
+                },
+                &TypeInfo::Handle { ref name, parent: Some(ref parents), .. } => {
+                    for parent in parents.split(",").filter(|p| !self.has_handle(p)) {
+                        errors.push(ValidationError::UnknownHandleParent {
+                            handle: name.clone(),
+                            parent: parent.into(),
+                        });
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        let mut commands: Vec<_> = self.commands.values().filter(|c| c.alias.is_none()).collect();
+        commands.sort_by_key(|c| c.name.as_str());
+        for cmd in commands {
+            let types = ::std::iter::once((String::from("return type"), &cmd.return_type))
+                .chain(cmd.params.iter().map(|p| (format!("parameter {}", &p.name), &p.ty)));
+            for (what, ty) in types {
+                if !self.has_type(ty.ty.as_str()) {
+                    errors.push(ValidationError::UndefinedType {
+                        context: format!("{} of {}", what, &cmd.name),
+                        ty: ty.ty.clone(),
+                    });
+                }
+            }
+        }
+
+        for extension in self.extensions.iter() {
+            for required in extension.requires.iter() {
+                let known = self.extensions.iter().any(|e| &e.name == required) ||
+                    self.features.iter().any(|f| &f.name == required);
+                if !known {
+                    errors.push(ValidationError::UnknownRequiredExtension {
+                        extension: extension.name.clone(),
+                        requires: required.clone(),
+                    });
+                }
+            }
+        }
+
+        let mut enum_values = EnumValues::new();
+        for enums in self.enums.iter() {
+            // API constants aren't an enumerated type, so they're allowed to share values
+            let name = match (&enums.name, enums.ty) {
+                (&Some(ref name), Some(_)) => name.as_str(),
+                _ => continue,
+            };
+            for &(ref value_name, ref value) in enums.values.iter() {
+                enum_values.insert(name, value_name.as_str(), value.as_integer(), &mut errors);
+            }
+        }
+        let feature_requirements = self.features.iter().map(|f| (None, f.requirements()));
+        let extension_requirements = self.extensions.iter().map(|e| (e.number, e.requirements()));
+        for (number, requirements) in feature_requirements.chain(extension_requirements) {
+            for requirement in requirements {
+                match requirement {
+                    &Requirement::EnumExtension { ref name, ref extends, ref value } => {
+                        enum_values.insert(extends.as_str(), name.as_str(), value.value(number), &mut errors);
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        errors
+    }
+}
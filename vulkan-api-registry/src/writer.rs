@@ -0,0 +1,544 @@
+//! Serialization of a `Registry` back into the vk.xml schema
+
+use std::borrow::Cow;
+use std::io::Write;
+use xml;
+use xml::writer::{ EmitterConfig, EventWriter, XmlEvent };
+use ::{ CommandBufferLevel, CommandInfo, CommandPipelineType, CommandRenderPassInfo, EnumExtensionStrategy, EnumValue, EnumsInfo, EnumsType, ExtensionInfo, ExtensionType, FeatureInfo, RequireBlock, Requirement, Registry, StructMember, TypeInfo, TyperefInfo };
+use ::xast;
+
+#[derive(Debug)]
+pub enum WriteError {
+    Custom(Cow<'static, str>),
+    Xml(xml::writer::Error),
+}
+
+impl From<xml::writer::Error> for WriteError {
+    fn from(e: xml::writer::Error) -> WriteError {
+        WriteError::Xml(e)
+    }
+}
+
+pub type WriteResult<T> = Result<T, WriteError>;
+
+type Attributes<'a> = Vec<(&'static str, Cow<'a, str>)>;
+
+/// Gets the parts of a C declaration that go before and after the name of the type that a type
+/// reference refers to (i.e. `const ` and `* const*` for `const char* const*`)
+pub fn pointer_declaration(ty: &TyperefInfo) -> (&'static str, String) {
+    let prefix = if ty.constness.first() == Some(&true) { "const " } else { "" };
+    let mut pointers = String::new();
+    for (i, &constness) in ty.constness.iter().enumerate() {
+        if i > 0 && constness {
+            pointers.push_str(" const");
+        }
+        pointers.push('*');
+    }
+    (prefix, pointers)
+}
+
+fn join<'a, It: IntoIterator<Item=&'a String>>(values: It) -> String {
+    values.into_iter().map(|s| s.as_str()).collect::<Vec<_>>().join(",")
+}
+
+/// Pushes an attribute, unless it's empty
+fn push_attribute<'a, S: Into<Cow<'a, str>>>(attributes: &mut Attributes<'a>, name: &'static str, value: S) {
+    let value = value.into();
+    if !value.is_empty() {
+        attributes.push((name, value));
+    }
+}
+
+fn enum_value_attribute(value: &EnumValue) -> (&'static str, Cow<'_, str>) {
+    match *value {
+        EnumValue::Bitpos(bp) => ("bitpos", bp.to_string().into()),
+        EnumValue::Value(v) => ("value", v.to_string().into()),
+        EnumValue::StrValue(ref v) => ("value", v.as_str().into()),
+        EnumValue::Alias(ref a) => ("alias", a.as_str().into()),
+    }
+}
+
+/// Wraps an `EventWriter` to keep track of indentation.
+///
+/// The emitter's own indentation can't be used, because it would add whitespace to the middle of
+/// elements like `<member>` whose text matters.
+struct RegistryWriter<W: Write> {
+    writer: EventWriter<W>,
+    depth: usize,
+}
+
+impl<W: Write> RegistryWriter<W> {
+    fn newline(&mut self) -> WriteResult<()> {
+        let indent = format!("\n{}", "    ".repeat(self.depth));
+        self.writer.write(XmlEvent::characters(indent.as_str()))?;
+        Ok(())
+    }
+
+    /// Starts an element on a new line, without changing the indentation of its contents
+    fn start(&mut self, name: &str, attributes: &Attributes) -> WriteResult<()> {
+        self.newline()?;
+        let mut start = XmlEvent::start_element(name);
+        for &(attribute, ref value) in attributes.iter() {
+            start = start.attr(attribute, value);
+        }
+        self.writer.write(start)?;
+        Ok(())
+    }
+
+    fn end(&mut self) -> WriteResult<()> {
+        self.writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+
+    /// Starts an element whose contents go on their own indented lines
+    fn open(&mut self, name: &str, attributes: &Attributes) -> WriteResult<()> {
+        self.start(name, attributes)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn close(&mut self) -> WriteResult<()> {
+        self.depth -= 1;
+        self.newline()?;
+        self.end()
+    }
+
+    /// Writes an element with no contents on its own line
+    fn empty(&mut self, name: &str, attributes: &Attributes) -> WriteResult<()> {
+        self.start(name, attributes)?;
+        self.end()
+    }
+
+    fn text(&mut self, text: &str) -> WriteResult<()> {
+        if !text.is_empty() {
+            self.writer.write(XmlEvent::characters(text))?;
+        }
+        Ok(())
+    }
+
+    /// Writes an element containing only text, inline with the current element
+    fn inline(&mut self, name: &str, text: &str) -> WriteResult<()> {
+        self.writer.write(XmlEvent::start_element(name))?;
+        self.text(text)?;
+        self.end()
+    }
+
+    /// Writes a type reference followed by a name, like `const <type>char</type>* <name>pName</name>`
+    fn typed_name(&mut self, ty: &TyperefInfo, name: &str) -> WriteResult<()> {
+        let (prefix, mut pointers) = pointer_declaration(ty);
+        self.text(prefix)?;
+        self.inline("type", ty.ty.as_str())?;
+        pointers.push(' ');
+        self.text(pointers.as_str())?;
+        self.inline("name", name)?;
+        for size in ty.array.iter() {
+            self.text("[")?;
+            // Sizes that aren't literals are API constants
+            if size.chars().all(|c| c.is_ascii_digit()) {
+                self.text(size.as_str())?;
+            } else {
+                self.inline("enum", size.as_str())?;
+            }
+            self.text("]")?;
+        }
+        Ok(())
+    }
+
+    /// Writes a node from the original registry as-is
+    fn node(&mut self, node: &xast::Node) -> WriteResult<()> {
+        let mut start = XmlEvent::start_element(node.name.as_str());
+        for attribute in node.attributes.iter() {
+            start = start.attr(attribute.name.local_name.as_str(), attribute.value.as_str());
+        }
+        self.writer.write(start)?;
+        for content in node.contents.iter() {
+            match *content {
+                xast::Content::Text(ref s) => self.text(s.as_str())?,
+                xast::Content::Comment(ref s) => self.writer.write(XmlEvent::comment(s.as_str()))?,
+                xast::Content::Child(ref child) => self.node(child)?,
+            }
+        }
+        self.end()
+    }
+
+    fn members(&mut self, members: &::std::collections::LinkedList<StructMember>) -> WriteResult<()> {
+        for member in members.iter() {
+            let mut attributes = Attributes::new();
+            if let Some(ref values) = member.values {
+                push_attribute(&mut attributes, "values", join(values));
+            }
+            if let Some(ref len) = member.len {
+                push_attribute(&mut attributes, "len", len.as_str());
+            }
+            if let Some(ref altlen) = member.altlen {
+                push_attribute(&mut attributes, "altlen", altlen.as_str());
+            }
+            if member.externsync {
+                push_attribute(&mut attributes, "externsync", "true");
+            }
+            if member.optional {
+                push_attribute(&mut attributes, "optional", "true");
+            }
+            if member.noautovalidity {
+                push_attribute(&mut attributes, "noautovalidity", "true");
+            }
+            self.start("member", &attributes)?;
+            self.typed_name(&member.ty, member.name.as_str())?;
+            if let Some(bits) = member.bitfield {
+                self.text(format!(":{}", bits).as_str())?;
+            }
+            self.end()?;
+        }
+        Ok(())
+    }
+
+    fn type_info(&mut self, registry: &Registry, key: &str, info: &TypeInfo) -> WriteResult<()> {
+        match *info {
+            TypeInfo::Basetype { ref name, ref ty } => {
+                self.start("type", &vec![("category", "basetype".into())])?;
+                self.text("typedef ")?;
+                self.inline("type", ty.as_str())?;
+                self.text(" ")?;
+                self.inline("name", name.as_str())?;
+                self.text(";")?;
+                self.end()
+            },
+            TypeInfo::Bitmask { ref name, ref ty, ref requires } => {
+                let mut attributes = Attributes::new();
+                // 64-bit bitmasks name their bits with `bitvalues` instead of `requires`
+                let requires_attribute = if ty.ends_with("Flags64") { "bitvalues" } else { "requires" };
+                push_attribute(&mut attributes, requires_attribute, join(requires));
+                attributes.push(("category", "bitmask".into()));
+                self.start("type", &attributes)?;
+                self.text("typedef ")?;
+                self.inline("type", ty.as_str())?;
+                self.text(" ")?;
+                self.inline("name", name.as_str())?;
+                self.text(";")?;
+                self.end()
+            },
+            TypeInfo::Define { ref name, ref text } => {
+                // Name the define with a `<name>` tag in its directive if that's unambiguous, and
+                // with an attribute otherwise (i.e. when it's defined differently per platform)
+                let directive = format!("#define {}", name);
+                if text.matches(directive.as_str()).count() == 1 {
+                    let (before, after) = text.split_at(text.find(directive.as_str()).unwrap() + "#define ".len());
+                    self.start("type", &vec![("category", "define".into())])?;
+                    self.text(before)?;
+                    self.inline("name", name.as_str())?;
+                    self.text(&after[name.len()..])?;
+                } else {
+                    self.start("type", &vec![("name", name.as_str().into()), ("category", "define".into())])?;
+                    self.text(text.as_str())?;
+                }
+                self.end()
+            },
+            TypeInfo::Enum { ref name, .. } => {
+                self.empty("type", &vec![("name", name.as_str().into()), ("category", "enum".into())])
+            },
+            TypeInfo::Funcpointer { ref name, ref arguments, ref return_type } => {
+                self.start("type", &vec![("category", "funcpointer".into())])?;
+                let (prefix, pointers) = pointer_declaration(return_type);
+                self.text(format!("typedef {}{}{} ({}API_PTR *", prefix, &return_type.ty, pointers, registry.dialect.macro_prefix).as_str())?;
+                self.inline("name", name.as_str())?;
+                self.text(")(")?;
+                if arguments.is_empty() {
+                    self.text("void")?;
+                }
+                for (i, (argument, ty)) in arguments.iter().enumerate() {
+                    self.text(if i > 0 { ",\n    " } else { "\n    " })?;
+                    let (prefix, pointers) = pointer_declaration(ty);
+                    self.text(prefix)?;
+                    self.inline("type", ty.ty.as_str())?;
+                    self.text(format!("{} {}", pointers, argument).as_str())?;
+                }
+                self.text(");")?;
+                self.end()
+            },
+            TypeInfo::Handle { ref name, ref parent, ty } => {
+                let handle_macro = registry.dialect.handle_macros.iter()
+                    .find(|&&(_, t)| t == ty)
+                    .map(|&(m, _)| m)
+                    .ok_or_else(|| WriteError::Custom(format!("no handle macro for {:?} handles like {}", ty, name).into()))?;
+                let mut attributes = Attributes::new();
+                if let Some(ref parent) = *parent {
+                    push_attribute(&mut attributes, "parent", parent.as_str());
+                }
+                attributes.push(("category", "handle".into()));
+                self.start("type", &attributes)?;
+                self.inline("type", handle_macro)?;
+                self.text("(")?;
+                self.inline("name", name.as_str())?;
+                self.text(")")?;
+                self.end()
+            },
+            TypeInfo::Struct { ref name, ref members, ref extends } => {
+                let mut attributes: Attributes = vec![("category", "struct".into()), ("name", name.as_str().into())];
+                push_attribute(&mut attributes, "structextends", join(extends));
+                self.open("type", &attributes)?;
+                self.members(members)?;
+                self.close()
+            },
+            TypeInfo::Union { ref name, ref members } => {
+                self.open("type", &vec![("category", "union".into()), ("name", name.as_str().into())])?;
+                self.members(members)?;
+                self.close()
+            },
+            TypeInfo::Include(ref name) => {
+                self.empty("type", &vec![("name", name.as_str().into()), ("category", "include".into())])
+            },
+            TypeInfo::Alias { ref name, ref alias } => {
+                let mut attributes: Attributes = Vec::new();
+                // Generators need to know what kind of type an alias is, so use the category of
+                // whatever it aliases
+                let category = registry.types.get(alias).and_then(|t| match *t {
+                    TypeInfo::Basetype { .. } => Some("basetype"),
+                    TypeInfo::Bitmask { .. } => Some("bitmask"),
+                    TypeInfo::Enum { .. } => Some("enum"),
+                    TypeInfo::Handle { .. } => Some("handle"),
+                    TypeInfo::Struct { .. } => Some("struct"),
+                    TypeInfo::Union { .. } => Some("union"),
+                    _ => None,
+                });
+                if let Some(category) = category {
+                    attributes.push(("category", category.into()));
+                }
+                attributes.push(("name", name.as_str().into()));
+                attributes.push(("alias", alias.as_str().into()));
+                self.empty("type", &attributes)
+            },
+            TypeInfo::Uncategorized { ref node, .. } => {
+                self.newline()?;
+                self.node(node)
+            },
+            // Neither of these can come out of the parser, and they have no `<type>` form
+            TypeInfo::Constant { .. } | TypeInfo::Group => {
+                Err(WriteError::Custom(format!("type {} can't be written", key).into()))
+            },
+        }
+    }
+
+    fn enums(&mut self, enums: &EnumsInfo) -> WriteResult<()> {
+        let mut attributes = Attributes::new();
+        if let Some(ref name) = enums.name {
+            push_attribute(&mut attributes, "name", name.as_str());
+        }
+        match enums.ty {
+            Some(EnumsType::Enum) => attributes.push(("type", "enum".into())),
+            Some(EnumsType::Bitmask) => attributes.push(("type", "bitmask".into())),
+            None => {},
+        }
+        if let Some(ref vendor) = enums.vendor {
+            push_attribute(&mut attributes, "vendor", vendor.as_str());
+        }
+        self.open("enums", &attributes)?;
+        for (name, value) in enums.values.iter() {
+            let mut attributes = vec![enum_value_attribute(value), ("name", name.as_str().into())];
+            if let Some(ty) = enums.value_types.get(name) {
+                attributes.push(("type", ty.as_str().into()));
+            }
+            self.empty("enum", &attributes)?;
+        }
+        self.close()
+    }
+
+    fn command(&mut self, command: &CommandInfo) -> WriteResult<()> {
+        if let Some(ref alias) = command.alias {
+            return self.empty("command", &vec![("name", command.name.as_str().into()), ("alias", alias.as_str().into())]);
+        }
+        let mut attributes = Attributes::new();
+        push_attribute(&mut attributes, "queues", join(&command.queues));
+        push_attribute(&mut attributes, "successcodes", join(&command.successcodes));
+        push_attribute(&mut attributes, "errorcodes", join(&command.errorcodes));
+        if let Some(ref renderpass) = command.renderpass {
+            attributes.push(("renderpass", match *renderpass {
+                CommandRenderPassInfo::Inside => "inside",
+                CommandRenderPassInfo::Outside => "outside",
+                CommandRenderPassInfo::Both => "both",
+            }.into()));
+        }
+        if let Some(ref levels) = command.cmdbufferlevel {
+            let levels: Vec<&str> = levels.iter().map(|l| match *l {
+                CommandBufferLevel::Primary => "primary",
+                CommandBufferLevel::Secondary => "secondary",
+            }).collect();
+            attributes.push(("cmdbufferlevel", levels.join(",").into()));
+        }
+        if let Some(ref pipeline) = command.pipeline {
+            attributes.push(("pipeline", match *pipeline {
+                CommandPipelineType::Compute => "compute",
+                CommandPipelineType::Transfer => "transfer",
+                CommandPipelineType::Graphics => "graphics",
+            }.into()));
+        }
+        self.open("command", &attributes)?;
+        self.start("proto", &Vec::new())?;
+        self.typed_name(&command.return_type, command.name.as_str())?;
+        self.end()?;
+        for param in command.params.iter() {
+            let mut attributes = Attributes::new();
+            if param.optional {
+                attributes.push(("optional", "true".into()));
+            }
+            if let Some(ref len) = param.len {
+                attributes.push(("len", len.as_str().into()));
+            }
+            self.start("param", &attributes)?;
+            self.typed_name(&param.ty, param.name.as_str())?;
+            self.end()?;
+        }
+        self.close()
+    }
+
+    fn require_block(&mut self, block: &RequireBlock) -> WriteResult<()> {
+        let mut attributes = Attributes::new();
+        if let Some(ref depends) = block.depends {
+            push_attribute(&mut attributes, "depends", depends.as_str());
+        }
+        self.open("require", &attributes)?;
+        for requirement in block.requirements.iter() {
+            match *requirement {
+                Requirement::Type(ref name) => self.empty("type", &vec![("name", name.as_str().into())])?,
+                Requirement::Command(ref name) => self.empty("command", &vec![("name", name.as_str().into())])?,
+                Requirement::Enum(ref name) => self.empty("enum", &vec![("name", name.as_str().into())])?,
+                Requirement::Constant { ref name, ref value, ref ty } => {
+                    let mut attributes = vec![enum_value_attribute(value), ("name", name.as_str().into())];
+                    if let Some(ref ty) = *ty {
+                        attributes.push(("type", ty.as_str().into()));
+                    }
+                    self.empty("enum", &attributes)?;
+                },
+                Requirement::EnumExtension { ref name, ref extends, ref value } => {
+                    let mut attributes: Attributes = Vec::new();
+                    match *value {
+                        EnumExtensionStrategy::Value(ref value) => attributes.push(enum_value_attribute(value)),
+                        EnumExtensionStrategy::Alias(ref alias) => attributes.push(("alias", alias.as_str().into())),
+                        EnumExtensionStrategy::Offset { offset, negated, extnumber } => {
+                            if let Some(extnumber) = extnumber {
+                                attributes.push(("extnumber", extnumber.to_string().into()));
+                            }
+                            attributes.push(("offset", offset.to_string().into()));
+                            if negated {
+                                attributes.push(("dir", "-".into()));
+                            }
+                        },
+                    }
+                    attributes.push(("extends", extends.as_str().into()));
+                    attributes.push(("name", name.as_str().into()));
+                    self.empty("enum", &attributes)?;
+                },
+            }
+        }
+        self.close()
+    }
+
+    fn feature(&mut self, feature: &FeatureInfo) -> WriteResult<()> {
+        let number = feature.number.to_string();
+        self.open("feature", &vec![
+            ("api", feature.api.as_str().into()),
+            ("name", feature.name.as_str().into()),
+            ("number", number.into()),
+        ])?;
+        for block in feature.require_blocks.iter() {
+            self.require_block(block)?;
+        }
+        self.close()
+    }
+
+    fn extension(&mut self, extension: &ExtensionInfo) -> WriteResult<()> {
+        let mut attributes: Attributes = vec![
+            ("name", extension.name.as_str().into()),
+        ];
+        if let Some(number) = extension.number {
+            attributes.push(("number", number.to_string().into()));
+        }
+        match extension.ty {
+            ExtensionType::Instance { .. } => attributes.push(("type", "instance".into())),
+            ExtensionType::Device { .. } => attributes.push(("type", "device".into())),
+            ExtensionType::Disabled => {},
+        }
+        push_attribute(&mut attributes, "requires", join(&extension.requires));
+        if let Some(ref version) = extension.requires_core {
+            attributes.push(("requiresCore", version.to_string().into()));
+        }
+        {
+            let mut optional = |name: &'static str, value: &Option<String>| {
+                if let Some(ref value) = *value {
+                    // Some attributes are allowed to be empty, so these are pushed directly
+                    attributes.push((name, value.clone().into()));
+                }
+            };
+            optional("depends", &extension.depends);
+            optional("author", &extension.author);
+            optional("contact", &extension.contact);
+            optional("protect", &extension.protect);
+            optional("platform", &extension.platform);
+            optional("promotedto", &extension.promotedto);
+            optional("deprecatedby", &extension.deprecatedby);
+            optional("obsoletedby", &extension.obsoletedby);
+        }
+        push_attribute(&mut attributes, "supported", join(&extension.supported));
+        push_attribute(&mut attributes, "ratified", join(&extension.ratified));
+        if extension.provisional {
+            attributes.push(("provisional", "true".into()));
+        }
+        push_attribute(&mut attributes, "specialuse", join(&extension.specialuse));
+        self.open("extension", &attributes)?;
+        for block in extension.require_blocks.iter() {
+            self.require_block(block)?;
+        }
+        self.close()
+    }
+}
+
+impl Registry {
+    /// Writes this registry out in the vk.xml schema.
+    ///
+    /// Parsing the output gives back an equivalent `Registry`. Anything that the parser doesn't
+    /// keep, like the bodies of defines, isn't written, and types are written in alphabetical
+    /// order.
+    pub fn write<W: Write>(&self, sink: W) -> WriteResult<()> {
+        let config = EmitterConfig::new()
+            .perform_indent(false)
+            .write_document_declaration(true);
+        let mut writer = RegistryWriter {
+            writer: config.create_writer(sink),
+            depth: 0,
+        };
+        writer.open("registry", &Vec::new())?;
+
+        writer.open("types", &Vec::new())?;
+        let mut types: Vec<(&String, &TypeInfo)> = self.types.iter().collect();
+        types.sort_by_key(|&(name, _)| name);
+        for (name, info) in types {
+            writer.type_info(self, name.as_str(), info)?;
+        }
+        writer.close()?;
+
+        for enums in self.enums.iter() {
+            writer.enums(enums)?;
+        }
+
+        writer.open("commands", &Vec::new())?;
+        let mut commands: Vec<&CommandInfo> = self.commands.values().collect();
+        commands.sort_by_key(|c| c.name.as_str());
+        for command in commands {
+            writer.command(command)?;
+        }
+        writer.close()?;
+
+        for feature in self.features.iter() {
+            writer.feature(feature)?;
+        }
+
+        writer.open("extensions", &Vec::new())?;
+        for extension in self.extensions.iter() {
+            writer.extension(extension)?;
+        }
+        writer.close()?;
+
+        writer.close()?;
+        writer.text("\n")?;
+        Ok(())
+    }
+}
@@ -1,19 +1,23 @@
+//! A minimal owned XML tree, for the parts of the registry that are easier to handle as a whole
+//! element than as a stream of events
+
 use std::borrow::Cow;
 use std::collections::LinkedList;
+use std::collections::linked_list;
 use xml::attribute::OwnedAttribute;
 use xml::reader::XmlEvent;
 use xml::reader::Result as XmlResult;
 use ::{ ParseError, ParseResult, XmlParse };
 use ::xml_iter::XmlContents;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Content {
     Comment(String),
     Text(String),
     Child(Node),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Node {
     pub name: String,
     pub attributes: Vec<OwnedAttribute>,
@@ -34,10 +38,87 @@ impl Node {
     }
 
     pub fn get_child(&self, name: &str) -> Option<&Node> {
-        self.contents.iter().filter_map(|c| match c {
+        self.children().find(|c| c.name == name)
+    }
+
+    /// Iterates over the child elements of this node
+    pub fn children<'a>(&'a self) -> Box<Iterator<Item=&'a Node> + 'a> {
+        Box::new(self.contents.iter().filter_map(|c| match c {
             &Content::Child(ref n) => Some(n),
-            _ => None
-        }).find(|c| c.name == name)
+            _ => None,
+        }))
+    }
+
+    /// Iterates over the child elements of this node that have the given name
+    pub fn children_named<'a>(&'a self, name: &'a str) -> Box<Iterator<Item=&'a Node> + 'a> {
+        Box::new(self.children().filter(move |c| c.name == name))
+    }
+
+    /// Iterates over all of the elements inside of this node (not including itself) in document
+    /// order
+    pub fn descendants<'a>(&'a self) -> Box<Iterator<Item=&'a Node> + 'a> {
+        Box::new(self.walk().filter_map(|item| match item {
+            Item::Start(n) => Some(n),
+            _ => None,
+        }))
+    }
+
+    /// Iterates over all of the elements inside of this node that have the given name
+    pub fn descendants_named<'a>(&'a self, name: &'a str) -> Box<Iterator<Item=&'a Node> + 'a> {
+        Box::new(self.descendants().filter(move |c| c.name == name))
+    }
+
+    /// Walks over the contents of this node (not including itself) in document order
+    pub fn walk(&self) -> Walk {
+        Walk {
+            stack: vec![(self, self.contents.iter())],
+        }
+    }
+
+    /// Finds the elements matching a path of element names relative to this node.
+    ///
+    /// `require/command` finds every `<command>` inside of a `<require>` child, and `*` matches
+    /// any element.
+    pub fn select<'a>(&'a self, path: &str) -> Vec<&'a Node> {
+        let mut nodes = vec![self];
+        for step in path.split("/").filter(|s| s.len() > 0) {
+            nodes = nodes.into_iter()
+                .flat_map(|n| n.children().filter(|c| step == "*" || c.name == step))
+                .collect();
+        }
+        nodes
+    }
+
+    /// Gets values from the elements matching a path.
+    ///
+    /// A path ending in `@attribute` (like `require/command@name`) gives that attribute of each
+    /// element that has it. Otherwise, the text of each element is given.
+    pub fn select_values<'a>(&'a self, path: &str) -> Vec<Cow<'a, str>> {
+        let mut parts = path.splitn(2, "@");
+        let elements = self.select(parts.next().unwrap());
+        match parts.next() {
+            Some(attribute) => elements.into_iter()
+                .filter_map(|n| n.get_attribute(attribute))
+                .map(Into::into)
+                .collect(),
+            None => elements.into_iter()
+                .map(|n| n.text().into())
+                .collect(),
+        }
+    }
+
+    /// Concatenates all of the text inside of this node, including inside of child elements.
+    ///
+    /// `<type>typedef <type>uint32_t</type> <name>VkFlags</name>;</type>` gives
+    /// `typedef uint32_t VkFlags;`.
+    pub fn text(&self) -> String {
+        let mut ret = String::new();
+        for item in self.walk() {
+            if let Item::Text(s) = item {
+                ret.push_str(s);
+            }
+        }
+        ret
     }
 
     pub fn concat_text(&self) -> String {
@@ -53,6 +134,50 @@ impl Node {
     }
 }
 
+/// An item in a document order walk over a node
+#[derive(Debug, Clone, Copy)]
+pub enum Item<'a> {
+    /// The start of an element, before any of its contents
+    Start(&'a Node),
+    /// The end of an element, after all of its contents
+    End(&'a Node),
+    Text(&'a str),
+    Comment(&'a str),
+}
+
+/// Iterator over the contents of a node in document order
+pub struct Walk<'a> {
+    stack: Vec<(&'a Node, linked_list::Iter<'a, Content>)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Item<'a>;
+
+    fn next(&mut self) -> Option<Item<'a>> {
+        let (next, depth) = match self.stack.last_mut() {
+            Some(&mut (_, ref mut contents)) => (contents.next(), self.stack.len()),
+            None => return None,
+        };
+        match next {
+            Some(&Content::Child(ref n)) => {
+                self.stack.push((n, n.contents.iter()));
+                Some(Item::Start(n))
+            },
+            Some(&Content::Text(ref s)) => Some(Item::Text(s.as_str())),
+            Some(&Content::Comment(ref s)) => Some(Item::Comment(s.as_str())),
+            None => {
+                let (n, _) = self.stack.pop().unwrap();
+                // The node being walked over doesn't have a start, so it doesn't get an end either
+                if depth > 1 {
+                    Some(Item::End(n))
+                } else {
+                    None
+                }
+            },
+        }
+    }
+}
+
 impl XmlParse for Node {
     fn parse<It: Iterator<Item=XmlResult<XmlEvent>>>(events: It, name: String, attributes: Vec<OwnedAttribute>) -> ParseResult<Node> {
         let events = XmlContents::new_inside(events);
@@ -76,7 +201,7 @@ impl XmlParse for Node {
                     let node = node_stack.front_mut().unwrap();
                     node.contents.push_back(Content::Child(child));
                 },
-                XmlEvent::Characters(s) => {
+                XmlEvent::Characters(s) | XmlEvent::Whitespace(s) => {
                     let node = node_stack.front_mut().unwrap();
                     node.contents.push_back(Content::Text(s));
                 },
@@ -94,3 +219,75 @@ impl XmlParse for Node {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use xml::reader::{ EventReader, XmlEvent };
+    use ::XmlParse;
+    use super::{ Item, Node };
+
+    fn parse(xml: &str) -> Node {
+        let mut events = EventReader::new(xml.as_bytes()).into_iter()
+            .skip_while(|e| match e {
+                &Ok(XmlEvent::StartElement { .. }) => false,
+                _ => true,
+            });
+        match events.next() {
+            Some(Ok(XmlEvent::StartElement { name, attributes, .. })) => {
+                Node::parse(events, name.local_name, attributes).unwrap()
+            },
+            e => panic!("no root element: {:?}", e),
+        }
+    }
+
+    const FEATURE: &'static str = r#"<feature name="VK_VERSION_1_0">
+        <require comment="Device initialization">
+            <command name="vkCreateInstance"/>
+            <command name="vkDestroyInstance"/>
+            <type name="VkInstance"/>
+        </require>
+        <remove><command name="vkOldCommand"/></remove>
+        <require><command name="vkCreateDevice"/></require>
+    </feature>"#;
+
+    #[test]
+    fn children_by_name() {
+        let node = parse(FEATURE);
+        assert_eq!(node.children().count(), 3);
+        assert_eq!(node.children_named("require").count(), 2);
+        assert_eq!(node.get_child("remove").map(|n| n.children().count()), Some(1));
+    }
+
+    #[test]
+    fn descendants() {
+        let node = parse(FEATURE);
+        let commands: Vec<_> = node.descendants_named("command").filter_map(|n| n.get_attribute("name")).collect();
+        assert_eq!(commands, vec!["vkCreateInstance", "vkDestroyInstance", "vkOldCommand", "vkCreateDevice"]);
+        assert_eq!(node.descendants().count(), 8);
+    }
+
+    #[test]
+    fn paths() {
+        let node = parse(FEATURE);
+        assert_eq!(node.select_values("require/command@name"), vec!["vkCreateInstance", "vkDestroyInstance", "vkCreateDevice"]);
+        assert_eq!(node.select_values("*/command@name").len(), 4);
+        assert_eq!(node.select_values("require@comment"), vec!["Device initialization"]);
+        assert_eq!(node.select("require/type").len(), 1);
+        assert!(node.select("require/member").is_empty());
+    }
+
+    #[test]
+    fn document_order() {
+        let node = parse("<type category=\"define\">#define <name>VK_NULL_HANDLE</name> 0</type>");
+        let items: Vec<String> = node.walk().map(|item| match item {
+            Item::Start(n) => format!("<{}>", n.name),
+            Item::End(n) => format!("</{}>", n.name),
+            Item::Text(s) => s.into(),
+            Item::Comment(s) => format!("<!--{}-->", s),
+        }).collect();
+        assert_eq!(items, vec!["#define ", "<name>", "VK_NULL_HANDLE", "</name>", " 0"]);
+        assert_eq!(node.text(), "#define VK_NULL_HANDLE 0");
+        assert_eq!(node.concat_text(), "#define  0");
+        assert_eq!(parse("<name>vkCreateDevice</name>").select_values(""), vec!["vkCreateDevice"]);
+    }
+}
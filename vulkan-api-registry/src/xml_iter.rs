@@ -1,5 +1,6 @@
 //! Iterator tools for `xml-rs`
 
+use xml::attribute::OwnedAttribute;
 use xml::reader::XmlEvent;
 use xml::reader::Result as XmlResult;
 
@@ -19,6 +20,11 @@ impl<It: Iterator<Item=XmlResult<XmlEvent>>> XmlContents<It> {
         }
     }
 
+    /// Gets the iterator that the contents are read from
+    pub fn inner_mut(&mut self) -> &mut It {
+        &mut self.it
+    }
+
     /// Checks if this XmlContents iterator has terminated (i.e. is outside the scope of the
     /// element that it was reading
     #[inline(always)]
@@ -62,3 +68,67 @@ impl<It: Iterator<Item=XmlResult<XmlEvent>>> XmlIteratorExtensions for It {
         XmlContents::new_inside(self)
     }
 }
+
+/// Checks if a comma-separated `api` list (as found on `api` and `supported` attributes) includes
+/// the given API name
+fn api_list_contains(list: &str, api: &str) -> bool {
+    list.split(",").any(|s| s.trim() == api)
+}
+
+/// Iterator that drops every element (along with its contents) carrying an `api` attribute that
+/// doesn't include the selected API
+pub struct ApiFilter<'a, It: Iterator<Item=XmlResult<XmlEvent>>> {
+    it: It,
+    api: Option<&'a str>,
+    passed_api_specific: bool,
+}
+
+impl<'a, It: Iterator<Item=XmlResult<XmlEvent>>> ApiFilter<'a, It> {
+    /// Creates a new ApiFilter. If `api` is `None`, then no elements are dropped.
+    pub fn new(it: It, api: Option<&'a str>) -> ApiFilter<'a, It> {
+        ApiFilter {
+            it: it,
+            api: api,
+            passed_api_specific: false,
+        }
+    }
+
+    /// Checks if an element with an `api` attribute has been passed along since the last call
+    pub fn take_passed_api_specific(&mut self) -> bool {
+        ::std::mem::replace(&mut self.passed_api_specific, false)
+    }
+
+    fn is_excluded(&self, attributes: &[OwnedAttribute]) -> bool {
+        match self.api {
+            Some(api) => attributes.iter()
+                .find(|attr| attr.name.local_name == "api")
+                .map(|attr| !api_list_contains(attr.value.as_str(), api))
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+}
+
+impl<'a, It: Iterator<Item=XmlResult<XmlEvent>>> Iterator for ApiFilter<'a, It> {
+    type Item = XmlResult<XmlEvent>;
+    fn next(&mut self) -> Option<XmlResult<XmlEvent>> {
+        loop {
+            match self.it.next() {
+                Some(Ok(XmlEvent::StartElement { ref attributes, .. })) if self.is_excluded(attributes) => {},
+                e => {
+                    if let Some(Ok(XmlEvent::StartElement { ref attributes, .. })) = e {
+                        self.passed_api_specific |= attributes.iter().any(|attr| attr.name.local_name == "api");
+                    }
+                    return e;
+                },
+            }
+            // Skip over the contents of the excluded element. Errors are passed along since there's
+            // no way to recover from them anyways.
+            for e in XmlContents::new_inside(&mut self.it) {
+                if let Err(e) = e {
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
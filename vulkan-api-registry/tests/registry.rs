@@ -0,0 +1,1038 @@
+extern crate vulkan_api_registry;
+extern crate xml;
+
+use vulkan_api_registry::*;
+use xml::reader::EventReader;
+
+fn parse_fixture(xml: &str, api: Option<&str>) -> Registry {
+    let include_all = |_: &str| true;
+    let events = EventReader::new(xml.as_bytes()).into_iter();
+    Registry::new(events, api, &include_all, &include_all).unwrap()
+}
+
+fn types_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/types.xml"), Some("vulkan"))
+}
+
+fn enums_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/enums.xml"), Some("vulkan"))
+}
+
+fn commands_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/commands.xml"), Some("vulkan"))
+}
+
+fn extensions_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/extensions.xml"), Some("vulkan"))
+}
+
+fn find_enums<'a>(registry: &'a Registry, name: &str) -> &'a EnumsInfo {
+    registry.enums.iter()
+        .find(|e| e.name.as_ref().map(|s| s.as_str()) == Some(name))
+        .unwrap()
+}
+
+fn find_extension<'a>(registry: &'a Registry, name: &str) -> &'a ExtensionInfo {
+    registry.extensions.iter().find(|e| e.name == name).unwrap()
+}
+
+fn struct_members<'a>(registry: &'a Registry, name: &str) -> Vec<&'a StructMember> {
+    match registry.types.get(name) {
+        Some(&TypeInfo::Struct { ref members, .. }) => members.iter().collect(),
+        Some(&TypeInfo::Union { ref members, .. }) => members.iter().collect(),
+        t => panic!("{} is not a struct or union: {:?}", name, t),
+    }
+}
+
+#[test]
+fn missing_types_is_an_error() {
+    let include_all = |_: &str| true;
+    let events = EventReader::new("<registry><commands/></registry>".as_bytes()).into_iter();
+    assert!(Registry::new(events, None, &include_all, &include_all).is_err());
+}
+
+#[test]
+fn basetypes() {
+    let registry = types_fixture();
+    match registry.types.get("VkDeviceSize") {
+        Some(&TypeInfo::Basetype { ref name, ref ty }) => {
+            assert_eq!(name, "VkDeviceSize");
+            assert_eq!(ty, "uint64_t");
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+}
+
+#[test]
+fn bitmasks() {
+    let registry = types_fixture();
+    match registry.types.get("VkQueueFlags") {
+        Some(&TypeInfo::Bitmask { ref ty, ref requires, .. }) => {
+            assert_eq!(ty, "VkFlags");
+            assert_eq!(requires.iter().collect::<Vec<_>>(), vec!["VkQueueFlagBits"]);
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("VkInstanceCreateFlags") {
+        Some(&TypeInfo::Bitmask { ref requires, .. }) => assert!(requires.is_empty()),
+        t => panic!("unexpected type: {:?}", t),
+    }
+}
+
+#[test]
+fn handles() {
+    let registry = types_fixture();
+    match registry.types.get("VkInstance") {
+        Some(&TypeInfo::Handle { ref parent, ty, .. }) => {
+            assert_eq!(parent, &None);
+            assert_eq!(ty, HandleType::Normal);
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("VkBuffer") {
+        Some(&TypeInfo::Handle { ref parent, ty, .. }) => {
+            assert_eq!(parent.as_ref().map(|s| s.as_str()), Some("VkDevice"));
+            assert_eq!(ty, HandleType::NonDispatchable);
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+}
+
+#[test]
+fn other_categories() {
+    let registry = types_fixture();
+    match registry.types.get("VK_HEADER_VERSION") {
+        Some(&TypeInfo::Define { .. }) => {},
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("VK_DEFINE_HANDLE") {
+        Some(&TypeInfo::Define { .. }) => {},
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("VkStructureType") {
+        Some(&TypeInfo::Enum { .. }) => {},
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("vk_platform") {
+        Some(&TypeInfo::Include(_)) => {},
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("uint32_t") {
+        Some(&TypeInfo::Uncategorized { ref node, .. }) => {
+            assert_eq!(node.get_attribute("requires"), Some("vk_platform"));
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+}
+
+#[test]
+fn define_bodies() {
+    let registry = types_fixture();
+    match registry.types.get("VK_API_VERSION_1_0") {
+        Some(&TypeInfo::Define { ref text, .. }) => {
+            assert_eq!(text, "// Vulkan 1.0 version number\n#define VK_API_VERSION_1_0 VK_MAKE_API_VERSION(0, 1, 0, 0)// Patch version should always be set to 0");
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+    let defines = registry.defines();
+    let names: Vec<_> = defines.iter().map(|d| d.name.as_str()).collect();
+    // Commented out defines and defines of declarations aren't expressions
+    assert_eq!(names, vec!["VK_API_VERSION_1_0", "VK_API_VERSION_MINOR", "VK_HEADER_VERSION", "VK_HEADER_VERSION_COMPLETE", "VK_MAKE_API_VERSION"]);
+    let literal = |v| Expression::Literal(ConstantValue::I32(v));
+    assert_eq!(defines[0].parameters, None);
+    assert_eq!(defines[0].body, Expression::Call("VK_MAKE_API_VERSION".into(), vec![literal(0), literal(1), literal(0), literal(0)]));
+    assert_eq!(defines[2].body, literal(63));
+    assert_eq!(defines[4].parameters, Some(vec!["variant".into(), "major".into(), "minor".into(), "patch".into()]));
+    assert_eq!(defines[4].body.to_string(), "((((((uint32_t)variant) << 29U) | (((uint32_t)major) << 22U)) | (((uint32_t)minor) << 12U)) | ((uint32_t)patch))");
+}
+
+#[test]
+fn funcpointers() {
+    let registry = types_fixture();
+    for name in &["PFN_vkVoidFunction", "PFN_vkAllocationFunction"] {
+        match registry.types.get(*name) {
+            Some(&TypeInfo::Funcpointer { name: ref n, .. }) => assert_eq!(n, name),
+            t => panic!("unexpected type: {:?}", t),
+        }
+    }
+    match registry.types.get("PFN_vkVoidFunction") {
+        Some(&TypeInfo::Funcpointer { ref arguments, ref return_type, .. }) => {
+            assert_eq!(format!("{:?}", return_type), "\"void\"");
+            assert!(arguments.is_empty());
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("PFN_vkAllocationFunction") {
+        Some(&TypeInfo::Funcpointer { ref arguments, ref return_type, .. }) => {
+            assert_eq!(format!("{:?}", return_type), "\"*mut void\"");
+            let arguments: Vec<_> = arguments.iter().map(|&(ref name, ref ty)| format!("{}: {:?}", name, ty)).collect();
+            assert_eq!(arguments, vec!["pUserData: \"*mut void\"", "size: \"size_t\""]);
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+}
+
+#[test]
+fn type_aliases() {
+    let registry = types_fixture();
+    match registry.types.get("VkExtent2DKHR") {
+        Some(&TypeInfo::Alias { ref alias, .. }) => assert_eq!(alias, "VkExtent2D"),
+        t => panic!("unexpected type: {:?}", t),
+    }
+    match registry.types.get("VkQueueFlagsKHR") {
+        Some(&TypeInfo::Alias { ref alias, .. }) => assert_eq!(alias, "VkQueueFlags"),
+        t => panic!("unexpected type: {:?}", t),
+    }
+}
+
+#[test]
+fn struct_members_are_parsed() {
+    let registry = types_fixture();
+    let members = struct_members(&registry, "VkInstanceCreateInfo");
+    let names: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["sType", "pNext", "flags", "pApplicationInfo", "enabledLayerCount", "ppEnabledLayerNames"]);
+
+    let s_type = members[0];
+    assert_eq!(s_type.ty.ty, "VkStructureType");
+    assert_eq!(s_type.values.as_ref().unwrap().iter().collect::<Vec<_>>(), vec!["VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO"]);
+
+    let next = members[1];
+    assert_eq!(next.ty.ty, "void");
+    assert_eq!(next.ty.constness, vec![true]);
+
+    let application_info = members[3];
+    assert!(application_info.optional);
+    assert_eq!(application_info.ty.ty, "VkApplicationInfo");
+
+    let layer_names = members[5];
+    assert_eq!(layer_names.ty.ty, "char");
+    assert_eq!(layer_names.ty.constness, vec![true, true]);
+    assert_eq!(layer_names.len.as_ref().map(|s| s.as_str()), Some("enabledLayerCount,null-terminated"));
+}
+
+#[test]
+fn union_members_are_parsed() {
+    let registry = types_fixture();
+    let members = struct_members(&registry, "VkClearColorValue");
+    let types: Vec<&str> = members.iter().map(|m| m.ty.ty.as_str()).collect();
+    assert_eq!(types, vec!["float", "uint32_t"]);
+}
+
+#[test]
+fn api_constants() {
+    let registry = enums_fixture();
+    let constants = find_enums(&registry, "API Constants");
+    assert_eq!(constants.ty, None);
+    let values: Vec<_> = constants.values.iter().map(|&(ref name, ref value)| (name.as_str(), format!("{:?}", value))).collect();
+    assert_eq!(values, vec![
+        ("VK_MAX_EXTENSION_NAME_SIZE", "Value(256)".to_string()),
+        ("VK_LOD_CLAMP_NONE", "StrValue(\"1000.0F\")".to_string()),
+        ("VK_WHOLE_SIZE", "StrValue(\"(~0ULL)\")".to_string()),
+        ("VK_MAX_EXTENSION_NAME_SIZE_ALIAS", "Alias(\"VK_MAX_EXTENSION_NAME_SIZE\")".to_string()),
+    ]);
+}
+
+#[test]
+fn enum_values() {
+    let registry = enums_fixture();
+    let result = find_enums(&registry, "VkResult");
+    assert_eq!(result.ty, Some(EnumsType::Enum));
+    let values: Vec<_> = result.values.iter().map(|&(ref name, ref value)| match value {
+        &EnumValue::Value(v) => (name.as_str(), v),
+        v => panic!("unexpected value for {}: {:?}", name, v),
+    }).collect();
+    assert_eq!(values, vec![("VK_SUCCESS", 0), ("VK_NOT_READY", 1), ("VK_ERROR_OUT_OF_HOST_MEMORY", -1)]);
+}
+
+#[test]
+fn enum_bitpos() {
+    let registry = enums_fixture();
+    let flag_bits = find_enums(&registry, "VkQueueFlagBits");
+    assert_eq!(flag_bits.ty, Some(EnumsType::Bitmask));
+    assert_eq!(flag_bits.vendor.as_ref().map(|s| s.as_str()), Some("KHR"));
+    let values: Vec<_> = flag_bits.values.iter().map(|&(ref name, ref value)| match value {
+        &EnumValue::Bitpos(bp) => (name.as_str(), Some(bp)),
+        &EnumValue::Alias(ref alias) => {
+            assert_eq!(alias, "VK_QUEUE_GRAPHICS_BIT");
+            (name.as_str(), None)
+        },
+        v => panic!("unexpected value for {}: {:?}", name, v),
+    }).collect();
+    assert_eq!(values, vec![
+        ("VK_QUEUE_GRAPHICS_BIT", Some(0)),
+        ("VK_QUEUE_COMPUTE_BIT", Some(1)),
+        ("VK_QUEUE_TRANSFER_BIT", Some(2)),
+        ("VK_QUEUE_GRAPHICS_BIT_KHR", None),
+    ]);
+}
+
+#[test]
+fn commands() {
+    let registry = commands_fixture();
+    let create_instance = registry.commands.get("vkCreateInstance").unwrap();
+    assert_eq!(create_instance.return_type.ty, "VkResult");
+    assert_eq!(create_instance.successcodes.iter().collect::<Vec<_>>(), vec!["VK_SUCCESS"]);
+    assert_eq!(create_instance.errorcodes.iter().collect::<Vec<_>>(), vec!["VK_ERROR_OUT_OF_HOST_MEMORY", "VK_ERROR_INITIALIZATION_FAILED"]);
+    assert_eq!(create_instance.alias, None);
+    let params: Vec<_> = create_instance.params.iter().map(|p| (p.name.as_str(), p.ty.ty.as_str(), p.ty.constness.clone(), p.optional)).collect();
+    assert_eq!(params, vec![
+        ("pCreateInfo", "VkInstanceCreateInfo", vec![true], false),
+        ("pAllocator", "VkAllocationCallbacks", vec![true], true),
+        ("pInstance", "VkInstance", vec![false], false),
+    ]);
+    let lens: Vec<_> = registry.commands.get("vkEnumeratePhysicalDevices").unwrap().params.iter().map(|p| p.len.as_ref().map(|l| l.as_str())).collect();
+    assert_eq!(lens, vec![None, None, Some("pPhysicalDeviceCount")]);
+    let name = registry.commands.get("vkGetInstanceProcAddr").unwrap().params.iter().last().unwrap();
+    assert_eq!(name.len, Some("null-terminated".into()));
+}
+
+#[test]
+fn command_attributes() {
+    use std::collections::BTreeSet;
+    let registry = commands_fixture();
+    let draw = registry.commands.get("vkCmdDraw").unwrap();
+    assert_eq!(draw.return_type.ty, "void");
+    assert_eq!(draw.queues.iter().collect::<Vec<_>>(), vec!["graphics"]);
+    assert_eq!(draw.renderpass, Some(CommandRenderPassInfo::Inside));
+    assert_eq!(draw.pipeline, Some(CommandPipelineType::Graphics));
+    let levels: BTreeSet<_> = vec![CommandBufferLevel::Primary, CommandBufferLevel::Secondary].into_iter().collect();
+    assert_eq!(draw.cmdbufferlevel, Some(levels));
+    assert!(draw.successcodes.is_empty());
+    assert!(draw.errorcodes.is_empty());
+}
+
+#[test]
+fn command_aliases() {
+    let registry = commands_fixture();
+    let copy = registry.commands.get("vkCmdCopyBuffer2").unwrap();
+    let alias = registry.commands.get("vkCmdCopyBuffer2KHR").unwrap();
+    assert_eq!(alias.name, "vkCmdCopyBuffer2KHR");
+    assert_eq!(alias.alias.as_ref().map(|s| s.as_str()), Some("vkCmdCopyBuffer2"));
+    assert_eq!(alias.renderpass, copy.renderpass);
+    assert_eq!(alias.queues, copy.queues);
+    let param_names = |cmd: &CommandInfo| cmd.params.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+    assert_eq!(param_names(alias), param_names(copy));
+}
+
+#[test]
+fn unknown_command_alias_is_an_error() {
+    let include_all = |_: &str| true;
+    let xml = r#"<registry><types/><commands><command name="vkFooKHR" alias="vkFoo"/></commands></registry>"#;
+    let events = EventReader::new(xml.as_bytes()).into_iter();
+    assert!(Registry::new(events, None, &include_all, &include_all).is_err());
+}
+
+#[test]
+fn features() {
+    let registry = extensions_fixture();
+    assert_eq!(registry.features.len(), 1);
+    let feature = registry.features.front().unwrap();
+    assert_eq!(feature.name, "VK_VERSION_1_0");
+    assert_eq!(feature.api, "vulkan");
+    assert_eq!(feature.number, ApiVersion::new(1, 0));
+    let requirements: Vec<_> = feature.requirements().map(|r| format!("{:?}", r)).collect();
+    assert_eq!(requirements, vec![
+        "Enum(\"VK_MAX_EXTENSION_NAME_SIZE\")",
+        "Type(\"VkInstance\")",
+        "Command(\"vkCreateInstance\")",
+    ]);
+}
+
+#[test]
+fn extension_attributes() {
+    let registry = extensions_fixture();
+    let names: Vec<&str> = registry.extensions.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["VK_KHR_surface", "VK_KHR_swapchain", "VK_KHR_xlib_surface"]);
+
+    let surface = find_extension(&registry, "VK_KHR_surface");
+    assert_eq!(surface.number, Some(1));
+    assert_eq!(surface.author.as_ref().map(|s| s.as_str()), Some("KHR"));
+    assert_eq!(surface.contact.as_ref().map(|s| s.as_str()), Some("Jane Doe @jdoe"));
+    match surface.ty {
+        ExtensionType::Instance { .. } => {},
+        ref t => panic!("unexpected extension type: {:?}", t),
+    }
+
+    let swapchain = find_extension(&registry, "VK_KHR_swapchain");
+    match swapchain.ty {
+        ExtensionType::Device { .. } => {},
+        ref t => panic!("unexpected extension type: {:?}", t),
+    }
+    assert_eq!(swapchain.requires.iter().collect::<Vec<_>>(), vec!["VK_KHR_surface"]);
+    assert_eq!(swapchain.protect, None);
+
+    let xlib = find_extension(&registry, "VK_KHR_xlib_surface");
+    assert_eq!(xlib.protect.as_ref().map(|s| s.as_str()), Some("VK_USE_PLATFORM_XLIB_KHR"));
+}
+
+#[test]
+fn disabled_extensions() {
+    let registry = parse_fixture(include_str!("fixtures/extensions.xml"), None);
+    let disabled = find_extension(&registry, "VK_NV_disabled");
+    match disabled.ty {
+        ExtensionType::Disabled => {},
+        ref t => panic!("unexpected extension type: {:?}", t),
+    }
+}
+
+#[test]
+fn extension_enum_offsets() {
+    let registry = extensions_fixture();
+    let extended: Vec<_> = find_extension(&registry, "VK_KHR_swapchain").requirements().filter_map(|r| match r {
+        &Requirement::EnumExtension { ref name, ref extends, ref value } => Some((name.as_str(), extends.as_str(), format!("{:?}", value))),
+        _ => None,
+    }).collect();
+    assert_eq!(extended, vec![
+        ("VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR", "VkStructureType", "Offset { offset: 0, negated: false, extnumber: None }".to_string()),
+        ("VK_ERROR_OUT_OF_DATE_KHR", "VkResult", "Offset { offset: 4, negated: true, extnumber: None }".to_string()),
+        ("VK_SWAPCHAIN_CREATE_SPLIT_INSTANCE_BIND_REGIONS_BIT_KHR", "VkSwapchainCreateFlagBitsKHR", "Value(Bitpos(0))".to_string()),
+        ("VK_IMAGE_LAYOUT_PRESENT_SRC_KHR", "VkImageLayout", "Value(StrValue(\"1000001002\"))".to_string()),
+        ("VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_ALIAS_KHR", "VkStructureType", "Alias(\"VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR\")".to_string()),
+    ]);
+}
+
+#[test]
+fn extension_constants() {
+    let registry = extensions_fixture();
+    let constants: Vec<_> = find_extension(&registry, "VK_KHR_surface").requirements().filter_map(|r| match r {
+        &Requirement::Constant { ref name, ref value, .. } => Some((name.as_str(), format!("{:?}", value))),
+        _ => None,
+    }).collect();
+    assert_eq!(constants, vec![
+        ("VK_KHR_SURFACE_SPEC_VERSION", "StrValue(\"25\")".to_string()),
+        ("VK_KHR_SURFACE_EXTENSION_NAME", "StrValue(\"\\\"VK_KHR_surface\\\"\")".to_string()),
+    ]);
+}
+
+#[test]
+fn typed_constants() {
+    let registry = enums_fixture();
+    assert_eq!(registry.constants().unwrap(), vec![
+        ("VK_MAX_EXTENSION_NAME_SIZE", ConstantValue::U32(256)),
+        ("VK_LOD_CLAMP_NONE", ConstantValue::F32(1000.0)),
+        ("VK_WHOLE_SIZE", ConstantValue::U64(!0)),
+        ("VK_MAX_EXTENSION_NAME_SIZE_ALIAS", ConstantValue::U32(256)),
+    ]);
+}
+
+#[test]
+fn untyped_constants_are_typed_by_their_expressions() {
+    let registry = cheader_fixture();
+    let constants = registry.constants().unwrap();
+    let find = |name: &str| constants.iter().find(|&&(n, _)| n == name).map(|&(_, ref v)| v.clone());
+    assert_eq!(find("VK_MAX_EXTENSION_NAME_SIZE"), Some(ConstantValue::I32(256)));
+    assert_eq!(find("VK_LOD_CLAMP_NONE"), Some(ConstantValue::F32(1000.0)));
+    assert_eq!(find("VK_WHOLE_SIZE"), Some(ConstantValue::U64(!0)));
+
+    let registry = extensions_fixture();
+    let constants = registry.constants().unwrap();
+    assert!(constants.contains(&("VK_KHR_SURFACE_SPEC_VERSION", ConstantValue::I32(25))));
+    assert!(constants.contains(&("VK_KHR_SURFACE_EXTENSION_NAME", ConstantValue::Str("VK_KHR_surface".into()))));
+}
+
+#[test]
+fn api_selection() {
+    let xml = include_str!("fixtures/api.xml");
+    let vulkan = parse_fixture(xml, Some("vulkan"));
+    let vulkansc = parse_fixture(xml, Some("vulkansc"));
+    let member_names = |registry: &Registry| struct_members(registry, "VkPipelineCacheCreateInfo").iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+    assert_eq!(member_names(&vulkan), vec!["initialDataSize", "pInitialData"]);
+    assert_eq!(member_names(&vulkansc), vec!["initialDataSize", "pInitialData", "scOnlyCount"]);
+
+    assert!(vulkan.commands.contains_key("vkTrimCommandPool"));
+    assert!(!vulkansc.commands.contains_key("vkTrimCommandPool"));
+
+    let feature_names = |registry: &Registry| registry.features.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+    assert_eq!(feature_names(&vulkan), vec!["VK_VERSION_1_0"]);
+    assert_eq!(feature_names(&vulkansc), vec!["VK_VERSION_1_0", "VKSC_VERSION_1_0"]);
+
+    let extension_names = |registry: &Registry| registry.extensions.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+    assert_eq!(extension_names(&vulkan), vec!["VK_KHR_surface", "VK_KHR_maintenance1"]);
+    assert_eq!(extension_names(&vulkansc), vec!["VK_KHR_surface", "VK_EXT_application_parameters"]);
+}
+
+#[test]
+fn no_api_selection_keeps_everything() {
+    let registry = parse_fixture(include_str!("fixtures/api.xml"), None);
+    assert_eq!(registry.features.len(), 2);
+    assert_eq!(registry.extensions.len(), 3);
+    assert!(registry.commands.contains_key("vkTrimCommandPool"));
+}
+
+#[test]
+fn valid_registry_has_no_problems() {
+    let registry = types_fixture();
+    assert_eq!(registry.validate(), vec![]);
+}
+
+#[test]
+fn validation_problems() {
+    let registry = parse_fixture(include_str!("fixtures/invalid.xml"), Some("vulkan"));
+    let problems: Vec<String> = registry.validate().iter().map(|e| e.to_string()).collect();
+    assert_eq!(problems, vec![
+        "VkBrokenInfo::pValues has len countt which isn't a member of VkBrokenInfo",
+        "member VkBrokenInfo::undefined has undefined type VkUndefined",
+        "bitmask VkMissingFlags requires missing enum VkMissingFlagBits",
+        "handle VkOrphan has unknown parent VkNowhere",
+        "parameter mystery of vkDoSomething has undefined type VkMystery",
+        "extension VK_EXT_first requires unknown extension VK_EXT_nonexistent",
+        "VkResult: VK_ALSO_NOT_READY has the same value (1) as VK_NOT_READY",
+        "VkResult: VK_ERROR_SECOND_EXT has the same value (-1000000000) as VK_ERROR_FIRST_EXT",
+    ]);
+}
+
+fn providers_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/providers.xml"), Some("vulkan"))
+}
+
+#[test]
+fn version_numbers() {
+    let registry = providers_fixture();
+    let versions: Vec<ApiVersion> = registry.features.iter().map(|f| f.number).collect();
+    assert_eq!(versions, vec![ApiVersion::new(1, 0), ApiVersion::new(1, 1)]);
+    assert!(ApiVersion::new(1, 0) < ApiVersion::new(1, 1));
+    assert_eq!(ApiVersion::new(1, 1).to_string(), "1.1");
+    assert!("1".parse::<ApiVersion>().is_err());
+}
+
+#[test]
+fn require_block_depends() {
+    let registry = providers_fixture();
+    let device_group = find_extension(&registry, "VK_KHR_device_group");
+    let depends: Vec<_> = device_group.require_blocks.iter().map(|b| b.depends.clone()).collect();
+    assert_eq!(depends, vec![
+        None,
+        Some("VK_KHR_surface".to_string()),
+        Some("VK_VERSION_1_1+VK_KHR_swapchain".to_string()),
+    ]);
+}
+
+#[test]
+fn providers() {
+    let registry = providers_fixture();
+    let providers: Vec<Provider> = registry.providers_of("vkTrimCommandPool").into_iter().collect();
+    assert_eq!(providers, vec![
+        Provider::Version { version: ApiVersion::new(1, 1), depends: None },
+        Provider::Extension { name: "VK_KHR_device_group".into(), depends: None },
+        Provider::Extension { name: "VK_KHR_maintenance1".into(), depends: None },
+    ]);
+
+    let providers: Vec<Provider> = registry.providers_of("vkGetDeviceGroupPresentCapabilitiesKHR").into_iter().collect();
+    assert_eq!(providers, vec![
+        Provider::Version { version: ApiVersion::new(1, 1), depends: Some("VK_KHR_surface".into()) },
+        Provider::Extension { name: "VK_KHR_device_group".into(), depends: Some("VK_KHR_surface".into()) },
+    ]);
+
+    let index = registry.providers();
+    assert_eq!(index.get("VK_STRUCTURE_TYPE_DEVICE_GROUP_INFO").map(|p| p.len()), Some(1));
+    assert!(index.get("vkDestroySurfaceKHR").unwrap().iter().all(|p| !p.is_core()));
+    assert!(registry.providers_of("vkNotAThing").is_empty());
+}
+
+#[test]
+fn minimum_versions() {
+    let registry = providers_fixture();
+    assert_eq!(registry.minimum_version("vkCreateInstance"), Some(ApiVersion::new(1, 0)));
+    assert_eq!(registry.minimum_version("vkTrimCommandPool"), Some(ApiVersion::new(1, 1)));
+    assert_eq!(registry.minimum_version("vkGetDeviceGroupPresentCapabilitiesKHR"), None);
+    assert_eq!(registry.minimum_version("vkDestroySurfaceKHR"), None);
+}
+
+#[test]
+fn provider_index() {
+    let registry = providers_fixture();
+    let index = registry.providers();
+    assert_eq!(index.providers_of("vkTrimCommandPool"), registry.providers_of("vkTrimCommandPool"));
+    assert_eq!(index.minimum_version("vkCreateInstance"), Some(ApiVersion::new(1, 0)));
+    assert_eq!(index.minimum_version("vkTrimCommandPool"), Some(ApiVersion::new(1, 1)));
+    assert_eq!(index.minimum_version("vkGetDeviceGroupPresentCapabilitiesKHR"), None);
+    assert!(index.providers_of("vkNotAThing").is_empty());
+}
+
+#[test]
+fn extension_enum_values() {
+    let registry = providers_fixture();
+    let value = registry.features.iter().flat_map(|f| f.requirements()).filter_map(|r| match r {
+        &Requirement::EnumExtension { ref name, ref value, .. } if name == "VK_STRUCTURE_TYPE_DEVICE_GROUP_INFO" => value.value(None),
+        _ => None,
+    }).next();
+    assert_eq!(value, Some(1000059000));
+}
+
+fn promotion_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/promotion.xml"), Some("vulkan"))
+}
+
+#[test]
+fn extension_metadata() {
+    let registry = promotion_fixture();
+    let maintenance1 = find_extension(&registry, "VK_KHR_maintenance1");
+    assert_eq!(maintenance1.promotedto.as_ref().map(|s| s.as_str()), Some("VK_VERSION_1_1"));
+    assert_eq!(maintenance1.supported.iter().collect::<Vec<_>>(), vec!["vulkan"]);
+    assert_eq!(maintenance1.ratified.iter().collect::<Vec<_>>(), vec!["vulkan"]);
+    assert!(!maintenance1.provisional);
+
+    let portability = find_extension(&registry, "VK_KHR_portability_subset");
+    assert!(portability.provisional);
+    assert_eq!(portability.platform.as_ref().map(|s| s.as_str()), Some("provisional"));
+    assert_eq!(portability.specialuse.iter().collect::<Vec<_>>(), vec!["cadsupport", "glemulation"]);
+    assert!(portability.ratified.is_empty());
+
+    let indexing = find_extension(&registry, "VK_EXT_descriptor_indexing");
+    assert_eq!(indexing.ratified.iter().collect::<Vec<_>>(), vec!["vulkan", "vulkansc"]);
+}
+
+#[test]
+fn promotion_chains() {
+    let registry = promotion_fixture();
+    assert_eq!(registry.promotion_chain("VK_KHR_maintenance1"), vec!["VK_VERSION_1_1"]);
+    assert_eq!(registry.promotion_chain("VK_EXT_descriptor_indexing_draft"), vec!["VK_EXT_descriptor_indexing", "VK_VERSION_1_2"]);
+    assert!(registry.promotion_chain("VK_EXT_debug_report").is_empty());
+    assert!(registry.promotion_chain("VK_NOT_an_extension").is_empty());
+
+    assert_eq!(registry.promoted_version("VK_KHR_maintenance1"), Some(ApiVersion::new(1, 1)));
+    assert_eq!(registry.promoted_version("VK_EXT_descriptor_indexing_draft"), Some(ApiVersion::new(1, 2)));
+    assert_eq!(registry.promoted_version("VK_EXT_debug_report"), None);
+}
+
+#[test]
+fn deprecations() {
+    let registry = promotion_fixture();
+    assert_eq!(registry.deprecation("VK_KHR_maintenance1"), None);
+    assert_eq!(registry.deprecation("VK_EXT_debug_report"), Some(Deprecation::DeprecatedBy(Some("VK_EXT_debug_utils".into()))));
+    assert_eq!(registry.deprecation("VK_NV_glsl_shader"), Some(Deprecation::DeprecatedBy(None)));
+    assert_eq!(registry.deprecation("VK_AMD_negative_viewport_height"), Some(Deprecation::ObsoletedBy(Some("VK_KHR_maintenance1".into()))));
+
+    let messages: Vec<String> = ["VK_EXT_debug_report", "VK_NV_glsl_shader", "VK_AMD_negative_viewport_height"].iter()
+        .map(|e| registry.deprecation(e).unwrap().to_string())
+        .collect();
+    assert_eq!(messages, vec![
+        "deprecated by VK_EXT_debug_utils",
+        "deprecated without replacement",
+        "obsoleted by VK_KHR_maintenance1",
+    ]);
+}
+
+fn depends_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/depends.xml"), Some("vulkan"))
+}
+
+fn resolve(registry: &Registry, major: u32, minor: u32, desired: &[&str]) -> Result<Vec<String>, ResolveError> {
+    registry.resolve_extensions(ApiVersion::new(major, minor), desired.iter().cloned())
+}
+
+#[test]
+fn extension_dependencies() {
+    let registry = depends_fixture();
+    let dependencies = |name: &str| find_extension(&registry, name).dependencies(&registry.dialect).unwrap().map(|d| d.to_string());
+    assert_eq!(dependencies("VK_KHR_surface"), None);
+    assert_eq!(dependencies("VK_KHR_maintenance3").unwrap(), "VK_KHR_get_physical_device_properties2,VK_VERSION_1_1");
+    // Older registries use `requires` and `requiresCore` instead of `depends`
+    assert_eq!(dependencies("VK_KHR_display_swapchain").unwrap(), "VK_VERSION_1_0+VK_KHR_swapchain+VK_KHR_display");
+    assert_eq!(find_extension(&registry, "VK_KHR_display_swapchain").requires_core, Some(ApiVersion::new(1, 0)));
+}
+
+#[test]
+fn resolve_extensions() {
+    let registry = depends_fixture();
+    assert_eq!(resolve(&registry, 1, 0, &["VK_KHR_swapchain"]).unwrap(), vec!["VK_KHR_surface", "VK_KHR_swapchain"]);
+    // Satisfied by the core version, so nothing else is needed
+    assert_eq!(resolve(&registry, 1, 1, &["VK_KHR_maintenance3"]).unwrap(), vec!["VK_KHR_maintenance3"]);
+    assert_eq!(resolve(&registry, 1, 0, &["VK_KHR_maintenance3"]).unwrap(), vec!["VK_KHR_get_physical_device_properties2", "VK_KHR_maintenance3"]);
+    assert_eq!(resolve(&registry, 1, 0, &["VK_KHR_buffer_device_address"]).unwrap(), vec![
+        "VK_KHR_get_physical_device_properties2",
+        "VK_KHR_device_group_creation",
+        "VK_KHR_device_group",
+        "VK_KHR_buffer_device_address",
+    ]);
+    // Shared dependencies are only listed once
+    assert_eq!(resolve(&registry, 1, 0, &["VK_KHR_display_swapchain", "VK_KHR_swapchain"]).unwrap(), vec![
+        "VK_KHR_surface",
+        "VK_KHR_swapchain",
+        "VK_KHR_display",
+        "VK_KHR_display_swapchain",
+    ]);
+    assert_eq!(resolve(&registry, 1, 0, &["VK_EXT_cycle_a"]).unwrap(), vec!["VK_EXT_cycle_b", "VK_EXT_cycle_a"]);
+}
+
+#[test]
+fn resolve_extension_errors() {
+    let registry = depends_fixture();
+    assert_eq!(resolve(&registry, 1, 0, &["VK_KHR_not_real"]), Err(ResolveError::UnknownName("VK_KHR_not_real".into())));
+
+    let error = resolve(&registry, 1, 1, &["VK_KHR_ray_query"]).unwrap_err();
+    assert_eq!(error, ResolveError::Unsatisfied {
+        extension: "VK_KHR_ray_query".into(),
+        depends: "VK_VERSION_1_2+VK_KHR_swapchain".parse().unwrap(),
+        causes: vec![ResolveError::VersionTooLow {
+            feature: "VK_VERSION_1_2".into(),
+            version: ApiVersion::new(1, 2),
+            target: ApiVersion::new(1, 1),
+        }],
+    });
+    assert_eq!(error.to_string(), "VK_KHR_ray_query depends on VK_VERSION_1_2+VK_KHR_swapchain, which can't be satisfied (VK_VERSION_1_2 needs version 1.2, but the target version is 1.1)");
+
+    // Extensions that aren't supported by the selected API aren't in the registry at all
+    let error = resolve(&registry, 1, 3, &["VK_EXT_needs_disabled"]).unwrap_err();
+    assert_eq!(error.to_string(), "VK_EXT_needs_disabled depends on VK_EXT_disabled, which can't be satisfied (VK_EXT_disabled is not a known extension or version)");
+}
+
+fn openxr_fixture() -> Registry {
+    let include_all = |_: &str| true;
+    let xml = include_str!("fixtures/openxr.xml");
+    let events = EventReader::new(xml.as_bytes()).into_iter();
+    Registry::with_dialect(events, &Dialect::openxr(), Some("openxr"), &include_all, &include_all).unwrap()
+}
+
+#[test]
+fn openxr_registry() {
+    let registry = openxr_fixture();
+    assert!(registry.validate().is_empty(), "{:?}", registry.validate());
+    for name in &["XrInstance", "XrSession"] {
+        match registry.types.get(*name) {
+            Some(&TypeInfo::Handle { ty, .. }) => assert_eq!(ty, HandleType::NonDispatchable),
+            t => panic!("unexpected type: {:?}", t),
+        }
+    }
+    match registry.types.get("XrInstanceCreateFlags") {
+        Some(&TypeInfo::Bitmask { ref ty, ref requires, .. }) => {
+            assert_eq!(ty, "XrFlags64");
+            assert_eq!(requires.iter().collect::<Vec<_>>(), vec!["XrInstanceCreateFlagBits"]);
+        },
+        t => panic!("unexpected type: {:?}", t),
+    }
+    let get_system = registry.commands.get("xrGetSystem").unwrap();
+    assert_eq!(get_system.params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["instance", "getInfo", "systemId"]);
+    assert_eq!(registry.minimum_version("xrDestroySession"), Some(ApiVersion::new(1, 0)));
+
+    let extensions: Vec<_> = registry.extensions.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(extensions, vec!["XR_KHR_composition_layer_depth", "XR_EXT_hand_tracking"]);
+    let hand_tracking = find_extension(&registry, "XR_EXT_hand_tracking");
+    assert_eq!(hand_tracking.dependencies(&registry.dialect).unwrap().unwrap().to_string(), "XR_VERSION_1_0+XR_KHR_composition_layer_depth");
+    assert_eq!(registry.resolve_extensions(ApiVersion::new(1, 0), vec!["XR_EXT_hand_tracking"]).unwrap(), vec!["XR_KHR_composition_layer_depth", "XR_EXT_hand_tracking"]);
+    let values: Vec<_> = hand_tracking.requirements().filter_map(|r| match r {
+        &Requirement::EnumExtension { ref value, .. } => value.value(hand_tracking.number),
+        _ => None,
+    }).collect();
+    assert_eq!(values, vec![-1000051001]);
+}
+
+#[test]
+fn unknown_handle_macro_is_an_error() {
+    let include_all = |_: &str| true;
+    let xml = include_str!("fixtures/openxr.xml");
+    let events = EventReader::new(xml.as_bytes()).into_iter();
+    assert!(Registry::new(events, Some("openxr"), &include_all, &include_all).is_err());
+}
+
+/// Describes everything in a registry, in a stable order
+fn describe(registry: &Registry) -> Vec<String> {
+    let mut types: Vec<String> = registry.types.iter().map(|(name, t)| format!("{}: {:?}", name, t)).collect();
+    types.sort();
+    let mut commands: Vec<String> = registry.commands.values().map(|c| format!("{:?}", c)).collect();
+    commands.sort();
+    types.into_iter()
+        .chain(commands)
+        .chain(registry.enums.iter().map(|e| format!("{:?}", e)))
+        .chain(registry.features.iter().map(|f| format!("{:?}", f)))
+        .chain(registry.extensions.iter().map(|e| format!("{:?}", e)))
+        .collect()
+}
+
+fn round_trip(registry: &Registry, api: Option<&str>) -> Registry {
+    let mut xml = Vec::new();
+    registry.write(&mut xml).unwrap();
+    let include_all = |_: &str| true;
+    let events = EventReader::new(xml.as_slice()).into_iter();
+    Registry::with_dialect(events, &registry.dialect, api, &include_all, &include_all).unwrap()
+}
+
+#[test]
+fn write_round_trips() {
+    let vulkan = Some("vulkan");
+    let fixtures = vec![
+        (types_fixture(), vulkan),
+        (enums_fixture(), vulkan),
+        (commands_fixture(), vulkan),
+        (extensions_fixture(), vulkan),
+        (providers_fixture(), vulkan),
+        (promotion_fixture(), vulkan),
+        (depends_fixture(), vulkan),
+        (cheader_fixture(), vulkan),
+        (video_fixture(), vulkan),
+        (parse_fixture(include_str!("fixtures/api.xml"), Some("vulkansc")), Some("vulkansc")),
+        (parse_fixture(include_str!("fixtures/api.xml"), None), None),
+    ];
+    for (registry, api) in fixtures {
+        assert_eq!(describe(&round_trip(&registry, api)), describe(&registry));
+    }
+    let registry = openxr_fixture();
+    assert_eq!(describe(&round_trip(&registry, Some("openxr"))), describe(&registry));
+}
+
+#[test]
+fn written_xml() {
+    let registry = commands_fixture();
+    let mut xml = Vec::new();
+    registry.write(&mut xml).unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains("<param optional=\"true\">const <type>VkAllocationCallbacks</type>* <name>pAllocator</name></param>"), "{}", xml);
+}
+
+fn cheader_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/cheader.xml"), Some("vulkan"))
+}
+
+#[test]
+fn arrays_and_bitfields() {
+    let registry = cheader_fixture();
+    let members = struct_members(&registry, "VkTransformMatrixKHR");
+    assert_eq!(members[0].ty.array, vec!["3", "4"]);
+    let members = struct_members(&registry, "VkExtensionProperties");
+    assert_eq!(members[0].ty.array, vec!["VK_MAX_EXTENSION_NAME_SIZE"]);
+    assert!(members[1].ty.array.is_empty());
+    let bitfields: Vec<_> = struct_members(&registry, "VkAccelerationStructureInstanceKHR").iter().map(|m| m.bitfield).collect();
+    assert_eq!(bitfields, vec![None, Some(24), Some(8), Some(24), Some(8), None]);
+    let blend_constants = registry.commands.get("vkCmdSetBlendConstants").unwrap().params.iter().last().unwrap();
+    assert_eq!(blend_constants.ty.array, vec!["4"]);
+}
+
+#[test]
+fn c_header_compiles() {
+    let registry = cheader_fixture();
+    assert!(registry.validate().is_empty(), "{:?}", registry.validate());
+    let mut header = Vec::new();
+    registry.write_c_header(&mut header).unwrap();
+    let header = String::from_utf8(header).unwrap();
+    // Platform specific extensions are left out, like in vulkan_core.h
+    assert!(!header.contains("VkXlibSurfaceCreateInfoKHR "), "{}", header);
+    assert!(!header.contains("vkCreateXlibSurfaceKHR"), "{}", header);
+    assert!(header.contains("VK_STRUCTURE_TYPE_XLIB_SURFACE_CREATE_INFO_KHR = 1000004000,"), "{}", header);
+    assert!(header.contains("static const VkAccessFlagBits2 VK_ACCESS_2_SHADER_SAMPLED_READ_BIT = 0x100000000ULL;"), "{}", header);
+
+    let checks = r#"
+#include "generated.h"
+_Static_assert(sizeof(VkResult) == 4, "enums are 32 bits");
+_Static_assert(VK_ERROR_SURFACE_LOST_KHR == -1000000000, "negative extension enum");
+_Static_assert(VK_STRUCTURE_TYPE_MEMORY_BARRIER_2 == 1000314000, "extnumber");
+_Static_assert(VK_STRUCTURE_TYPE_MEMORY_BARRIER_2_KHR == VK_STRUCTURE_TYPE_MEMORY_BARRIER_2, "enum alias");
+_Static_assert(sizeof(VkTransformMatrixKHR) == 48, "2D arrays");
+_Static_assert(sizeof(VkAccelerationStructureInstanceKHR) == 64, "bitfields");
+_Static_assert(sizeof(VkExtensionProperties) == 260, "arrays sized by constants");
+_Static_assert(sizeof(VkClearColorValue) == 16, "unions");
+_Static_assert(sizeof(VkMemoryBarrier2KHR) == sizeof(VkMemoryBarrier2), "struct aliases");
+_Static_assert(VK_API_VERSION_1_0 == 4194304, "version defines");
+_Static_assert(VK_HEADER_VERSION_COMPLETE == 4206842, "nested version defines");
+_Static_assert(VK_API_VERSION_MINOR(VK_HEADER_VERSION_COMPLETE) == 3, "version macros");
+static PFN_vkCreateInstance create_instance = vkCreateInstance;
+static PFN_vkDebugReportCallbackEXT debug_callback;
+int main(void) {
+    (void) create_instance;
+    (void) debug_callback;
+    return 0;
+}
+"#;
+    compile_c_header("core", &header, checks);
+}
+
+/// Compiles `checks` against the generated header, which it can include as `generated.h`
+fn compile_c_header(name: &str, header: &str, checks: &str) {
+    use std::fs::File;
+    use std::io::Write;
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("vulkan-api-registry-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("generated.h")).unwrap().write_all(header.as_bytes()).unwrap();
+    File::create(dir.join("checks.c")).unwrap().write_all(checks.as_bytes()).unwrap();
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    let output = Command::new(compiler)
+        .args(&["-std=c11", "-Wall", "-Werror", "-fsyntax-only", "checks.c"])
+        .current_dir(&dir)
+        .output()
+        .expect("a C compiler is needed to check the generated header");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(output.status.success(), "{}\n{}", String::from_utf8_lossy(&output.stderr), header);
+}
+
+#[test]
+fn merged_enum_values() {
+    let registry = cheader_fixture();
+    let values = registry.enum_values("VkStructureType");
+    assert!(values.contains(&("VK_STRUCTURE_TYPE_MEMORY_BARRIER_2", EnumValue::Value(1000314000))));
+    assert!(values.contains(&("VK_STRUCTURE_TYPE_XLIB_SURFACE_CREATE_INFO_KHR", EnumValue::Value(1000004000))));
+    assert_eq!(values.last(), Some(&("VK_STRUCTURE_TYPE_MEMORY_BARRIER_2_KHR", EnumValue::Alias("VK_STRUCTURE_TYPE_MEMORY_BARRIER_2".into()))));
+    let results = registry.enum_values("VkResult");
+    assert!(results.contains(&("VK_ERROR_SURFACE_LOST_KHR", EnumValue::Value(-1000000000))));
+}
+
+#[test]
+fn portable_types() {
+    let registry = cheader_fixture();
+    let types = registry.portable_types();
+    assert!(types.contains("VkInstance"));
+    assert!(types.contains("uint32_t"));
+    assert!(!types.contains("VkXlibSurfaceCreateInfoKHR"));
+    assert!(!types.contains("Display"));
+    assert!(!types.contains("VK_API_VERSION_1_0"));
+}
+
+fn parse_sources(sources: &[&str], api: Option<&str>) -> ParseResult<Registry> {
+    let include_all = |_: &str| true;
+    let sources = sources.iter().map(|xml| EventReader::new(xml.as_bytes()).into_iter());
+    Registry::from_sources(sources, &Dialect::vulkan(), api, &include_all, &include_all)
+}
+
+#[test]
+fn repeated_blocks_accumulate() {
+    let registry = parse_fixture(include_str!("fixtures/overlay.xml"), Some("vulkan"));
+    assert!(registry.types.contains_key("VkResult"));
+    assert!(registry.types.contains_key("VkAcmeLayerSettingsEXT"));
+}
+
+#[test]
+fn merged_sources() {
+    let sources = [
+        include_str!("fixtures/commands.xml"),
+        include_str!("fixtures/extensions.xml"),
+        include_str!("fixtures/overlay.xml"),
+    ];
+    let registry = parse_sources(&sources, Some("vulkan")).unwrap();
+    assert!(registry.commands.contains_key("vkCreateInstance"));
+    assert!(registry.commands.contains_key("vkAcmeSetVerbosityEXT"));
+    assert!(registry.types.contains_key("VkStructureType"));
+    assert!(registry.types.contains_key("VkAcmeLayerSettingsEXT"));
+    let extensions: Vec<_> = registry.extensions.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(extensions, vec!["VK_KHR_surface", "VK_KHR_swapchain", "VK_KHR_xlib_surface", "VK_EXT_acme_layer"]);
+    assert_eq!(registry.features.len(), 1);
+}
+
+#[test]
+fn placeholders_are_replaced() {
+    let placeholder = r#"<registry><types><type requires="vk_video/vulkan_video_codec_h264std.h" name="StdVideoH264ProfileIdc"/></types></registry>"#;
+    let definition = r#"<registry><types><type name="StdVideoH264ProfileIdc" category="enum"/></types></registry>"#;
+    for sources in &[[placeholder, definition], [definition, placeholder]] {
+        let registry = parse_sources(sources, Some("vulkan")).unwrap();
+        match registry.types.get("StdVideoH264ProfileIdc") {
+            Some(&TypeInfo::Enum { .. }) => {},
+            t => panic!("unexpected type: {:?}", t),
+        }
+    }
+}
+
+#[test]
+fn conflicting_definitions_are_errors() {
+    let first = r#"<registry><types><type category="struct" name="VkFoo"><member><type>uint32_t</type> <name>a</name></member></type></types></registry>"#;
+    let second = r#"<registry><types><type category="struct" name="VkFoo"><member><type>uint64_t</type> <name>a</name></member></type></types></registry>"#;
+    match parse_sources(&[first, second], Some("vulkan")) {
+        Err(ParseError::Custom(ref message)) => assert!(message.contains("type VkFoo"), "{}", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert!(parse_sources(&[first, first], Some("vulkan")).is_ok());
+    // Selecting an API doesn't matter for definitions that aren't specific to one
+    match parse_sources(&[first, second], None) {
+        Err(ParseError::Custom(ref message)) => assert!(message.contains("type VkFoo"), "{}", message),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    // Without an API selection, the variants for different APIs all get through and the last wins
+    let variant = r#"<registry><types><type category="struct" name="VkFoo" api="vulkansc"><member><type>uint64_t</type> <name>a</name></member></type></types></registry>"#;
+    let registry = parse_sources(&[first, variant], None).unwrap();
+    assert_eq!(struct_members(&registry, "VkFoo")[0].ty.ty, "uint64_t");
+    assert_eq!(struct_members(&parse_sources(&[first, variant], Some("vulkan")).unwrap(), "VkFoo")[0].ty.ty, "uint32_t");
+
+    let commands = r#"<registry><types/><commands><command><proto><type>void</type> <name>vkFoo</name></proto></command></commands></registry>"#;
+    let other_commands = r#"<registry><commands><command><proto><type>VkResult</type> <name>vkFoo</name></proto></command></commands></registry>"#;
+    for api in &[Some("vulkan"), None] {
+        match parse_sources(&[commands, other_commands], *api) {
+            Err(ParseError::Custom(ref message)) => assert!(message.contains("command vkFoo"), "{}", message),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
+
+#[test]
+fn sources_are_filtered() {
+    let include_feature = |name: &str| name != "VK_VERSION_1_0";
+    let include_extension = |name: &str| name == "VK_KHR_surface";
+    let sources = [include_str!("fixtures/extensions.xml"), include_str!("fixtures/overlay.xml")]
+        .iter()
+        .map(|xml| EventReader::new(xml.as_bytes()).into_iter());
+    let registry = Registry::from_sources(sources, &Dialect::vulkan(), Some("vulkan"), &include_feature, &include_extension).unwrap();
+    assert!(registry.features.is_empty());
+    let extensions: Vec<_> = registry.extensions.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(extensions, vec!["VK_KHR_surface"]);
+    // Types and commands aren't filtered
+    assert!(registry.types.contains_key("VkAcmeLayerSettingsEXT"));
+}
+
+fn video_fixture() -> Registry {
+    parse_fixture(include_str!("fixtures/video.xml"), Some("vulkan"))
+}
+
+#[test]
+fn video_registry() {
+    let registry = video_fixture();
+    assert!(registry.validate().is_empty(), "{:?}", registry.validate());
+    // The std codec headers are described as extensions, but they aren't registered
+    let h264 = find_extension(&registry, "vulkan_video_codec_h264std");
+    assert_eq!(h264.number, None);
+    assert_eq!(h264.ty, ExtensionType::Disabled);
+    assert!(h264.is_supported_by("vulkan"));
+
+    let members = struct_members(&registry, "StdVideoH264ScalingLists");
+    assert_eq!(members[2].ty.array, vec!["STD_VIDEO_H264_SCALING_LIST_4X4_NUM_LISTS", "STD_VIDEO_H264_SCALING_LIST_4X4_NUM_ELEMENTS"]);
+    let bitfields: Vec<_> = struct_members(&registry, "StdVideoH264SpsFlags").iter().map(|m| m.bitfield).collect();
+    assert_eq!(bitfields, vec![Some(1); 5]);
+
+    let constants = registry.constants().unwrap();
+    assert!(constants.contains(&("STD_VIDEO_H264_SCALING_LIST_8X8_NUM_ELEMENTS", ConstantValue::I32(64))), "{:?}", constants);
+    assert!(constants.contains(&("STD_VIDEO_H264_NO_REFERENCE_PICTURE", ConstantValue::I32(0xFF))), "{:?}", constants);
+    let defines: Vec<_> = registry.defines().into_iter().map(|d| d.name).collect();
+    assert_eq!(defines, vec!["VK_MAKE_VIDEO_STD_VERSION", "VK_STD_VULKAN_VIDEO_CODEC_H264_DECODE_API_VERSION_1_0_0"]);
+}
+
+#[test]
+fn video_c_header_compiles() {
+    // vk.xml only has placeholders for the std types, and they're filled in from video.xml
+    let registry = parse_sources(&[include_str!("fixtures/cheader.xml"), include_str!("fixtures/video.xml")], Some("vulkan")).unwrap();
+    assert!(registry.validate().is_empty(), "{:?}", registry.validate());
+    let mut header = Vec::new();
+    registry.write_c_header(&mut header).unwrap();
+    let header = String::from_utf8(header).unwrap();
+    let checks = r#"
+#include <stddef.h>
+#include "generated.h"
+_Static_assert(sizeof(StdVideoH264SpsFlags) == 4, "bitfields");
+_Static_assert(sizeof(StdVideoH264ScalingLists) == 484, "arrays sized by constants");
+_Static_assert(offsetof(StdVideoH264SequenceParameterSet, offset_for_non_ref_pic) == 16, "enum members");
+_Static_assert(sizeof(StdVideoH264ChromaFormatIdc) == 4, "enums are 32 bits");
+_Static_assert(VK_STD_VULKAN_VIDEO_CODEC_H264_DECODE_SPEC_VERSION == VK_MAKE_VIDEO_STD_VERSION(1, 0, 0), "version defines");
+int main(void) {
+    return 0;
+}
+"#;
+    compile_c_header("video", &header, checks);
+}
+
+#[test]
+fn constructors() {
+    let xml = include_str!("fixtures/types.xml");
+    let expected = describe(&types_fixture());
+    assert_eq!(describe(&Registry::from_str(xml, Some("vulkan")).unwrap()), expected);
+    assert_eq!(describe(&Registry::from_reader(std::io::Cursor::new(xml), Some("vulkan")).unwrap()), expected);
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/types.xml");
+    assert_eq!(describe(&Registry::from_path(&path, Some("vulkan")).unwrap()), expected);
+    match Registry::from_path(path.with_file_name("missing.xml"), Some("vulkan")) {
+        Err(ParseError::Io(ref e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[cfg(feature = "embedded")]
+#[test]
+fn embedded_registry() {
+    let registry = Registry::embedded(Some("vulkan")).unwrap();
+    assert!(registry.types.contains_key("VkStructureType"));
+    assert!(!registry.features.is_empty());
+}
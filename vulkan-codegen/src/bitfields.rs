@@ -0,0 +1,88 @@
+//! Bitfield members, which Rust has no equivalent of
+
+use std::collections::LinkedList;
+use std::fmt;
+use vulkan_api_registry::StructMember;
+use structs::MemberExt;
+
+/// Lays out bitfield members the way C compilers do: runs of bitfields are packed in to units of
+/// their type, starting from the least significant bit, and a bitfield that doesn't fit in the
+/// rest of a unit starts a new one.
+pub fn bitfields<'a>(members: &'a LinkedList<StructMember>) -> Vec<Bitfield<'a>> {
+    let mut bitfields: Vec<Bitfield<'a>> = Vec::new();
+    let mut units = 0;
+    let mut used: Option<u32> = None;
+    for member in members {
+        let width = match member.bitfield {
+            Some(width) => width as u32,
+            None => {
+                used = None;
+                continue;
+            },
+        };
+        let unit_bits = match member.ty.ty.as_str() {
+            "uint8_t" | "int8_t" => 8,
+            "uint16_t" | "int16_t" => 16,
+            "uint64_t" | "int64_t" => 64,
+            _ => 32,
+        };
+        let offset = match used {
+            Some(used) if used + width <= unit_bits => used,
+            _ => {
+                units += 1;
+                0
+            },
+        };
+        used = Some(offset + width);
+        bitfields.push(Bitfield {
+            name: member.clean_name(),
+            unit: units,
+            unit_bits,
+            offset,
+            width,
+        });
+    }
+    bitfields
+}
+
+/// A bitfield member, which is stored in the `_bitfield_{unit}` member of the generated struct
+pub struct Bitfield<'a> {
+    pub name: &'a str,
+    pub unit: usize,
+    pub unit_bits: u32,
+    pub offset: u32,
+    pub width: u32,
+}
+
+impl<'a> Bitfield<'a> {
+    /// Gets the unsigned integer type of the unit that this bitfield is stored in, which its
+    /// accessors also use, since bitfields of flags can't be shifted and masked
+    pub fn unit_type(&self) -> &'static str {
+        match self.unit_bits {
+            8 => "u8",
+            16 => "u16",
+            64 => "u64",
+            _ => "u32",
+        }
+    }
+
+    /// Writes a getter named `name`, and a setter named `set_{name}`
+    pub fn write_accessors<W: fmt::Write>(&self, f: &mut W, name: &str) -> fmt::Result {
+        let ty = self.unit_type();
+        let mask = if self.width >= 64 {
+            u64::MAX
+        } else {
+            (1u64 << self.width) - 1
+        };
+        // Shifting by 0 would be a no-op
+        let (unit, mask_in_unit, value_in_unit) = if self.offset > 0 {
+            (format!("(self._bitfield_{} >> {})", self.unit, self.offset), format!("({:#x} << {})", mask, self.offset), format!("((value & {:#x}) << {})", mask, self.offset))
+        } else {
+            (format!("self._bitfield_{}", self.unit), format!("{:#x}", mask), format!("(value & {:#x})", mask))
+        };
+        write!(f, "    #[inline(always)]\n    pub fn {}(&self) -> {} {{\n", name, ty)?;
+        write!(f, "        {} & {:#x}\n    }}\n", unit, mask)?;
+        write!(f, "    #[inline(always)]\n    pub fn set_{}(&mut self, value: {}) {{\n", name, ty)?;
+        write!(f, "        self._bitfield_{0} = (self._bitfield_{0} & !{1}) | {2};\n    }}\n", self.unit, mask_in_unit, value_in_unit)
+    }
+}
@@ -0,0 +1,595 @@
+//! Dispatch tables of function pointers to the commands, and wrappers around them
+
+use std;
+use std::collections::{ BTreeSet, HashMap };
+use std::fmt;
+use vulkan_api_registry::{ CommandInfo, HandleType, ParameterInfo, Provider, Registry, TypeInfo };
+use names::{ clean_c_name, snake_case };
+use structs::StructInfo;
+
+/// How a command wrapper takes (or returns) one of the command's parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParamKind {
+    /// Passed through as it is
+    Value,
+    /// The length of some slices, which is taken from the first of them that isn't optional
+    Count,
+    /// `const T*` with a length, taken as `&[T]`
+    Slice,
+    /// `T*` with a length, taken as `&mut [T]`
+    SliceMut,
+    /// `const T name[N]`, taken as `&[T; N]`
+    Array,
+    /// Null-terminated `const char*`, taken as `&CStr`
+    CStr,
+    /// `const T*`, taken as `&T`
+    Ref,
+    /// `T*`, taken as `&mut T`
+    RefMut,
+    /// The last parameter, which the command writes a value to that's returned
+    Out,
+    /// `uint32_t* pCount` right before an array that's filled in with the two-call idiom
+    EnumerateCount,
+    /// The array after an `EnumerateCount`, which is returned as a `Vec`
+    Enumerate,
+    /// Anything else, which is passed as a raw pointer
+    Pointer,
+}
+
+/// Converts an integer expression from one Rust type to another, if they're different
+fn cast(expression: String, from: &str, to: &str) -> String {
+    if from == to {
+        expression
+    } else {
+        format!("{} as {}", expression, to)
+    }
+}
+
+/// Converts the length of a slice to the type of the count that's passed with it, panicking if it
+/// doesn't fit in a `uint32_t`
+fn count(len: String, to: &str) -> String {
+    match to {
+        "u32" => format!("count_u32({})", len),
+        to => cast(len, "usize", to),
+    }
+}
+
+/// Checks if a type is a struct that starts with `sType`, which has to be set before a command
+/// can write to it
+fn has_structure_type(registry: &Registry, ty: &str) -> bool {
+    match registry.types.get(ty) {
+        Some(TypeInfo::Struct { members, .. }) => members.front().is_some_and(|m| m.name == "sType"),
+        _ => false,
+    }
+}
+
+fn param_kinds(registry: &Registry, command: &CommandInfo) -> Vec<ParamKind> {
+    let params: Vec<&ParameterInfo> = command.params.iter().collect();
+    let mut kinds: Vec<ParamKind> = params.iter().map(|p| {
+        let constness = p.ty.constness.first() == Some(&true);
+        let is_void = p.ty.ty == "void";
+        let len = p.len.as_deref();
+        if !p.ty.array.is_empty() {
+            ParamKind::Array
+        } else if p.ty.constness.is_empty() {
+            ParamKind::Value
+        } else if p.ty.constness.len() > 1 {
+            ParamKind::Pointer
+        } else if len == Some("null-terminated") && constness && p.ty.ty == "char" {
+            ParamKind::CStr
+        } else if len.is_some_and(|len| params.iter().any(|c| c.name == len && c.ty.constness.is_empty())) {
+            if constness { ParamKind::Slice } else { ParamKind::SliceMut }
+        } else if len.is_some() || is_void {
+            ParamKind::Pointer
+        } else if constness {
+            ParamKind::Ref
+        } else {
+            ParamKind::RefMut
+        }
+    }).collect();
+    let n = params.len();
+    if n >= 2 && kinds[n - 2] == ParamKind::RefMut {
+        let (count, array) = (params[n - 2], params[n - 1]);
+        if array.len.as_ref() == Some(&count.name) && array.ty.constness == [false] && !has_structure_type(registry, &array.ty.ty) {
+            kinds[n - 2] = ParamKind::EnumerateCount;
+            kinds[n - 1] = ParamKind::Enumerate;
+        }
+    }
+    if n >= 1 && !params[n - 1].optional {
+        let last = params[n - 1];
+        let writes_pointer = last.ty.ty == "void" && last.ty.constness == [false, false];
+        if (kinds[n - 1] == ParamKind::RefMut && !has_structure_type(registry, &last.ty.ty)) || writes_pointer {
+            kinds[n - 1] = ParamKind::Out;
+        }
+    }
+    // A count can only be left out if there's a slice that has to be given
+    for i in 0..n {
+        if kinds[i] != ParamKind::Value {
+            continue;
+        }
+        let users: Vec<usize> = (0..n).filter(|&j| {
+            (kinds[j] == ParamKind::Slice || kinds[j] == ParamKind::SliceMut) && params[j].len.as_ref() == Some(&params[i].name)
+        }).collect();
+        if users.iter().any(|&j| !params[j].optional) {
+            kinds[i] = ParamKind::Count;
+        } else {
+            for j in users {
+                kinds[j] = ParamKind::Pointer;
+            }
+        }
+    }
+    kinds
+}
+
+/// Gets the raw Rust type of a parameter, with pointers but without lifetimes
+fn raw_type(registry: &Registry, param: &ParameterInfo) -> String {
+    let base = clean_c_name(param.ty.ty.as_str());
+    if !param.ty.array.is_empty() {
+        return format!("*const {}", base);
+    }
+    if param.optional && param.ty.constness.is_empty() && is_dispatchable_handle(registry, &param.ty.ty) {
+        return format!("Option<{}>", base);
+    }
+    // The first constness is of the innermost pointer's target
+    param.ty.constness.iter().fold(String::from(base), |ty, &constness| {
+        format!("{} {}", if constness { "*const" } else { "*mut" }, ty)
+    })
+}
+
+fn is_handle(registry: &Registry, ty: &str) -> bool {
+    matches!(registry.types.get(ty), Some(&TypeInfo::Handle { .. }))
+}
+
+fn is_dispatchable_handle(registry: &Registry, ty: &str) -> bool {
+    match registry.types.get(ty) {
+        Some(&TypeInfo::Handle { ty, .. }) => ty != HandleType::NonDispatchable,
+        _ => false,
+    }
+}
+
+/// Gets a command's raw parameter list and return type, i.e. `(device: VkDevice) -> VkResult`
+fn raw_signature(registry: &Registry, command: &CommandInfo) -> String {
+    let params: Vec<String> = command.params.iter().map(|p| format!("{}: {}", &p.name, raw_type(registry, p))).collect();
+    let mut signature = format!("({})", params.join(", "));
+    if command.return_type.ty != "void" || !command.return_type.constness.is_empty() {
+        signature.push_str(" -> ");
+        signature.push_str(&raw_type(registry, &ParameterInfo {
+            name: String::new(),
+            ty: command.return_type.clone(),
+            optional: false,
+            len: None,
+        }));
+    }
+    signature
+}
+
+/// Declares a command as it's exported by the loader
+fn raw_command_declaration(registry: &Registry, command: &CommandInfo) -> String {
+    format!("    pub fn {}{};\n", &command.name, raw_signature(registry, command))
+}
+
+/// What a command wrapper calls
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CallTarget {
+    /// The function exported by the loader, which the wrapper is a free function around
+    Exported,
+    /// A function pointer in the dispatch table that the wrapper is a method of
+    Table,
+}
+
+/// Writes a wrapper for a command that takes slices and references instead of pointers and counts,
+/// returns what it writes through its last pointers, and turns `VkResult`s in to `Result`s.
+fn write_command_wrapper<W: fmt::Write>(f: &mut W, registry: &Registry, lifetime_types: &BTreeSet<&str>, command: &CommandInfo, target: CallTarget) -> fmt::Result {
+    let params: Vec<&ParameterInfo> = command.params.iter().collect();
+    let kinds = param_kinds(registry, command);
+    let with_lifetime = |ty: &str| if lifetime_types.contains(ty) {
+        format!("{}<'a>", clean_c_name(ty))
+    } else {
+        String::from(clean_c_name(ty))
+    };
+    let element_type = |p: &ParameterInfo| if p.ty.ty == "void" {
+        String::from("u8")
+    } else {
+        with_lifetime(&p.ty.ty)
+    };
+    let optional = |p: &ParameterInfo, ty: String| if p.optional {
+        format!("Option<{}>", ty)
+    } else {
+        ty
+    };
+    let mut arguments: Vec<String> = Vec::new();
+    let mut setup: Vec<String> = Vec::new();
+    let mut call: Vec<String> = Vec::new();
+    let mut first_call: Vec<String> = Vec::new();
+    let mut values: Vec<(String, String)> = Vec::new();
+    // The indices in `values` of what the command writes through `Out` parameters
+    let mut outputs: Vec<usize> = Vec::new();
+    let mut enumerated: Option<(String, String)> = None;
+    for (i, (&p, &kind)) in params.iter().zip(kinds.iter()).enumerate() {
+        let name = snake_case(&p.name);
+        let (argument, first) = match kind {
+            ParamKind::Value => {
+                let ty = with_lifetime(&p.ty.ty);
+                if p.optional && is_handle(registry, &p.ty.ty) {
+                    arguments.push(format!("{}: Option<{}>", &name, ty));
+                    // The raw commands take optional dispatchable handles as `Option`s already
+                    if is_dispatchable_handle(registry, &p.ty.ty) {
+                        (name, None)
+                    } else {
+                        (format!("{}.unwrap_or_else(NullableHandle::null)", &name), None)
+                    }
+                } else {
+                    arguments.push(format!("{}: {}", &name, ty));
+                    (name, None)
+                }
+            },
+            ParamKind::Count => {
+                let users: Vec<usize> = (0..params.len())
+                    .filter(|&j| (kinds[j] == ParamKind::Slice || kinds[j] == ParamKind::SliceMut) && params[j].len.as_ref() == Some(&p.name))
+                    .collect();
+                let given = users.iter().find(|&&j| !params[j].optional).map(|&j| snake_case(&params[j].name)).unwrap();
+                for user in users.iter().filter(|&&j| snake_case(&params[j].name) != given).map(|&j| params[j]) {
+                    if user.optional {
+                        setup.push(format!("if let Some(s) = {} {{ assert_eq!(s.len(), {}.len()); }}", snake_case(&user.name), &given));
+                    } else {
+                        setup.push(format!("assert_eq!({}.len(), {}.len());", snake_case(&user.name), &given));
+                    }
+                }
+                (count(format!("{}.len()", &given), clean_c_name(&p.ty.ty)), None)
+            },
+            ParamKind::Slice | ParamKind::SliceMut => {
+                let mutability = if kind == ParamKind::Slice { "" } else { "mut " };
+                let (as_ptr, null, pointer) = if kind == ParamKind::Slice {
+                    ("as_ptr", "null", "*const")
+                } else {
+                    ("as_mut_ptr", "null_mut", "*mut")
+                };
+                arguments.push(format!("{}: {}", &name, optional(p, format!("&'a {}[{}]", mutability, element_type(p)))));
+                let mut argument = if p.optional {
+                    format!("{}.map_or(ptr::{}(), |s| s.{}())", &name, null, as_ptr)
+                } else {
+                    format!("{}.{}()", &name, as_ptr)
+                };
+                if p.ty.ty == "void" {
+                    argument = format!("{} as {} c_void", argument, pointer);
+                }
+                (argument, None)
+            },
+            ParamKind::Array => {
+                let sizes: String = p.ty.array.iter().map(|size| format!("; {}]", size)).collect();
+                arguments.push(format!("{}: &'a {}{}{}", &name, "[".repeat(p.ty.array.len()), clean_c_name(&p.ty.ty), sizes));
+                if p.ty.array.len() > 1 {
+                    (format!("{}.as_ptr() as *const {}", &name, clean_c_name(&p.ty.ty)), None)
+                } else {
+                    (format!("{}.as_ptr()", &name), None)
+                }
+            },
+            ParamKind::CStr => {
+                arguments.push(format!("{}: {}", &name, optional(p, String::from("&'a CStr"))));
+                if p.optional {
+                    (format!("{}.map_or(ptr::null(), |s| s.as_ptr())", &name), None)
+                } else {
+                    (format!("{}.as_ptr()", &name), None)
+                }
+            },
+            ParamKind::Ref | ParamKind::RefMut => {
+                let (mutability, null, pointer) = if kind == ParamKind::Ref {
+                    ("", "null", "*const")
+                } else {
+                    ("mut ", "null_mut", "*mut")
+                };
+                arguments.push(format!("{}: {}", &name, optional(p, format!("&'a {}{}", mutability, with_lifetime(&p.ty.ty)))));
+                if p.optional {
+                    (format!("{}.map_or(ptr::{}(), |r| r as {} _)", &name, null, pointer), None)
+                } else {
+                    (name, None)
+                }
+            },
+            ParamKind::Out => {
+                let ty = if p.ty.constness.len() > 1 {
+                    // The command writes a pointer (i.e. vkMapMemory's ppData)
+                    p.ty.constness[..p.ty.constness.len() - 1].iter().fold(with_lifetime(&p.ty.ty), |ty, &constness| {
+                        format!("{} {}", if constness { "*const" } else { "*mut" }, ty)
+                    })
+                } else {
+                    with_lifetime(&p.ty.ty)
+                };
+                setup.push(format!("let mut {} = MaybeUninit::uninit();", &name));
+                outputs.push(values.len());
+                values.push((ty, format!("{}.assume_init()", &name)));
+                (format!("{}.as_mut_ptr()", &name), None)
+            },
+            ParamKind::EnumerateCount => {
+                (format!("&mut {}", &name), Some(format!("&mut {}", &name)))
+            },
+            ParamKind::Enumerate => {
+                let count = snake_case(&params[i - 1].name);
+                enumerated = Some((name.clone(), format!("let mut {}: {} = 0;", &count, clean_c_name(&params[i - 1].ty.ty))));
+                values.push((format!("Vec<{}>", element_type(p)), name.clone()));
+                let argument = if p.ty.ty == "void" {
+                    format!("{}.as_mut_ptr() as *mut c_void", &name)
+                } else {
+                    format!("{}.as_mut_ptr()", &name)
+                };
+                (argument, Some(String::from("ptr::null_mut()")))
+            },
+            ParamKind::Pointer => {
+                arguments.push(format!("{}: {}", &name, raw_type(registry, p)));
+                (name, None)
+            },
+        };
+        first_call.push(first.unwrap_or_else(|| argument.clone()));
+        call.push(argument);
+    }
+
+    let returns_result = command.return_type.ty == "VkResult" && command.return_type.constness.is_empty();
+    if !returns_result && (command.return_type.ty != "void" || !command.return_type.constness.is_empty()) {
+        let ty = raw_type(registry, &ParameterInfo {
+            name: String::new(),
+            ty: command.return_type.clone(),
+            optional: false,
+            len: None,
+        });
+        values.insert(0, (ty, String::from("value")));
+        for output in &mut outputs {
+            *output += 1;
+        }
+    }
+    // Success codes other than VK_SUCCESS are passed along with the value
+    let other_successes: Vec<&str> = command.successcodes.iter()
+        .map(|code| code.as_str())
+        .filter(|&code| code != "VK_SUCCESS" && !(enumerated.is_some() && code == "VK_INCOMPLETE"))
+        .collect();
+    // Commands don't write their outputs when they return something like VK_TIMEOUT or
+    // VK_NOT_READY, so the outputs are only returned with the codes that they're written with
+    if returns_result && other_successes.iter().any(|&code| code != "VK_SUBOPTIMAL_KHR") {
+        let written: Vec<String> = std::iter::once("VK_SUCCESS").chain(other_successes.iter().cloned().filter(|&code| code == "VK_SUBOPTIMAL_KHR"))
+            .map(|code| format!("VkResult::{}", code))
+            .collect();
+        for &i in &outputs {
+            let (ref mut ty, ref mut expression) = values[i];
+            *ty = format!("Option<{}>", ty);
+            *expression = format!("match result {{ {} => Some({}), _ => None }}", written.join(" | "), expression);
+        }
+    }
+    let (mut value_type, mut value) = match values.len() {
+        0 => (String::from("()"), String::from("()")),
+        1 => values[0].clone(),
+        _ => {
+            let types: Vec<&str> = values.iter().map(|v| v.0.as_str()).collect();
+            let expressions: Vec<&str> = values.iter().map(|v| v.1.as_str()).collect();
+            (format!("({})", types.join(", ")), format!("({})", expressions.join(", ")))
+        },
+    };
+    if returns_result && !other_successes.is_empty() {
+        if values.is_empty() {
+            value_type = String::from("VkResult");
+            value = String::from("result");
+        } else {
+            value_type = format!("({}, VkResult)", value_type);
+            value = format!("({}, result)", value);
+        }
+    }
+    let successes: Vec<String> = std::iter::once("VK_SUCCESS").chain(other_successes.iter().cloned())
+        .map(|code| format!("VkResult::{}", code))
+        .collect();
+    let successes = successes.join(" | ");
+
+    writeln!(f, "/// Wrapper around `{}`", &command.name)?;
+    if target == CallTarget::Table {
+        arguments.insert(0, String::from("&self"));
+        setup.insert(0, format!("let fp = self.{0}.expect(\"{0} isn't loaded\");", &command.name));
+    }
+    write!(f, "pub unsafe fn {}<'a>({})", snake_case(&command.name), arguments.join(", "))?;
+    if returns_result {
+        write!(f, " -> Result<{}, VkResult>", &value_type)?;
+    } else if !values.is_empty() {
+        write!(f, " -> {}", &value_type)?;
+    }
+    f.write_str(" {\n")?;
+    for statement in &setup {
+        writeln!(f, "    {}", statement)?;
+    }
+    let raw_call = |arguments: &[String]| match target {
+        CallTarget::Exported => format!("{}({})", &command.name, arguments.join(", ")),
+        CallTarget::Table => format!("fp({})", arguments.join(", ")),
+    };
+    match enumerated {
+        Some((ref array, ref count_declaration)) if returns_result => {
+            f.write_str("    loop {\n")?;
+            writeln!(f, "        {}", count_declaration)?;
+            writeln!(f, "        match {} {{", raw_call(&first_call))?;
+            writeln!(f, "            {} => {{}},", &successes)?;
+            f.write_str("            e => return Err(e),\n        }\n")?;
+            let count = cast(snake_case(&params[params.len() - 2].name), clean_c_name(&params[params.len() - 2].ty.ty), "usize");
+            writeln!(f, "        let mut {} = Vec::with_capacity({});", array, &count)?;
+            writeln!(f, "        let result = {};", raw_call(&call))?;
+            f.write_str("        match result {\n")?;
+            writeln!(f, "            {} => {{", &successes)?;
+            writeln!(f, "                {}.set_len({});", array, &count)?;
+            write!(f, "                return Ok({});\n            }},\n", &value)?;
+            f.write_str("            // The number of elements changed between the calls\n")?;
+            f.write_str("            VkResult::VK_INCOMPLETE => {},\n")?;
+            f.write_str("            e => return Err(e),\n        }\n    }\n")?;
+        },
+        Some((ref array, ref count_declaration)) => {
+            let count = cast(snake_case(&params[params.len() - 2].name), clean_c_name(&params[params.len() - 2].ty.ty), "usize");
+            writeln!(f, "    {}", count_declaration)?;
+            writeln!(f, "    {};", raw_call(&first_call))?;
+            writeln!(f, "    let mut {} = Vec::with_capacity({});", array, &count)?;
+            writeln!(f, "    {};", raw_call(&call))?;
+            writeln!(f, "    {}.set_len({});", array, &count)?;
+            writeln!(f, "    {}", &value)?;
+        },
+        None if returns_result => {
+            writeln!(f, "    let result = {};", raw_call(&call))?;
+            f.write_str("    match result {\n")?;
+            writeln!(f, "        {} => Ok({}),", &successes, &value)?;
+            f.write_str("        e => Err(e),\n    }\n")?;
+        },
+        None if values.len() == 1 && values[0].1 == "value" => {
+            writeln!(f, "    {}", raw_call(&call))?;
+        },
+        None => {
+            if values.first().is_some_and(|v| v.1 == "value") {
+                writeln!(f, "    let value = {};", raw_call(&call))?;
+            } else {
+                writeln!(f, "    {};", raw_call(&call))?;
+            }
+            if !values.is_empty() {
+                writeln!(f, "    {}", &value)?;
+            }
+        },
+    }
+    f.write_str("}\n")
+}
+
+/// The types that wrappers have to give a lifetime to
+fn lifetime_types(registry: &Registry) -> BTreeSet<&str> {
+    registry.types.iter().filter(|&(_, info)| match *info {
+        TypeInfo::Handle { .. } => true,
+        TypeInfo::Struct { .. } => {
+            StructInfo::of(registry, info).unwrap().has_lifetime()
+        },
+        _ => false,
+    }).map(|(name, _)| name.as_str()).collect()
+}
+
+/// Which dispatch table a command is loaded in to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CommandLevel {
+    /// Commands that are loaded without an instance
+    Entry,
+    /// Commands dispatched on an instance or physical device
+    Instance,
+    /// Commands dispatched on a device or one of its children (i.e. a queue or command buffer)
+    Device,
+}
+
+impl CommandLevel {
+    /// Decides a command's level from the handle type of its first parameter
+    fn of(registry: &Registry, command: &CommandInfo) -> CommandLevel {
+        let mut ty = match command.params.front() {
+            Some(param) if param.ty.constness.is_empty() && is_dispatchable_handle(registry, &param.ty.ty) => param.ty.ty.as_str(),
+            _ => return CommandLevel::Entry,
+        };
+        loop {
+            if ty == "VkDevice" {
+                return CommandLevel::Device;
+            }
+            ty = match registry.types.get(ty) {
+                Some(&TypeInfo::Handle { parent: Some(ref parent), .. }) => parent.as_str(),
+                _ => return CommandLevel::Instance,
+            };
+        }
+    }
+
+    fn table_name(self) -> &'static str {
+        match self {
+            CommandLevel::Entry => "EntryFns",
+            CommandLevel::Instance => "InstanceFns",
+            CommandLevel::Device => "DeviceFns",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            CommandLevel::Entry => "The commands that are loaded without an instance, like `vkCreateInstance`",
+            CommandLevel::Instance => "The commands that are dispatched on an instance or physical device",
+            CommandLevel::Device => "The commands that are dispatched on a device or one of its children, which are loaded with\n/// `vkGetDeviceProcAddr` so that calling them skips the loader's trampolines",
+        }
+    }
+}
+
+/// Writes a table of function pointers to commands, with a method wrapping each of them
+fn write_dispatch_table<W: fmt::Write>(f: &mut W, registry: &Registry, lifetime_types: &BTreeSet<&str>, level: CommandLevel, commands: &[(&CommandInfo, Vec<&str>)]) -> fmt::Result {
+    let table = level.table_name();
+    write!(f, "/// {}\n#[derive(Clone, Copy)]\npub struct {} {{\n", level.description(), table)?;
+    for &(command, _) in commands {
+        writeln!(f, "    pub {}: Option<unsafe extern \"system\" fn{}>,", &command.name, raw_signature(registry, command))?;
+    }
+    write!(f, "}}\n\nimpl {} {{\n", table)?;
+    f.write_str("    /// Loads each command with `f`, which is given the names of the command's aliases when it\n")?;
+    f.write_str("    /// doesn't find the command under its own name\n")?;
+    writeln!(f, "    pub unsafe fn load_with<F: FnMut(&CStr) -> PFN_vkVoidFunction>(mut f: F) -> {} {{", table)?;
+    writeln!(f, "        {} {{", table)?;
+    for &(command, ref aliases) in commands {
+        let names: Vec<String> = std::iter::once(command.name.as_str()).chain(aliases.iter().cloned())
+            .map(|name| format!("b\"{}\\0\"", name))
+            .collect();
+        writeln!(f, "            {}: mem::transmute(load(&mut f, &[{}])),", &command.name, names.join(", "))?;
+    }
+    f.write_str("        }\n    }\n")?;
+    for &(command, _) in commands {
+        let mut wrapper = String::new();
+        write_command_wrapper(&mut wrapper, registry, lifetime_types, command, CallTarget::Table)?;
+        f.write_str("\n")?;
+        for line in wrapper.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+    }
+    f.write_str("}\n")
+}
+
+/// Gets the commands that don't need a platform's headers, other than aliases, sorted by name,
+/// along with the names of their aliases
+fn available_commands(registry: &Registry) -> (Vec<&CommandInfo>, HashMap<&str, Vec<&str>>) {
+    let providers = registry.providers();
+    let is_available = |name: &str| providers.get(name).is_some_and(|providers| providers.iter().any(|provider| match provider {
+        &Provider::Version { .. } => true,
+        Provider::Extension { name, .. } => registry.extensions.iter()
+            .find(|extension| &extension.name == name)
+            .is_some_and(|extension| extension.protect.is_none() && extension.is_supported_by("vulkan")),
+    }));
+    // vkGetInstanceProcAddr is declared by hand in ffi.rs, and it's what the tables are loaded with
+    let mut commands: Vec<&CommandInfo> = registry.commands.values()
+        .filter(|command| command.alias.is_none() && command.name != "vkGetInstanceProcAddr" && is_available(&command.name))
+        .collect();
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut aliases: HashMap<&str, Vec<&str>> = HashMap::new();
+    for command in registry.commands.values().filter(|command| is_available(&command.name)) {
+        if let Some(ref alias) = command.alias {
+            aliases.entry(alias.as_str()).or_default().push(command.name.as_str());
+        }
+    }
+    for names in aliases.values_mut() {
+        names.sort();
+    }
+    (commands, aliases)
+}
+
+/// Writes the dispatch tables that every command that doesn't need a platform's headers can be
+/// loaded in to
+pub fn write_commands<W: fmt::Write>(f: &mut W, registry: &Registry) -> fmt::Result {
+    let (commands, aliases) = available_commands(registry);
+    let lifetime_types = lifetime_types(registry);
+    for &level in &[CommandLevel::Entry, CommandLevel::Instance, CommandLevel::Device] {
+        let table: Vec<(&CommandInfo, Vec<&str>)> = commands.iter()
+            .filter(|command| CommandLevel::of(registry, command) == level)
+            .map(|&command| (command, aliases.get(command.name.as_str()).cloned().unwrap_or_else(Vec::new)))
+            .collect();
+        if level != CommandLevel::Entry {
+            f.write_str("\n")?;
+        }
+        write_dispatch_table(f, registry, &lifetime_types, level, &table)?;
+    }
+    Ok(())
+}
+
+/// Writes the core commands that the loader exports, along with wrappers for them, which are only
+/// used when linking to the loader
+pub fn write_linked_commands<W: fmt::Write>(f: &mut W, registry: &Registry) -> fmt::Result {
+    let providers = registry.providers();
+    let is_core = |name: &str| providers.get(name).is_some_and(|providers| providers.iter().any(|provider| matches!(provider, &Provider::Version { .. })));
+    let (commands, _) = available_commands(registry);
+    let lifetime_types = lifetime_types(registry);
+    f.write_str("#[link(name = \"vulkan\")]\nextern \"system\" {\n")?;
+    for command in commands.iter().filter(|command| is_core(&command.name)) {
+        f.write_str(&raw_command_declaration(registry, command))?;
+    }
+    f.write_str("}\n")?;
+    for command in commands.iter().filter(|command| is_core(&command.name)) {
+        write_command_wrapper(f, registry, &lifetime_types, command, CallTarget::Exported)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! What to generate, and how
+
+use std::collections::BTreeSet;
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// How the members of the safe structs (and their bitfield accessors) are named
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamingStyle {
+    /// As they're named in the registry (i.e. `pApplicationInfo`), except for Rust keywords
+    Registry,
+    /// In snake case, without the `p` prefixes of pointers (i.e. `application_info`)
+    Rust,
+}
+
+impl Default for NamingStyle {
+    #[inline]
+    fn default() -> NamingStyle {
+        NamingStyle::Registry
+    }
+}
+
+impl FromStr for NamingStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<NamingStyle, String> {
+        match s {
+            "registry" => Ok(NamingStyle::Registry),
+            "rust" => Ok(NamingStyle::Rust),
+            s => Err(format!("unknown naming style {} (expected registry or rust)", s)),
+        }
+    }
+}
+
+/// One of the pieces of generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Output {
+    /// The raw types, API constants and defines
+    Types,
+    /// Tests of the raw types' layouts against the C headers
+    LayoutTests,
+    /// The safe structs, with their builder methods and `Extends` impls
+    Structs,
+    /// Tests of the safe structs' layouts against the raw structs
+    StructLayoutTests,
+    /// The dispatch tables, with wrappers for each command
+    Commands,
+    /// Declarations of the commands that the loader exports, with wrappers for them
+    LinkedCommands,
+}
+
+impl Output {
+    /// Every output, in the order they're written
+    pub const ALL: [Output; 6] = [Output::Types, Output::LayoutTests, Output::Structs, Output::StructLayoutTests, Output::Commands, Output::LinkedCommands];
+
+    /// Gets the name of the file the output is written to when each output has its own file
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Output::Types => "types.rs",
+            Output::LayoutTests => "layout_tests.rs",
+            Output::Structs => "safe_structs.rs",
+            Output::StructLayoutTests => "safe_layout_tests.rs",
+            Output::Commands => "commands.rs",
+            Output::LinkedCommands => "linked_commands.rs",
+        }
+    }
+
+    /// Gets the name of the module the output is put in when the outputs share a file
+    pub fn module_name(self) -> &'static str {
+        match self {
+            Output::Types => "types",
+            Output::Structs => "structs",
+            Output::LayoutTests => "layout_tests",
+            Output::StructLayoutTests => "struct_layout_tests",
+            Output::Commands => "commands",
+            Output::LinkedCommands => "linked_commands",
+        }
+    }
+
+    /// Gets the output that a test output tests, which its module goes inside of
+    pub fn tested(self) -> Option<Output> {
+        match self {
+            Output::LayoutTests => Some(Output::Types),
+            Output::StructLayoutTests => Some(Output::Structs),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Output, String> {
+        match s {
+            "types" => Ok(Output::Types),
+            "layout-tests" => Ok(Output::LayoutTests),
+            "structs" => Ok(Output::Structs),
+            "struct-layout-tests" => Ok(Output::StructLayoutTests),
+            "commands" => Ok(Output::Commands),
+            "linked-commands" => Ok(Output::LinkedCommands),
+            s => Err(format!("unknown output {}", s)),
+        }
+    }
+}
+
+/// How the outputs are split in to files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+    /// Each output has its own file (see `Output::file_name`), to be `include!`d in to a module
+    /// that defines the macros and imports it uses
+    Files,
+    /// Every output is written to one file, in a module of its own (see `Output::module_name`)
+    /// that imports everything from the module that the file is included in. Tests go in a
+    /// `#[cfg(test)]` module inside of the module of what they test.
+    Modules(String),
+}
+
+impl Default for Layout {
+    #[inline]
+    fn default() -> Layout {
+        Layout::Files
+    }
+}
+
+/// How to compile the C program that finds the layouts of the types in the C headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutProbe {
+    /// The C compiler to use
+    pub compiler: String,
+    /// Directories to search for `vulkan/vulkan_core.h`
+    pub include_dirs: Vec<PathBuf>,
+    /// Where the probe is compiled
+    pub build_dir: PathBuf,
+}
+
+impl LayoutProbe {
+    /// Makes a probe that's compiled in `build_dir` with `CC` (or `cc` if it isn't set), against
+    /// the headers in `VULKAN_SDK` if it's set, or else the system's
+    pub fn from_env<P: Into<PathBuf>>(build_dir: P) -> LayoutProbe {
+        LayoutProbe {
+            compiler: env::var("CC").unwrap_or_else(|_| String::from("cc")),
+            include_dirs: env::var_os("VULKAN_SDK").map(|sdk| PathBuf::from(sdk).join("include")).into_iter().collect(),
+            build_dir: build_dir.into(),
+        }
+    }
+}
+
+/// Configures what `generate` generates
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub naming: NamingStyle,
+    pub outputs: BTreeSet<Output>,
+    pub layout: Layout,
+    /// The layout tests are empty without a probe to find what to test against
+    pub layout_probe: Option<LayoutProbe>,
+    /// The API that `parse_registry` selects from the registry, or `None` for every variant
+    pub api: Option<String>,
+    /// The files that `parse_registry` reads, in order (i.e. vk.xml and then video.xml)
+    pub sources: Vec<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            naming: NamingStyle::default(),
+            outputs: Output::ALL.iter().cloned().collect(),
+            layout: Layout::default(),
+            layout_probe: None,
+            api: Some(String::from("vulkan")),
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    #[inline]
+    pub fn naming(mut self, naming: NamingStyle) -> Config {
+        self.naming = naming;
+        self
+    }
+
+    /// Generates only the given outputs
+    pub fn outputs<It: IntoIterator<Item=Output>>(mut self, outputs: It) -> Config {
+        self.outputs = outputs.into_iter().collect();
+        self
+    }
+
+    #[inline]
+    pub fn layout(mut self, layout: Layout) -> Config {
+        self.layout = layout;
+        self
+    }
+
+    #[inline]
+    pub fn layout_probe(mut self, probe: LayoutProbe) -> Config {
+        self.layout_probe = Some(probe);
+        self
+    }
+
+    #[inline]
+    pub fn api(mut self, api: Option<&str>) -> Config {
+        self.api = api.map(String::from);
+        self
+    }
+
+    /// Adds a registry file for `parse_registry` to read after the others
+    pub fn source<P: Into<PathBuf>>(mut self, path: P) -> Config {
+        self.sources.push(path.into());
+        self
+    }
+}
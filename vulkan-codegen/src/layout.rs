@@ -0,0 +1,155 @@
+//! Tests that the generated types are laid out the way the API expects
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io::{ self, Write };
+use std::process::Command;
+use vulkan_api_registry::{ Registry, TypeInfo };
+use config::{ LayoutProbe, NamingStyle };
+use names::ffi_member_name;
+use structs::{ MemberExt, StructInfo };
+
+/// Writes a C program that prints the size, alignment, and member offsets of each struct and union
+/// (and the size of each enum) as the Vulkan headers declare them, one per line.
+///
+/// It prints nothing if the headers aren't installed, or are for a different version of the
+/// registry, since they might not declare everything.
+fn layout_probe(registry: &Registry, types: &BTreeSet<&str>) -> String {
+    let header_version = registry.defines().into_iter()
+        .find(|d| d.name == "VK_HEADER_VERSION")
+        .map(|d| d.body.to_string())
+        .unwrap_or_else(|| String::from("-1"));
+    let mut probe = String::from("#include <stddef.h>\n#include <stdio.h>\n");
+    probe.push_str("#if defined(__has_include) && __has_include(<vulkan/vulkan_core.h>)\n#include <vulkan/vulkan_core.h>\n#endif\n");
+    probe.push_str("int main(void) {\n");
+    probe.push_str(&format!("#if defined(VK_HEADER_VERSION) && VK_HEADER_VERSION == {}\n", header_version));
+    for &name in types {
+        match registry.types[name] {
+            TypeInfo::Struct { ref members, .. } | TypeInfo::Union { ref members, .. } => {
+                probe.push_str(&format!("    printf(\"size {0} %zu\\n\", sizeof({0}));\n", name));
+                probe.push_str(&format!("    printf(\"align {0} %zu\\n\", _Alignof({0}));\n", name));
+                // Every member of a union is at offset 0, and bitfields don't have offsets
+                if let TypeInfo::Struct { .. } = registry.types[name] {
+                    for member in members.iter().filter(|m| m.bitfield.is_none()) {
+                        probe.push_str(&format!("    printf(\"offset {0} {1} %zu\\n\", offsetof({0}, {1}));\n", name, &member.name));
+                    }
+                }
+            },
+            TypeInfo::Enum { .. } => probe.push_str(&format!("    printf(\"size {0} %zu\\n\", sizeof({0}));\n", name)),
+            _ => {},
+        }
+    }
+    probe.push_str("#endif\n    return 0;\n}\n");
+    probe
+}
+
+/// Compiles and runs the layout probe, returning what it printed. Nothing is returned if there's
+/// no C compiler, or the probe doesn't compile, which is added to `warnings`.
+fn run_layout_probe(config: &LayoutProbe, probe: &str, warnings: &mut Vec<String>) -> io::Result<Option<String>> {
+    let source = config.build_dir.join("layout_probe.c");
+    let binary = config.build_dir.join("layout_probe");
+    fs::File::create(&source)?.write_all(probe.as_bytes())?;
+    let mut command = Command::new(&config.compiler);
+    command.arg("-std=c11").arg("-o").arg(&binary).arg(&source);
+    for dir in &config.include_dirs {
+        command.arg("-I").arg(dir);
+    }
+    match command.output() {
+        Ok(ref output) if output.status.success() => {},
+        Ok(output) => {
+            warnings.push(format!("couldn't compile the layout probe against the Vulkan headers: {}", String::from_utf8_lossy(&output.stderr).lines().next().unwrap_or("")));
+            return Ok(None);
+        },
+        // No C compiler, so no headers to check against either
+        Err(_) => return Ok(None),
+    }
+    Ok(Command::new(&binary).output().ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok()))
+}
+
+/// Finds the layouts of the raw types in the C headers with the layout probe
+pub fn probe_layouts(registry: &Registry, probe: &LayoutProbe, warnings: &mut Vec<String>) -> io::Result<String> {
+    let portable = registry.portable_types();
+    let types: BTreeSet<&str> = portable.iter().cloned().collect();
+    run_layout_probe(probe, &layout_probe(registry, &types), warnings).map(|layouts| layouts.unwrap_or_else(String::new))
+}
+
+/// Writes tests that check the size, alignment, and member offsets of the raw types against the
+/// `layouts` that the probe found. Nothing is written if there aren't any, because the headers
+/// weren't there to check against.
+pub fn write_layout_tests<W: fmt::Write>(f: &mut W, registry: &Registry, layouts: &str) -> fmt::Result {
+    let portable = registry.portable_types();
+    let mut tests: Vec<(&str, Vec<String>)> = Vec::new();
+    for line in layouts.lines() {
+        let words: Vec<&str> = line.split(' ').collect();
+        let assertion = match words.as_slice() {
+            ["size", name, size] => format!("assert_eq!(mem::size_of::<{}>(), {});", name, size),
+            ["align", name, align] => format!("assert_eq!(mem::align_of::<{}>(), {});", name, align),
+            ["offset", name, member, offset] => format!("assert_eq!(mem::offset_of!({}, {}), {});", name, ffi_member_name(member), offset),
+            _ => panic!("unexpected output from the layout probe: {}", line),
+        };
+        let name = portable.get(words[1]).cloned().unwrap();
+        match tests.last_mut() {
+            Some(&mut (test, ref mut assertions)) if test == name => {
+                assertions.push(assertion);
+                continue;
+            },
+            _ => {},
+        }
+        tests.push((name, vec![assertion]));
+    }
+    if tests.is_empty() {
+        return Ok(());
+    }
+    f.write_str("use super::*;\nuse std::mem;\n")?;
+    for (name, assertions) in tests {
+        write!(f, "\n#[test]\nfn layout_of_{}() {{\n", name)?;
+        for assertion in assertions {
+            writeln!(f, "    {}", assertion)?;
+        }
+        f.write_str("}\n")?;
+    }
+    Ok(())
+}
+
+/// Writes a test for each safe struct that checks that its size, alignment and member offsets are
+/// the same as the raw struct's, so that it can be passed to the API in its place
+pub fn write_struct_layout_tests<W: fmt::Write>(f: &mut W, registry: &Registry, naming: NamingStyle) -> fmt::Result {
+    f.write_str("use super::*;\nuse std::mem;\n")?;
+    let portable = registry.portable_types();
+    let names: BTreeSet<&str> = portable.iter().cloned().collect();
+    for name in names {
+        let mut info = match StructInfo::of(registry, &registry.types[name]) {
+            Some(info) => info,
+            None => continue,
+        };
+        info.naming = naming;
+        write!(f, "\n#[test]\nfn layout_of_{}() {{\n", name)?;
+        writeln!(f, "    assert_eq!(mem::size_of::<{0}>(), mem::size_of::<::ffi::{0}>());", name)?;
+        writeln!(f, "    assert_eq!(mem::align_of::<{0}>(), mem::align_of::<::ffi::{0}>());", name)?;
+        // Extendable structs have their members after the `VkStructInfo`
+        let base = if info.is_extendable() {
+            writeln!(f, "    assert_eq!(mem::offset_of!({}, struct_info), mem::offset_of!(::ffi::{}, sType));", name, name)?;
+            writeln!(f, "    assert_eq!(mem::offset_of!({}, struct_info) + mem::offset_of!(VkStructInfo, next), mem::offset_of!(::ffi::{}, pNext));", name, name)?;
+            writeln!(f, "    let data = mem::offset_of!({}, data);", name)?;
+            "data + "
+        } else {
+            ""
+        };
+        let bitfields = info.bitfields();
+        for member in info.base_members() {
+            let safe_name = match member.bitfield {
+                Some(_) => match bitfields.iter().find(|b| b.name == member.clean_name()) {
+                    Some(b) if b.offset == 0 => format!("_bitfield_{}", b.unit),
+                    _ => continue,
+                },
+                None => info.field_name(member).into_owned(),
+            };
+            let ffi_name = if member.bitfield.is_some() { safe_name.as_str() } else { ffi_member_name(member.name.as_str()) };
+            writeln!(f, "    assert_eq!({}mem::offset_of!({}, {}), mem::offset_of!(::ffi::{}, {}));", base, info.base_name(), safe_name, name, ffi_name)?;
+        }
+        f.write_str("}\n")?;
+    }
+    Ok(())
+}
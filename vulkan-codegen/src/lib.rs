@@ -0,0 +1,164 @@
+//! Generates Rust bindings to Vulkan from its registry.
+//!
+//! `generate` makes the raw types, safe structs, and command wrappers that `smolder-vulkan-sys`
+//! is built on, as configured by a `Config`. The `vulkan-codegen` binary does the same from the
+//! command line, so that bindings can be generated ahead of time and checked in.
+
+extern crate vulkan_api_registry;
+extern crate xml;
+
+mod bitfields;
+mod commands;
+mod config;
+mod layout;
+mod names;
+mod structs;
+mod types;
+
+pub use config::{ Config, Layout, LayoutProbe, NamingStyle, Output };
+
+use std::{ fs, io };
+use std::path::Path;
+use vulkan_api_registry::{ Dialect, ParseError, ParseResult, Registry, TypeInfo };
+use xml::reader::EventReader;
+
+/// Parses the registry files in `config.sources`, selecting `config.api`. vk.xml only names the
+/// std codec types used by the video extensions, so video.xml should be one of the sources too.
+pub fn parse_registry(config: &Config) -> ParseResult<Registry> {
+    let always_true = |_: &str| true;
+    let sources: Vec<_> = config.sources.iter().map(|p| fs::File::open(p).map(|f| EventReader::new(io::BufReader::new(f)).into_iter())).collect::<io::Result<_>>().map_err(ParseError::Io)?;
+    Registry::from_sources(sources, &Dialect::vulkan(), config.api.as_deref(), &always_true, &always_true)
+}
+
+/// Follows type aliases to the type that they name
+fn resolve_alias<'a>(registry: &'a Registry, name: &'a str) -> &'a str {
+    match registry.types.get(name) {
+        Some(TypeInfo::Alias { alias, .. }) => resolve_alias(registry, alias.as_str()),
+        _ => name,
+    }
+}
+
+/// The generated code, split in to files as the `Config` said to
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    /// The name and contents of each file
+    pub files: Vec<(String, String)>,
+    /// Problems with the registry that were worked around, like enumerants without values
+    pub warnings: Vec<String>,
+}
+
+impl Bindings {
+    /// Writes each of the files in to `dir`
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        for (name, contents) in &self.files {
+            fs::write(dir.as_ref().join(name), contents)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the names of the files that are missing from `dir`, or are different there
+    pub fn outdated_files<P: AsRef<Path>>(&self, dir: P) -> Vec<&str> {
+        self.files.iter()
+            .filter(|&(name, contents)| fs::read_to_string(dir.as_ref().join(name)).ok().as_ref() != Some(contents))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+fn fmt_error(_: std::fmt::Error) -> io::Error {
+    io::Error::other("couldn't format the generated code")
+}
+
+/// Generates the outputs that `config` asks for. Only the layout probe writes anything to disk.
+pub fn generate(registry: &Registry, config: &Config) -> io::Result<Bindings> {
+    let mut warnings = Vec::new();
+    let mut outputs: Vec<(Output, String)> = Vec::new();
+    for &output in &config.outputs {
+        let mut code = String::new();
+        match output {
+            Output::Types => types::write_types(&mut code, registry, &mut warnings).map_err(fmt_error)?,
+            Output::LayoutTests => {
+                let layouts = match config.layout_probe {
+                    Some(ref probe) => layout::probe_layouts(registry, probe, &mut warnings)?,
+                    None => String::new(),
+                };
+                layout::write_layout_tests(&mut code, registry, &layouts).map_err(fmt_error)?;
+            },
+            Output::Structs => structs::write_structs(&mut code, registry, config.naming).map_err(fmt_error)?,
+            Output::StructLayoutTests => layout::write_struct_layout_tests(&mut code, registry, config.naming).map_err(fmt_error)?,
+            Output::Commands => commands::write_commands(&mut code, registry).map_err(fmt_error)?,
+            Output::LinkedCommands => commands::write_linked_commands(&mut code, registry).map_err(fmt_error)?,
+        }
+        outputs.push((output, code));
+    }
+    let files = match config.layout {
+        Layout::Files => outputs.into_iter().map(|(output, code)| (String::from(output.file_name()), code)).collect(),
+        Layout::Modules(ref file_name) => vec![(file_name.clone(), modules(&outputs))],
+    };
+    Ok(Bindings {
+        files,
+        warnings,
+    })
+}
+
+/// Puts each output in a module of its own, with the tests of an output inside of its module
+fn modules(outputs: &[(Output, String)]) -> String {
+    let generated = |output: Output| outputs.iter().any(|&(o, _)| o == output);
+    let mut out = String::new();
+    for &(output, ref code) in outputs {
+        if let Some(tested) = output.tested() {
+            if generated(tested) {
+                continue;
+            }
+        }
+        let tests: Vec<&(Output, String)> = outputs.iter().filter(|&&(o, _)| o.tested() == Some(output)).collect();
+        let mut module = String::new();
+        if output.tested().is_none() {
+            module.push_str("use super::*;\n\n");
+            module.push_str(code);
+        } else {
+            test_module(&mut module, output, code);
+        }
+        for &&(test, ref test_code) in &tests {
+            if !test_code.is_empty() {
+                module.push('\n');
+            }
+            test_module(&mut module, test, test_code);
+        }
+        if module.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        if output.tested().is_none() {
+            // The generated code uses items from deprecated extensions alongside the rest
+            out.push_str(&format!("#[allow(deprecated)]\npub mod {} {{\n", output.module_name()));
+            indent(&mut out, &module);
+            out.push_str("}\n");
+        } else {
+            out.push_str(&module);
+        }
+    }
+    out
+}
+
+/// Writes a module of tests, if there are any
+fn test_module(out: &mut String, output: Output, code: &str) {
+    if code.is_empty() {
+        return;
+    }
+    out.push_str(&format!("#[cfg(test)]\n#[allow(non_snake_case)]\nmod {} {{\n", output.module_name()));
+    indent(out, code);
+    out.push_str("}\n");
+}
+
+fn indent(out: &mut String, code: &str) {
+    for line in code.lines() {
+        if !line.is_empty() {
+            out.push_str("    ");
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+}
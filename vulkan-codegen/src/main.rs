@@ -0,0 +1,106 @@
+//! Generates the bindings ahead of time, so that they can be checked in and reviewed
+
+extern crate vulkan_api_registry;
+extern crate vulkan_codegen;
+
+use std::{ env, fs, process };
+use vulkan_api_registry::{ ParseResult, Registry };
+use vulkan_codegen::{ Config, Layout, LayoutProbe };
+
+const USAGE: &str = "\
+Usage: vulkan-codegen [options] <out-dir>
+
+Options:
+    --registry <xml>      A registry file to generate from; give it again for each of the others,
+                          like video.xml
+    --embedded            Use the registry built in to vulkan-api-registry instead
+    --api <api>           The API to select from the registry: vulkan (the default), vulkansc, or
+                          all for every variant
+    --naming <style>      How safe struct members are named: registry (the default) or rust
+    --only <outputs>      Comma separated outputs to generate: types, layout-tests, structs,
+                          struct-layout-tests, commands, linked-commands
+    --single-file <name>  Write every output to one file, in a module of its own
+    --probe               Check the raw types' layouts against the C headers in VULKAN_SDK (or the
+                          system's), compiled with CC
+    --check               Write nothing, and fail if the files in <out-dir> are out of date";
+
+#[cfg(feature = "embedded")]
+fn embedded_registry(config: &Config) -> ParseResult<Registry> {
+    Registry::embedded(config.api.as_deref())
+}
+
+#[cfg(not(feature = "embedded"))]
+fn embedded_registry(_: &Config) -> ParseResult<Registry> {
+    fail("vulkan-codegen was built without the embedded feature")
+}
+
+fn fail<T>(message: &str) -> T {
+    eprintln!("vulkan-codegen: {}", message);
+    process::exit(2)
+}
+
+fn main() {
+    let mut config = Config::default();
+    let mut embedded = false;
+    let mut out_dir: Option<String> = None;
+    let mut check = false;
+    // The probe is compiled somewhere of its own, which is removed afterwards
+    let probe_dir = env::temp_dir().join(format!("vulkan-codegen-{}", process::id()));
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().unwrap_or_else(|| fail(&format!("{} needs a value", name)));
+        match arg.as_str() {
+            "--registry" => config = config.source(value("--registry")),
+            "--embedded" => embedded = true,
+            "--api" => {
+                let api = value("--api");
+                config = config.api(if api == "all" { None } else { Some(&api) });
+            },
+            "--naming" => config.naming = value("--naming").parse().unwrap_or_else(|e: String| fail(&e)),
+            "--only" => {
+                let outputs: Result<Vec<_>, String> = value("--only").split(',').map(|o| o.trim().parse()).collect();
+                config = config.outputs(outputs.unwrap_or_else(|e| fail(&e)));
+            },
+            "--single-file" => config.layout = Layout::Modules(value("--single-file")),
+            "--probe" => config = config.layout_probe(LayoutProbe::from_env(&probe_dir)),
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            a if a.starts_with('-') => fail(&format!("unknown option {}\n\n{}", a, USAGE)),
+            a if out_dir.is_none() => out_dir = Some(String::from(a)),
+            _ => fail(USAGE),
+        }
+    }
+    let out_dir = out_dir.unwrap_or_else(|| fail(USAGE));
+    let registry = match (embedded, config.sources.is_empty()) {
+        (false, false) => vulkan_codegen::parse_registry(&config),
+        (true, true) => embedded_registry(&config),
+        (false, true) => fail("either --registry or --embedded has to be given"),
+        (true, false) => fail("--registry and --embedded can't both be given"),
+    }.unwrap_or_else(|e| fail(&format!("couldn't parse the registry: {:?}", e)));
+    for problem in registry.validate() {
+        eprintln!("warning: vk.xml: {}", problem);
+    }
+    if config.layout_probe.is_some() {
+        fs::create_dir_all(&probe_dir).unwrap_or_else(|e| fail(&format!("couldn't make {}: {}", probe_dir.display(), e)));
+    }
+    let bindings = vulkan_codegen::generate(&registry, &config);
+    if config.layout_probe.is_some() {
+        let _ = fs::remove_dir_all(&probe_dir);
+    }
+    let bindings = bindings.unwrap_or_else(|e| fail(&format!("couldn't generate the bindings: {}", e)));
+    for warning in &bindings.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if check {
+        let outdated = bindings.outdated_files(&out_dir);
+        if !outdated.is_empty() {
+            eprintln!("vulkan-codegen: out of date in {}: {}", &out_dir, outdated.join(", "));
+            process::exit(1);
+        }
+    } else {
+        bindings.write_to(&out_dir).unwrap_or_else(|e| fail(&format!("couldn't write to {}: {}", &out_dir, e)));
+    }
+}
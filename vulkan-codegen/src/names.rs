@@ -0,0 +1,125 @@
+//! Translations of the registry's names in to Rust
+
+pub fn clean_c_name(name: &str) -> &str {
+    match name {
+        "int8_t" => "i8",
+        "uint8_t" => "u8",
+        "int16_t" => "i16",
+        "uint16_t" => "u16",
+        "int32_t" => "i32",
+        "uint32_t" => "u32",
+        "int64_t" => "i64",
+        "uint64_t" => "u64",
+        "size_t" => "usize",
+        "char" => "c_char",
+        "float" => "c_float",
+        "double" => "c_double",
+        "long" => "c_long",
+        "int" => "c_int",
+        "void" => "c_void",
+        s => s,
+    }
+}
+
+/// Gets the name of a struct or union member in Rust
+pub fn ffi_member_name(name: &str) -> &str {
+    match name {
+        "type" => "type_",
+        n => n,
+    }
+}
+
+/// Converts a Vulkan name (i.e. `vkGetPhysicalDeviceFeatures2KHR` or `pCreateInfo`) to snake
+/// case, leaving out the `vk` prefix of commands and the `p` prefixes of pointers
+pub fn snake_case(name: &str) -> String {
+    let unprefixed = name.trim_start_matches('p');
+    let name = if let Some(name) = name.strip_prefix("vk") {
+        name
+    } else if unprefixed.len() < name.len() && unprefixed.starts_with(|c: char| c.is_uppercase()) {
+        unprefixed
+    } else {
+        name
+    };
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if previous.is_lowercase() || previous.is_ascii_digit() || (previous.is_uppercase() && next_is_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    match snake.as_str() {
+        "type" => String::from("ty"),
+        _ => snake,
+    }
+}
+
+/// Shortens the name of a bit to how it's named in its flags type, i.e.
+/// `VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT` to `COLOR_ATTACHMENT`. The type's prefix and vendor
+/// suffix are left out, along with `_BIT`, but names that would start with a digit keep the last
+/// word of the prefix (`VK_SAMPLE_COUNT_1_BIT` is `COUNT_1`).
+pub fn flag_name(bits_type: &str, value_name: &str) -> String {
+    let (stem, rest) = match bits_type.find("FlagBits") {
+        Some(i) => (&bits_type[..i], &bits_type[i + "FlagBits".len()..]),
+        None => (bits_type, ""),
+    };
+    let stem = stem.strip_prefix("Vk").unwrap_or(stem);
+    let version = rest.trim_end_matches(|c: char| c.is_alphabetic());
+    let vendor = &rest[version.len()..];
+    let mut prefix = format!("VK_{}_", snake_case(stem).to_uppercase());
+    if !version.is_empty() {
+        prefix = format!("{}{}_", prefix, version);
+    }
+    let mut name = if value_name.starts_with(prefix.as_str()) {
+        let unprefixed = &value_name[prefix.len()..];
+        if unprefixed.starts_with(|c: char| c.is_ascii_digit()) {
+            let last_word = prefix[..prefix.len() - 1].rfind('_').map_or(0, |i| i + 1);
+            String::from(&value_name[last_word..])
+        } else {
+            String::from(unprefixed)
+        }
+    } else {
+        String::from(value_name.trim_start_matches("VK_"))
+    };
+    if !vendor.is_empty() && name.ends_with(vendor) && name[..name.len() - vendor.len()].ends_with('_') {
+        let len = name.len() - vendor.len() - 1;
+        name.truncate(len);
+    }
+    // `_BIT` comes before any other vendor's suffix
+    let vendor_start = name.rfind('_').filter(|&i| name[i + 1..].chars().all(|c| c.is_ascii_uppercase()) && name[..i].ends_with("_BIT"));
+    match vendor_start {
+        Some(i) => name.replace_range(i - 4..i, ""),
+        None if name.ends_with("_BIT") => {
+            let len = name.len() - 4;
+            name.truncate(len);
+        },
+        None => {},
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_case_names() {
+        assert_eq!(snake_case("vkGetPhysicalDeviceFeatures2KHR"), "get_physical_device_features2_khr");
+        assert_eq!(snake_case("pCreateInfo"), "create_info");
+        assert_eq!(snake_case("ppEnabledLayerNames"), "enabled_layer_names");
+        assert_eq!(snake_case("pfnCallback"), "pfn_callback");
+        assert_eq!(snake_case("type"), "ty");
+    }
+
+    #[test]
+    fn flag_names() {
+        assert_eq!(flag_name("VkImageUsageFlagBits", "VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT"), "COLOR_ATTACHMENT");
+        assert_eq!(flag_name("VkSampleCountFlagBits", "VK_SAMPLE_COUNT_1_BIT"), "COUNT_1");
+        assert_eq!(flag_name("VkGeometryInstanceFlagBitsKHR", "VK_GEOMETRY_INSTANCE_FORCE_OPAQUE_BIT_KHR"), "FORCE_OPAQUE");
+        assert_eq!(flag_name("VkGeometryInstanceFlagBitsKHR", "VK_GEOMETRY_INSTANCE_FORCE_OPAQUE_BIT_NV"), "FORCE_OPAQUE_NV");
+    }
+}
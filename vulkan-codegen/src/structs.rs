@@ -0,0 +1,590 @@
+//! The safe structs, which have references where the raw structs have pointers
+
+use std;
+use std::borrow::Cow;
+use std::collections::{ BTreeSet, HashSet, LinkedList };
+use std::fmt;
+use vulkan_api_registry::{ HandleType, Provider, ProviderIndex, Registry, StructMember, TypeInfo };
+use bitfields::{ bitfields, Bitfield };
+use config::NamingStyle;
+use names::{ clean_c_name, snake_case };
+use ::resolve_alias;
+
+pub struct StructInfo<'a> {
+    pub name: &'a str,
+    pub members: &'a LinkedList<StructMember>,
+    pub extends: &'a LinkedList<String>,
+    pub registry: &'a Registry,
+    pub deprecation: Option<String>,
+    pub naming: NamingStyle,
+}
+
+impl<'a> StructInfo<'a> {
+    pub fn new(registry: &'a Registry, name: &'a str, members: &'a LinkedList<StructMember>, extends: &'a LinkedList<String>) -> StructInfo<'a> {
+        StructInfo {
+            name,
+            members,
+            extends,
+            registry,
+            deprecation: None,
+            naming: NamingStyle::default(),
+        }
+    }
+
+    /// Gets the struct that a type is, if it is one
+    pub fn of(registry: &'a Registry, info: &'a TypeInfo) -> Option<StructInfo<'a>> {
+        match info {
+            TypeInfo::Struct { name, members, extends } => Some(StructInfo::new(registry, name, members, extends)),
+            _ => None,
+        }
+    }
+
+    pub fn base_name(&self) -> String {
+        if self.is_extendable() {
+            format!("{}Base", self.name)
+        } else {
+            String::from(self.name)
+        }
+    }
+
+    #[inline]
+    pub fn is_extendable(&self) -> bool {
+        self.extendable().is_some()
+    }
+
+    fn extendable(&self) -> Option<&str> {
+        let mut members = self.members.iter();
+        let s_type = members.next()
+            .and_then(|fst| fst.values.as_ref())
+            .and_then(|v| v.front());
+        let snd = members.next().and_then(|snd| if snd.name == "pNext" && snd.ty.ty == "void" {
+            Some(snd)
+        } else {
+            None
+        });
+        match (s_type, snd) {
+            (maybe_s_type, Some(_)) => {
+                maybe_s_type.map(|s| s.as_str())
+            },
+            _ => None,
+        }
+    }
+
+    fn used_as_count(&self, member_name: &str) -> bool {
+        self.members.iter()
+            .filter_map(|member| member.len.as_ref().map(|len| len.split(",")))
+            .filter_map(|mut lens| lens.find(|&len| len == member_name))
+            .next()
+            .is_some()
+    }
+
+    pub fn base_members(&self) -> std::iter::Skip<std::collections::linked_list::Iter<'a, StructMember>> {
+        // The `sType` and `pNext` of extendable structs are in the `VkStructInfo`
+        let skip = if self.is_extendable() { 2 } else { 0 };
+        self.members.iter().skip(skip)
+    }
+
+    pub fn bitfields(&self) -> Vec<Bitfield<'a>> {
+        bitfields(self.members)
+    }
+
+    /// Gets the name of a member of the safe struct
+    pub fn field_name<'m>(&self, member: &'m StructMember) -> Cow<'m, str> {
+        match self.naming {
+            NamingStyle::Registry => member.clean_name().into(),
+            NamingStyle::Rust => snake_case(member.name.as_str()).into(),
+        }
+    }
+
+    pub fn type_params(&self) -> BTreeSet<&'a str> {
+        let lifetimes = self.base_members().flat_map(|member| if !member.ty.constness.is_empty() {
+            Some("'a")
+        } else {
+            None
+        });
+        // Pointers already have a lifetime, and following them could go around in circles
+        let values = self.base_members().filter(|member| member.ty.constness.is_empty());
+        let handle_lifetimes = values.flat_map(|member| match self.registry.types.get(resolve_alias(self.registry, member.ty.ty.as_str())) {
+            Some(&TypeInfo::Handle { .. }) => {
+                let mut single = BTreeSet::new();
+                single.insert("'a");
+                single
+            },
+            Some(info @ &TypeInfo::Struct { .. }) => StructInfo::of(self.registry, info).unwrap().type_params(),
+            _ => BTreeSet::new(),
+        });
+        lifetimes.chain(handle_lifetimes).collect()
+    }
+
+    /// Checks if the safe struct's type has a lifetime, which extendable structs always have since
+    /// they are `VkStructInstance`s
+    pub fn has_lifetime(&self) -> bool {
+        self.is_extendable() || !self.type_params().is_empty()
+    }
+
+    /// Checks if the safe struct can be `Copy`, which it can't be if it has a mutable reference,
+    /// including a dispatchable handle or the chain of an extendable struct, in it
+    pub fn is_copy(&self) -> bool {
+        self.base_members().all(|member| match member.pointer_info().first() {
+            Some(info) => info.constness,
+            None => match self.registry.types.get(resolve_alias(self.registry, member.ty.ty.as_str())) {
+                Some(&TypeInfo::Handle { ty, .. }) => ty == HandleType::NonDispatchable,
+                Some(info @ &TypeInfo::Struct { .. }) => {
+                    let info = StructInfo::of(self.registry, info).unwrap();
+                    !info.is_extendable() && info.is_copy()
+                },
+                _ => true,
+            },
+        })
+    }
+
+    /// Gets the name of the member that counts the elements of a slice member, if the slice is
+    /// set by a builder method
+    fn slice_count<'m>(&self, member: &'m StructMember) -> Option<&'m str> {
+        let count = match member.pointer_info().first() {
+            Some(&PointerInfo { ty: PointerType::MemberSized(count), .. }) if member.ty.array.is_empty() => count,
+            _ => return None,
+        };
+        if self.members.iter().any(|m| m.name == count && m.ty.constness.is_empty() && m.ty.array.is_empty()) {
+            Some(count)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if a member is an optional pointer to a single value (or a null-terminated array),
+    /// which is an `Option` so that it can be null
+    fn is_optional_reference(&self, member: &StructMember) -> bool {
+        member.optional && member.ty.array.is_empty() && matches!(member.pointer_info().first(),
+            Some(&PointerInfo { ty: PointerType::Reference, .. }) | Some(&PointerInfo { ty: PointerType::NullTerminated, .. }))
+    }
+
+    /// Gets what a member's pointers, from the `skip`th one, point to
+    fn pointee_type(&self, member: &StructMember, skip: usize) -> String {
+        use std::borrow::Borrow;
+        let mut ty = String::new();
+        let mut ptr_infos: Vec<PointerInfo> = member.pointer_info().into_iter().skip(skip).collect();
+        for info in &ptr_infos {
+            info.begin(&mut ty).unwrap();
+        }
+        ty.push_str(self.registry.struct_member_representation_of(clean_c_name(member.ty.ty.as_str())).borrow());
+        ptr_infos.reverse();
+        for info in &ptr_infos {
+            info.end(&mut ty).unwrap();
+        }
+        ty
+    }
+
+    /// Gets the Rust type of a member. Slices are an optional reference to their first element, so
+    /// that they can be empty, and optional references are `Option`s too.
+    fn member_type(&self, member: &StructMember) -> String {
+        let mut ty = String::new();
+        // C array dimensions are outermost first, so `T x[A][B]` is `[[T; B]; A]`
+        for _ in &member.ty.array {
+            ty.push('[');
+        }
+        if self.slice_count(member).is_some() || self.is_optional_reference(member) {
+            ty.push_str(&format!("Option<{}>", self.pointee_type(member, 0)));
+        } else {
+            ty.push_str(&self.pointee_type(member, 0));
+        }
+        for size in member.ty.array.iter().rev() {
+            ty.push_str(&format!("; {}]", size));
+        }
+        ty
+    }
+
+    /// Checks if a member has to be given to `new`, because it's a reference or function pointer
+    /// that isn't optional, or its type has no default value
+    fn is_required(&self, member: &StructMember) -> bool {
+        member.bitfield.is_none() && self.slice_count(member).is_none() && !self.used_as_count(member.name.as_str()) &&
+            !self.is_optional_reference(member) &&
+            (!member.ty.constness.is_empty() || !has_default(self.registry, member.ty.ty.as_str()) ||
+                (!member.optional && is_funcpointer(self.registry, member.ty.ty.as_str())))
+    }
+
+    /// Writes `new`, which takes the members that have no default value, and a builder method for
+    /// each of the other members that isn't a bitfield. Slices that share a count are set together.
+    fn write_builder_methods<W: fmt::Write>(&self, f: &mut W, base_name: &str) -> fmt::Result {
+        let members: Vec<&StructMember> = self.base_members().collect();
+        let required: Vec<&StructMember> = members.iter().cloned().filter(|m| self.is_required(m)).collect();
+        if required.len() > 7 {
+            f.write_str("    #[allow(clippy::too_many_arguments)]\n")?;
+        }
+        f.write_str("    pub fn new(")?;
+        let params: Vec<String> = required.iter().map(|m| format!("{}: {}", snake_case(m.name.as_str()), self.member_type(m))).collect();
+        write!(f, "{}) -> Self {{\n        {} {{\n", params.join(", "), base_name)?;
+        let bitfields = self.bitfields();
+        for member in members.iter() {
+            let name = self.field_name(member);
+            if member.bitfield.is_some() {
+                match bitfields.iter().find(|b| b.name == member.clean_name()) {
+                    Some(b) if b.offset == 0 => writeln!(f, "            _bitfield_{}: 0,", b.unit)?,
+                    _ => {},
+                }
+            } else if self.is_required(member) {
+                let param = snake_case(member.name.as_str());
+                if param == name {
+                    writeln!(f, "            {},", name)?;
+                } else {
+                    writeln!(f, "            {}: {},", name, param)?;
+                }
+            } else if self.slice_count(member).is_some() || self.is_optional_reference(member) {
+                writeln!(f, "            {}: None,", name)?;
+            } else {
+                let mut value = String::from("Default::default()");
+                for size in member.ty.array.iter().rev() {
+                    value = format!("[{}; {}]", value, size);
+                }
+                writeln!(f, "            {}: {},", name, value)?;
+            }
+        }
+        f.write_str("        }\n    }\n")?;
+        let mut counts: Vec<&str> = Vec::new();
+        for member in members.iter() {
+            if member.bitfield.is_some() {
+                continue;
+            }
+            if let Some(count) = self.slice_count(member) {
+                if !counts.contains(&count) {
+                    counts.push(count);
+                    let slices: Vec<&StructMember> = members.iter().cloned().filter(|m| self.slice_count(m) == Some(count)).collect();
+                    self.write_slice_setter(f, count, &slices)?;
+                }
+            } else if self.is_optional_reference(member) {
+                let param = snake_case(member.name.as_str());
+                write!(f, "    #[inline]\n    pub fn {0}(mut self, {0}: {1}) -> Self {{\n", param, self.pointee_type(member, 0))?;
+                write!(f, "        self.{} = Some({});\n        self\n    }}\n", self.field_name(member), param)?;
+            } else if !(self.used_as_count(member.name.as_str()) || member.is_slice()) {
+                let param = snake_case(member.name.as_str());
+                write!(f, "    #[inline]\n    pub fn {0}(mut self, {0}: {1}) -> Self {{\n", param, self.member_type(member))?;
+                write!(f, "        self.{} = {};\n        self\n    }}\n", self.field_name(member), param)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the builder method for the slices that share a count, which sets the count to their
+    /// length. Slices that are optional when there are others can be `None`, and the rest have to
+    /// be the same length.
+    fn write_slice_setter<W: fmt::Write>(&self, f: &mut W, count: &str, slices: &[&StructMember]) -> fmt::Result {
+        let count_member = self.members.iter().find(|m| m.name == count).unwrap();
+        // The count is taken from the first slice that has to be given
+        let counted = slices.iter().position(|s| !s.optional).unwrap_or(0);
+        let is_optional = |i: usize| slices.len() > 1 && i != counted && slices[i].optional;
+        let names: Vec<String> = slices.iter().map(|s| snake_case(s.name.as_str())).collect();
+        let mut params: Vec<String> = Vec::new();
+        for (i, slice) in slices.iter().enumerate() {
+            let constness = slice.ty.constness.first() == Some(&true);
+            let element = match self.pointee_type(slice, 1).as_str() {
+                // Untyped data is a slice of bytes
+                "c_void" => String::from("u8"),
+                element => String::from(element),
+            };
+            let ty = format!("&'a {}[{}]", if constness { "" } else { "mut " }, element);
+            params.push(if is_optional(i) { format!("{}: Option<{}>", names[i], ty) } else { format!("{}: {}", names[i], ty) });
+        }
+        writeln!(f, "    pub fn {}(mut self, {}) -> Self {{", names[0], params.join(", "))?;
+        for (i, name) in names.iter().enumerate().filter(|&(i, _)| i != counted) {
+            if is_optional(i) {
+                write!(f, "        if let Some({0}) = {0} {{\n    ", name)?;
+            }
+            writeln!(f, "        assert_eq!({0}.len(), {1}.len(), \"{0} and {1} have to be the same length\");", names[counted], name)?;
+            if is_optional(i) {
+                f.write_str("        }\n")?;
+            }
+        }
+        let len = format!("{}.len()", names[counted]);
+        let count_value = match clean_c_name(count_member.ty.ty.as_str()) {
+            "u32" => format!("count_u32({})", len),
+            "usize" => len,
+            ty => format!("{} as {}", len, ty),
+        };
+        writeln!(f, "        self.{} = {};", self.field_name(count_member), count_value)?;
+        for (i, (slice, name)) in slices.iter().zip(names.iter()).enumerate() {
+            let constness = slice.ty.constness.first() == Some(&true);
+            let first = if constness { "first()" } else { "first_mut()" };
+            let mut pointer = match (self.pointee_type(slice, 1).as_str(), constness) {
+                ("c_void", true) => format!("{}.{}.map(|byte| unsafe {{ &*(byte as *const u8 as *const c_void) }})", name, first),
+                ("c_void", false) => format!("{}.{}.map(|byte| unsafe {{ &mut *(byte as *mut u8 as *mut c_void) }})", name, first),
+                _ => format!("{}.{}", name, first),
+            };
+            if is_optional(i) {
+                pointer = format!("{}.and_then(|{}| {})", name, name, pointer);
+            }
+            writeln!(f, "        self.{} = {};", self.field_name(slice), pointer)?;
+        }
+        f.write_str("        self\n    }\n")
+    }
+
+    /// Writes the `Extends` impls that let this struct be chained to the structs that it extends
+    fn write_extends_impls<W: fmt::Write>(&self, f: &mut W, portable: &HashSet<&str>) -> fmt::Result {
+        if !self.is_extendable() {
+            return Ok(());
+        }
+        for base in self.extends.iter() {
+            let base = resolve_alias(self.registry, base.as_str());
+            let base_info = match self.registry.types.get(base) {
+                Some(info) if portable.contains(base) => match StructInfo::of(self.registry, info) {
+                    Some(info) => info,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            if !base_info.is_extendable() {
+                continue;
+            }
+            // The base's lifetime is its own
+            let params = self.type_params();
+            let base_params: BTreeSet<&str> = base_info.type_params().into_iter().map(|p| if p == "'a" { "'b" } else { p }).collect();
+            let impl_params: BTreeSet<&str> = params.iter().chain(base_params.iter()).cloned().collect();
+            writeln!(f, "impl{} Extends<{}{}> for {}{} {{}}", generics(&impl_params), base_info.base_name(), generics(&base_params), self.base_name(), generics(&params))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the generic parameters of a type, like `<'a>`
+fn generics(params: &BTreeSet<&str>) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        let params: Vec<&str> = params.iter().cloned().collect();
+        format!("<{}>", params.join(", "))
+    }
+}
+
+/// Checks if a safe struct member of this type can default to zero, which handles, unions and
+/// structs with members that have to be given can't
+fn has_default(registry: &Registry, ty: &str) -> bool {
+    match registry.types.get(resolve_alias(registry, ty)) {
+        Some(&TypeInfo::Handle { .. }) | Some(&TypeInfo::Union { .. }) => false,
+        Some(info @ &TypeInfo::Struct { .. }) => {
+            let info = StructInfo::of(registry, info).unwrap();
+            !info.is_extendable() && info.members.iter().all(|m| !info.is_required(m))
+        },
+        _ => true,
+    }
+}
+
+fn is_funcpointer(registry: &Registry, ty: &str) -> bool {
+    matches!(registry.types.get(resolve_alias(registry, ty)), Some(&TypeInfo::Funcpointer { .. }))
+}
+
+pub enum PointerType<'a> {
+    Reference,
+    NullTerminated,
+    MemberSized(&'a str),
+    /// Sized by a `latexmath:` expression
+    LatexSized,
+}
+
+pub struct PointerInfo<'a> {
+    pub constness: bool,
+    pub ty: PointerType<'a>,
+}
+
+impl<'a> PointerInfo<'a> {
+    fn begin<W: fmt::Write>(&self, f: &mut W) -> fmt::Result {
+        f.write_str("&'a ")?;
+        if !self.constness {
+            f.write_str("mut ")?;
+        }
+        if let &PointerType::NullTerminated = &self.ty {
+            f.write_str("NTV<")?;
+        }
+        Ok(())
+    }
+
+    fn end<W: fmt::Write>(&self, f: &mut W) -> fmt::Result {
+        if let &PointerType::NullTerminated = &self.ty {
+            f.write_str(">")?;
+        }
+        Ok(())
+    }
+}
+
+pub trait MemberExt {
+    fn clean_name(&self) -> &str;
+    fn pointer_info<'a>(&'a self) -> Vec<PointerInfo<'a>>;
+
+    fn is_slice(&self) -> bool {
+        let ptr_infos = self.pointer_info();
+        ptr_infos.iter().any(|info| matches!(&info.ty, &PointerType::MemberSized(_)))
+    }
+}
+
+impl MemberExt for StructMember {
+    fn clean_name(&self) -> &str {
+        match self.name.as_str() {
+            "type" => "ty",
+            n => n,
+        }
+    }
+
+    fn pointer_info<'a>(&'a self) -> Vec<PointerInfo<'a>> {
+        let constness = self.ty.constness.iter().copied();
+        let len = self.len.as_ref()
+            .map(|s| s.as_str().split(","))
+            .map(|it| Box::new(it) as Box<dyn Iterator<Item=&str>>)
+            .unwrap_or_else(|| Box::new(std::iter::repeat("1")))
+            .chain(std::iter::repeat("1"))
+            .map(|l| match l {
+                "1" => PointerType::Reference,
+                "null-terminated" => PointerType::NullTerminated,
+                s => {
+                    if s.starts_with("latexmath:") {
+                        PointerType::LatexSized
+                    } else {
+                        PointerType::MemberSized(s)
+                    }
+                },
+            });
+        constness.zip(len).map(|(c, ty)| PointerInfo {
+            constness: c,
+            ty,
+        }).collect()
+    }
+}
+
+trait RegistryExt {
+    fn struct_member_representation_of<'a>(&'a self, ty: &'a str) -> Cow<'a, str>;
+}
+
+impl RegistryExt for Registry {
+    fn struct_member_representation_of<'a>(&'a self, ty: &'a str) -> Cow<'a, str> {
+        self.types.get(resolve_alias(self, ty)).map(|info| match *info {
+            TypeInfo::Handle { .. } => format!("{}<'a>", ty).into(),
+            TypeInfo::Struct { .. } if StructInfo::of(self, info).unwrap().has_lifetime() => format!("{}<'a>", ty).into(),
+            _ => ty.into(),
+        }).unwrap_or(ty.into())
+    }
+}
+
+fn print_comma_sep_iter<'a, W: fmt::Write, It: Iterator<Item=&'a T>, T: 'a + fmt::Display>(w: &mut W, items: It) -> fmt::Result {
+    let mut items = items.peekable();
+    loop {
+        let next_item = items.next();
+        match next_item {
+            Some(item) => {
+                if items.peek().is_some() {
+                    write!(w, "{},", item)?;
+                } else {
+                    write!(w, "{}", item)?;
+                }
+            },
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+impl<'a> fmt::Display for StructInfo<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let base_name = self.base_name();
+        let deprecated_attribute = self.deprecation.as_ref().map(|note| format!("#[deprecated(note = {:?})]", note));
+        if let Some(ref attribute) = deprecated_attribute {
+            writeln!(f, "{}", attribute)?;
+        }
+        f.write_str("#[repr(C)]\n")?;
+        if self.is_copy() {
+            f.write_str("#[derive(Clone, Copy)]\n")?;
+        }
+        write!(f, "pub struct {}", &base_name)?;
+        let t_params = self.type_params();
+        if !t_params.is_empty() {
+            f.write_str("<")?;
+            print_comma_sep_iter(f, t_params.iter())?;
+            f.write_str(">")?;
+        }
+        f.write_str(" {\n")?;
+        let bitfields = self.bitfields();
+        for member in self.base_members() {
+            if member.bitfield.is_some() {
+                // Bitfields share storage units, which are declared by their first bitfield
+                match bitfields.iter().find(|b| b.name == member.clean_name()) {
+                    Some(b) if b.offset == 0 => writeln!(f, "    pub _bitfield_{}: {},", b.unit, b.unit_type())?,
+                    _ => {},
+                }
+                continue;
+            }
+            f.write_str("    ")?;
+            let is_private = self.used_as_count(member.name.as_str()) || member.is_slice();
+            if !is_private {
+                f.write_str("pub ")?;
+            }
+            write!(f, "{}: {}", self.field_name(member), self.member_type(member))?;
+            f.write_str(",\n")?;
+        }
+        f.write_str("}\n")?;
+        let mut generics = String::new();
+        if !t_params.is_empty() {
+            generics.push('<');
+            print_comma_sep_iter(&mut generics, t_params.iter())?;
+            generics.push('>');
+        }
+        writeln!(f, "impl{} {}{} {{", generics, &base_name, generics)?;
+        for bitfield in &bitfields {
+            let name = match self.naming {
+                NamingStyle::Registry => Cow::from(bitfield.name),
+                NamingStyle::Rust => Cow::from(snake_case(bitfield.name)),
+            };
+            bitfield.write_accessors(f, &name)?;
+        }
+        self.write_builder_methods(f, &base_name)?;
+        f.write_str("}\n")?;
+        if self.base_members().all(|m| !self.is_required(m)) {
+            writeln!(f, "impl{} Default for {}{} {{", generics, &base_name, generics)?;
+            write!(f, "    #[inline]\n    fn default() -> Self {{\n        {}::new()\n    }}\n}}\n", &base_name)?;
+        }
+        if let Some(s_type) = self.extendable() {
+            use std::fmt::Write;
+            f.write_str("vk_extendable_struct!(")?;
+            if let Some(ref attribute) = deprecated_attribute {
+                write!(f, "{} ", attribute)?;
+            }
+            f.write_str(&base_name)?;
+            if !t_params.is_empty() {
+                f.write_char('(')?;
+                print_comma_sep_iter(f, t_params.iter())?;
+                f.write_char(')')?;
+            }
+            writeln!(f, ", ({}, VkStructureType::{}));", self.name, s_type)?;
+        }
+        Ok(())
+    }
+}
+
+/// Gets a deprecation note for a symbol if it is only provided by extensions that are all
+/// deprecated
+fn deprecation_note(registry: &Registry, providers: &ProviderIndex, name: &str) -> Option<String> {
+    let notes: Option<Vec<String>> = providers.get(name).into_iter().flat_map(|p| p.iter()).map(|provider| match provider {
+        Provider::Extension { name: extension, .. } => {
+            registry.deprecation(extension).map(|d| format!("{} is {}", extension, d))
+        },
+        &Provider::Version { .. } => None,
+    }).collect();
+    notes.and_then(|notes| if !notes.is_empty() {
+        Some(notes.join(", "))
+    } else {
+        None
+    })
+}
+
+/// Writes a safe struct for every struct that can be declared without any platform's headers,
+/// along with the `Extends` impls for the structs that can be chained to them
+pub fn write_structs<W: fmt::Write>(f: &mut W, registry: &Registry, naming: NamingStyle) -> fmt::Result {
+    let providers = registry.providers();
+    let portable = registry.portable_types();
+    // In order of name, so that the output doesn't change from one run to the next
+    let names: BTreeSet<&str> = portable.iter().cloned().collect();
+    let structs = names.into_iter().filter_map(|name| StructInfo::of(registry, &registry.types[name]));
+    for mut info in structs {
+        info.deprecation = deprecation_note(registry, &providers, info.name);
+        info.naming = naming;
+        let mut extends_impls = String::new();
+        info.write_extends_impls(&mut extends_impls, &portable)?;
+        write!(f, "{}{}", &info, extends_impls)?;
+    }
+    Ok(())
+}
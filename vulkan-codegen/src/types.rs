@@ -0,0 +1,284 @@
+//! The raw types, as C declares them, along with the API constants and defines
+
+use std::collections::{ BTreeSet, LinkedList };
+use std::fmt;
+use vulkan_api_registry::{ ConstantValue, Define, EnumsInfo, EnumsType, EnumValue, Expression, HandleType, Registry, StructMember, TypeInfo, TyperefInfo, UnaryOperator };
+use bitfields::bitfields;
+use names::{ clean_c_name, ffi_member_name, flag_name };
+use structs::MemberExt;
+use ::resolve_alias;
+
+/// Gets the names of the API constants that are used as array sizes
+fn array_size_constants(registry: &Registry) -> BTreeSet<&str> {
+    registry.types.values().flat_map(|info| match info {
+        &TypeInfo::Struct { ref members, .. } | &TypeInfo::Union { ref members, .. } => Some(members.iter()),
+        _ => None,
+    }).flatten().flat_map(|member| member.ty.array.iter()).map(|size| size.as_str()).collect()
+}
+
+/// Declares an API constant with a real Rust type.
+///
+/// Constants used as array sizes are `usize` so they can be used as array lengths. The API uses
+/// `uint32_t` for counts, versions, and booleans, so plain `int` literals that aren't negative are
+/// `u32`. Strings (like extension names) are nul-terminated byte strings, so they can be passed to
+/// C as they are.
+fn constant_declaration(name: &str, value: &ConstantValue, array_sizes: &BTreeSet<&str>) -> String {
+    let (ty, value) = match value {
+        ConstantValue::Str(s) => (format!("&[u8; {}]", s.len() + 1), format!("b\"{}\\0\"", s.escape_default())),
+        v if array_sizes.contains(name) => match v.as_u64() {
+            Some(size) => ("usize".into(), size.to_string()),
+            None => panic!("array size {} isn't a size: {:?}", name, v),
+        },
+        &ConstantValue::I32(v) if v >= 0 => ("u32".into(), v.to_string()),
+        v => (v.rust_type().into(), v.to_string()),
+    };
+    format!("pub const {}: {} = {};", name, ty, value)
+}
+
+/// Translates an expression from a define in to Rust, if it only uses integers.
+///
+/// Everything in the version defines is a `uint32_t`, so casts to it are dropped.
+fn rust_expression(expression: &Expression) -> Option<String> {
+    match expression {
+        Expression::Literal(value) => value.as_u64().map(|v| v.to_string()),
+        Expression::Name(name) => Some(name.clone()),
+        Expression::Call(name, arguments) => {
+            let arguments: Option<Vec<String>> = arguments.iter().map(rust_expression).collect();
+            arguments.map(|arguments| format!("{}({})", name, arguments.join(", ")))
+        },
+        Expression::Cast(ty, operand) if ty == "uint32_t" => rust_expression(operand),
+        &Expression::Cast(..) => None,
+        &Expression::Unary(UnaryOperator::Not, ref operand) => rust_expression(operand).map(|e| format!("!{}", e)),
+        &Expression::Unary(UnaryOperator::Negate, _) => None,
+        &Expression::Binary(operator, ref lhs, ref rhs) => {
+            rust_expression(lhs).and_then(|lhs| rust_expression(rhs).map(|rhs| format!("({} {} {})", lhs, operator.symbol(), rhs)))
+        },
+    }
+}
+
+/// Declares a define as a `u32` constant, or a `const fn` taking and returning `u32`s if it has
+/// parameters
+fn define_declaration(define: &Define) -> Option<String> {
+    let body = rust_expression(&define.body).map(|body| match &define.body {
+        // Binary operations are parenthesized, which isn't needed at the top level
+        &Expression::Binary(..) => String::from(&body[1..body.len() - 1]),
+        _ => body,
+    });
+    body.map(|body| match define.parameters {
+        Some(ref parameters) => {
+            let parameters: Vec<String> = parameters.iter().map(|p| format!("{}: u32", p)).collect();
+            format!("#[inline(always)]\npub const fn {}({}) -> u32 {{ {} }}", &define.name, parameters.join(", "), body)
+        },
+        None => format!("pub const {}: u32 = {};", &define.name, body),
+    })
+}
+
+/// Gets Rust declarations for the defines that can be translated, along with all of the
+/// defines that they use
+fn define_declarations<'a>(defines: &'a [Define], constants: &[(&str, ConstantValue)]) -> Vec<(&'a str, String)> {
+    let mut declarations: Vec<(&str, String)> = defines.iter()
+        .filter_map(|define| define_declaration(define).map(|d| (define.name.as_str(), d)))
+        .collect();
+    // Leaving out a define can leave out others that use it, so repeat until nothing changes
+    loop {
+        let kept: Vec<(&str, String)> = declarations.iter().filter(|&&(name, _)| {
+            let define = defines.iter().find(|d| d.name == name).unwrap();
+            define.body.names().into_iter().all(|used| {
+                define.parameters.iter().flat_map(|p| p.iter()).any(|p| p == used) ||
+                    declarations.iter().any(|&(n, _)| n == used) ||
+                    constants.iter().any(|&(n, _)| n == used)
+            })
+        }).cloned().collect();
+        if kept.len() == declarations.len() {
+            return kept;
+        }
+        declarations = kept;
+    }
+}
+
+/// Gets the Rust type that raw structs, unions and function pointers use for a C type. Handles are
+/// the pointers and 64-bit integers that C has, without the lifetimes that the aliases for them
+/// have.
+fn ffi_type(registry: &Registry, ty: &TyperefInfo) -> String {
+    let name = resolve_alias(registry, ty.ty.as_str());
+    let base = match registry.types.get(name) {
+        Some(&TypeInfo::Handle { ty: HandleType::NonDispatchable, .. }) => format!("handle::NondispatchableHandle<{}_T>", name),
+        Some(&TypeInfo::Handle { .. }) => format!("*mut {}_T", name),
+        _ => String::from(clean_c_name(ty.ty.as_str())),
+    };
+    // The first constness is of the innermost pointer's target
+    let pointer = ty.constness.iter().fold(base, |ty, &constness| {
+        format!("{} {}", if constness { "*const" } else { "*mut" }, ty)
+    });
+    // C array dimensions are outermost first, so `T x[A][B]` is `[[T; B]; A]`
+    ty.array.iter().rev().fold(pointer, |ty, size| format!("[{}; {}]", ty, size))
+}
+
+/// Writes a struct or union as C declares it
+fn write_ffi_aggregate<W: fmt::Write>(f: &mut W, registry: &Registry, keyword: &str, name: &str, members: &LinkedList<StructMember>) -> fmt::Result {
+    write!(f, "#[repr(C)]\n#[derive(Clone, Copy)]\npub {} {} {{\n", keyword, name)?;
+    let bitfields = bitfields(members);
+    for member in members {
+        if member.bitfield.is_some() {
+            // Bitfields share storage units, which are declared by their first bitfield
+            match bitfields.iter().find(|b| b.name == member.clean_name()) {
+                Some(b) if b.offset == 0 => writeln!(f, "    pub _bitfield_{}: {},", b.unit, b.unit_type())?,
+                _ => {},
+            }
+            continue;
+        }
+        writeln!(f, "    pub {}: {},", ffi_member_name(member.name.as_str()), ffi_type(registry, &member.ty))?;
+    }
+    f.write_str("}\n")?;
+    if !bitfields.is_empty() {
+        writeln!(f, "impl {} {{", name)?;
+        for bitfield in &bitfields {
+            bitfield.write_accessors(f, bitfield.name)?;
+        }
+        f.write_str("}\n")?;
+    }
+    Ok(())
+}
+
+/// Gets the values of an enum as integers, with aliases given the value of what they alias
+fn enum_integers<'a>(registry: &'a Registry, name: &str, warnings: &mut Vec<String>) -> Vec<(&'a str, i64)> {
+    let mut values: Vec<(&str, i64)> = Vec::new();
+    for (value_name, value) in registry.enum_values(name) {
+        let integer = match value {
+            EnumValue::Alias(ref alias) => values.iter().find(|&&(n, _)| n == alias).map(|&(_, v)| v),
+            v => v.as_integer(),
+        };
+        match integer {
+            Some(integer) => values.push((value_name, integer)),
+            None => warnings.push(format!("vk.xml: {} has no value", value_name)),
+        }
+    }
+    values
+}
+
+/// Writes an enum as a `vk_enum!` newtype. Values that repeat an earlier value (like aliases)
+/// name the constant they repeat, so that each value has one name when it's printed.
+fn write_ffi_enum<W: fmt::Write>(f: &mut W, registry: &Registry, name: &str, warnings: &mut Vec<String>) -> fmt::Result {
+    let values = enum_integers(registry, name, warnings);
+    // C compilers make enums with negative values `int`, and otherwise `unsigned int`
+    let repr = if values.iter().any(|&(_, v)| v < 0) { "i32" } else { "u32" };
+    let mut names: Vec<(&str, i64)> = Vec::new();
+    let mut repeats: Vec<(&str, &str)> = Vec::new();
+    for &(value_name, value) in values.iter() {
+        match names.iter().find(|&&(_, v)| v == value) {
+            Some(&(first, _)) => repeats.push((value_name, first)),
+            None => names.push((value_name, value)),
+        }
+    }
+    writeln!(f, "vk_enum!({}, {}, {{", name, repr)?;
+    for &(value_name, value) in names.iter() {
+        writeln!(f, "    {} = {},", value_name, value)?;
+    }
+    f.write_str("}, {\n")?;
+    for &(value_name, first) in repeats.iter() {
+        writeln!(f, "    {} = {},", value_name, first)?;
+    }
+    f.write_str("});\n")
+}
+
+/// Writes the bits of a bitmask as constants of a `vk_flag_bits!` type
+fn write_ffi_flag_bits<W: fmt::Write>(f: &mut W, registry: &Registry, name: &str, wide: bool, warnings: &mut Vec<String>) -> fmt::Result {
+    let repr = if wide { "u64" } else { "u32" };
+    writeln!(f, "vk_flag_bits!({}, {});", name, repr)?;
+    for (value_name, value) in flag_integers(registry, name, wide, warnings) {
+        writeln!(f, "pub const {}: {} = {}({:#x});", value_name, name, name, value)?;
+    }
+    Ok(())
+}
+
+/// Gets the values of a bitmask's bits as unsigned integers
+fn flag_integers<'a>(registry: &'a Registry, name: &str, wide: bool, warnings: &mut Vec<String>) -> Vec<(&'a str, u64)> {
+    enum_integers(registry, name, warnings).into_iter()
+        .map(|(value_name, value)| (value_name, if wide { value as u64 } else { value as u32 as u64 }))
+        .collect()
+}
+
+/// Writes a `*Flags` type as a `vk_flags!` set, with a constant for each of its bits
+fn write_ffi_flags<W: fmt::Write>(f: &mut W, registry: &Registry, name: &str, ty: &str, bits_type: Option<&str>, warnings: &mut Vec<String>) -> fmt::Result {
+    let wide = ty.ends_with("64");
+    let values = bits_type.map_or_else(Vec::new, |bits| flag_integers(registry, bits, wide, warnings));
+    let mut flags: Vec<(String, u64)> = Vec::new();
+    for &(value_name, value) in values.iter() {
+        let short_name = flag_name(bits_type.unwrap(), value_name);
+        // Shortening can make names collide, which leaves the later one as it was
+        let flag = if flags.iter().any(|(n, _)| *n == short_name) { String::from(value_name) } else { short_name };
+        flags.push((flag, value));
+    }
+    write!(f, "vk_flags!({}, {}, ", name, ty)?;
+    if let Some(bits) = bits_type {
+        write!(f, "{}, ", bits)?;
+    }
+    f.write_str("{\n")?;
+    for &(ref flag, value) in flags.iter() {
+        writeln!(f, "    {} = {:#x},", flag, value)?;
+    }
+    f.write_str("}, {\n")?;
+    let mut bits_written: Vec<u64> = Vec::new();
+    for &(ref flag, value) in flags.iter() {
+        if value.count_ones() == 1 && !bits_written.contains(&value) {
+            writeln!(f, "    {},", flag)?;
+            bits_written.push(value);
+        }
+    }
+    f.write_str("});\n")
+}
+
+/// Writes a Rust declaration of every type that can be declared without any platform's headers,
+/// along with the API constants and the defines that can be translated. Enumerants that have no
+/// value are left out, and added to `warnings`.
+pub fn write_types<W: fmt::Write>(f: &mut W, registry: &Registry, warnings: &mut Vec<String>) -> fmt::Result {
+    let constants = registry.constants().unwrap();
+    let array_sizes = array_size_constants(registry);
+    for &(name, ref value) in constants.iter() {
+        writeln!(f, "{}", constant_declaration(name, value, &array_sizes))?;
+    }
+    let defines = registry.defines();
+    for (_, declaration) in define_declarations(&defines, &constants) {
+        writeln!(f, "{}", declaration)?;
+    }
+    let bitmask_enums: BTreeSet<&str> = registry.enums.iter().filter_map(|&EnumsInfo { ref name, ty, .. }| match ty {
+        Some(EnumsType::Bitmask) => name.as_ref().map(|n| n.as_str()),
+        _ => None,
+    }).collect();
+    let portable = registry.portable_types();
+    // 64-bit bits are the ones that a `VkFlags64` bitmask requires
+    let wide_bits: BTreeSet<&str> = registry.types.values().flat_map(|info| match info {
+        TypeInfo::Bitmask { ty, requires, .. } if ty.ends_with("64") => Some(requires.iter().map(|r| r.as_str())),
+        _ => None,
+    }).flatten().collect();
+    let names: BTreeSet<&str> = portable.iter().cloned().collect();
+    for name in names {
+        match registry.types[name] {
+            TypeInfo::Basetype { ref ty, .. } => writeln!(f, "pub type {} = {};", name, clean_c_name(ty.as_str()))?,
+            TypeInfo::Bitmask { ref ty, ref requires, .. } => {
+                let bits = requires.front().map(|bits| bits.as_str()).filter(|bits| requires.len() == 1 && portable.contains(bits));
+                write_ffi_flags(f, registry, name, ty, bits, warnings)?;
+            },
+            TypeInfo::Enum { .. } if bitmask_enums.contains(name) => write_ffi_flag_bits(f, registry, name, wide_bits.contains(name), warnings)?,
+            TypeInfo::Enum { .. } => write_ffi_enum(f, registry, name, warnings)?,
+            TypeInfo::Handle { ty: HandleType::NonDispatchable, .. } => writeln!(f, "vk_non_dispatchable_handle!({}, {}_T);", name, name)?,
+            TypeInfo::Handle { .. } => writeln!(f, "vk_handle!({}, {}_T);", name, name)?,
+            TypeInfo::Alias { ref alias, .. } => match registry.types.get(resolve_alias(registry, alias.as_str())) {
+                Some(&TypeInfo::Handle { .. }) => writeln!(f, "pub type {}<'a> = {}<'a>;", name, alias)?,
+                _ => writeln!(f, "pub type {} = {};", name, alias)?,
+            },
+            TypeInfo::Funcpointer { ref arguments, ref return_type, .. } => {
+                let arguments: Vec<String> = arguments.iter().map(|(argument, ty)| format!("{}: {}", argument, ffi_type(registry, ty))).collect();
+                write!(f, "pub type {} = Option<unsafe extern \"system\" fn({})", name, arguments.join(", "))?;
+                if return_type.ty != "void" || !return_type.constness.is_empty() {
+                    write!(f, " -> {}", ffi_type(registry, return_type))?;
+                }
+                f.write_str(">;\n")?;
+            },
+            TypeInfo::Struct { ref members, .. } => write_ffi_aggregate(f, registry, "struct", name, members)?,
+            TypeInfo::Union { ref members, .. } => write_ffi_aggregate(f, registry, "union", name, members)?,
+            _ => {},
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+extern crate vulkan_api_registry;
+extern crate vulkan_codegen;
+
+use std::path::Path;
+use std::process::Command;
+use vulkan_api_registry::Registry;
+use vulkan_codegen::{ Config, Layout, NamingStyle, Output };
+
+fn structs_fixture() -> Registry {
+    Registry::from_str(include_str!("fixtures/structs.xml"), Some("vulkan")).unwrap()
+}
+
+fn commands_fixture() -> Registry {
+    Registry::from_str(include_str!("fixtures/commands.xml"), Some("vulkan")).unwrap()
+}
+
+fn file<'a>(bindings: &'a vulkan_codegen::Bindings, name: &str) -> &'a str {
+    bindings.files.iter().find(|(n, _)| n == name).map(|(_, contents)| contents.as_str()).unwrap()
+}
+
+#[test]
+fn each_output_has_a_file() {
+    let bindings = vulkan_codegen::generate(&structs_fixture(), &Config::default()).unwrap();
+    let names: Vec<&str> = bindings.files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["types.rs", "layout_tests.rs", "safe_structs.rs", "safe_layout_tests.rs", "commands.rs", "linked_commands.rs"]);
+    // Without a probe, there's nothing to test the raw types against
+    assert_eq!(file(&bindings, "layout_tests.rs"), "");
+    assert!(file(&bindings, "types.rs").contains("pub struct VkExtent2D {\n    pub width: u32,\n    pub height: u32,\n}\n"));
+    assert!(bindings.warnings.is_empty());
+}
+
+#[test]
+fn only_the_given_outputs() {
+    let config = Config::default().outputs(vec![Output::Structs]);
+    let bindings = vulkan_codegen::generate(&structs_fixture(), &config).unwrap();
+    assert_eq!(bindings.files.len(), 1);
+    let structs = file(&bindings, "safe_structs.rs");
+    assert!(structs.contains("pub struct VkApplicationInfoBase<'a> {\n    pub pApplicationName: &'a NTV<c_char>,\n    pub applicationVersion: u32,\n}\n"));
+    // Structs come in order of name
+    assert!(structs.find("VkApplicationInfoBase").unwrap() < structs.find("VkExtent2D").unwrap());
+}
+
+#[test]
+fn struct_lifetimes() {
+    let config = Config::default().outputs(vec![Output::Structs]);
+    let bindings = vulkan_codegen::generate(&structs_fixture(), &config).unwrap();
+    let structs = file(&bindings, "safe_structs.rs");
+    // Extendable structs always have a lifetime
+    assert!(structs.contains("#[repr(C)]\n#[derive(Clone, Copy)]\npub struct VkInstanceCreateInfoBase<'a> {\n    pub pApplicationInfo: &'a VkApplicationInfo<'a>,\n}\n"));
+    // Mutable references can't be copied
+    assert!(structs.contains("#[repr(C)]\npub struct VkAllocationCallbacks<'a> {\n    pub pUserData: &'a mut c_void,\n}\n"));
+}
+
+#[test]
+fn optional_members() {
+    let registry = Registry::from_str(include_str!("fixtures/vk.xml"), Some("vulkan")).unwrap();
+    let config = Config::default().outputs(vec![Output::Structs]);
+    let bindings = vulkan_codegen::generate(&registry, &config).unwrap();
+    let structs = file(&bindings, "safe_structs.rs");
+    // Optional pointers can be null, so they're left out of `new` and set by builder methods
+    assert!(structs.contains("    pub pSpecializationInfo: Option<&'a VkSpecializationInfo<'a>>,\n"));
+    assert!(structs.contains("    pub fn new(name: &'a NTV<c_char>) -> Self {\n        VkPipelineShaderStageCreateInfoBase {\n            pName: name,\n            pSpecializationInfo: None,\n"));
+    assert!(structs.contains("    pub fn specialization_info(mut self, specialization_info: &'a VkSpecializationInfo<'a>) -> Self {\n        self.pSpecializationInfo = Some(specialization_info);\n"));
+    assert!(structs.contains("    pub pUserData: Option<&'a mut c_void>,\n"));
+    // Function pointers that aren't optional have to be given
+    assert!(structs.contains("    pub fn new(pfn_allocation: PFN_vkAllocationFunction, pfn_free: PFN_vkFreeFunction) -> Self {\n        VkAllocationCallbacks {\n            pUserData: None,\n"));
+}
+
+#[test]
+fn rust_naming() {
+    let config = Config::default().outputs(vec![Output::Structs, Output::StructLayoutTests]).naming(NamingStyle::Rust);
+    let bindings = vulkan_codegen::generate(&structs_fixture(), &config).unwrap();
+    let structs = file(&bindings, "safe_structs.rs");
+    assert!(structs.contains("    pub application_name: &'a NTV<c_char>,\n    pub application_version: u32,\n"));
+    assert!(structs.contains("        self.application_version = application_version;\n"));
+    assert!(file(&bindings, "safe_layout_tests.rs").contains("mem::offset_of!(VkApplicationInfoBase, application_version), mem::offset_of!(::ffi::VkApplicationInfo, applicationVersion)"));
+}
+
+#[test]
+fn modules_in_one_file() {
+    let config = Config::default()
+        .outputs(vec![Output::Types, Output::LayoutTests, Output::Structs, Output::StructLayoutTests])
+        .layout(Layout::Modules(String::from("bindings.rs")));
+    let bindings = vulkan_codegen::generate(&structs_fixture(), &config).unwrap();
+    assert_eq!(bindings.files.len(), 1);
+    let code = file(&bindings, "bindings.rs");
+    assert!(code.starts_with("#[allow(deprecated)]\npub mod types {\n    use super::*;\n\n"));
+    assert!(code.contains("\n#[allow(deprecated)]\npub mod structs {\n    use super::*;\n\n"));
+    // The layout tests are empty, so they have no module
+    assert!(!code.contains("mod layout_tests"));
+    assert!(code.contains("\n    #[cfg(test)]\n    #[allow(non_snake_case)]\n    mod struct_layout_tests {\n        use super::*;\n"));
+}
+
+#[test]
+fn outdated_files() {
+    let dir = std::env::temp_dir().join(format!("vulkan-codegen-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = Config::default().outputs(vec![Output::Types, Output::Structs]);
+    let bindings = vulkan_codegen::generate(&structs_fixture(), &config).unwrap();
+    assert_eq!(bindings.outdated_files(&dir), ["types.rs", "safe_structs.rs"]);
+    bindings.write_to(&dir).unwrap();
+    assert!(bindings.outdated_files(&dir).is_empty());
+    std::fs::write(dir.join("types.rs"), "").unwrap();
+    assert_eq!(bindings.outdated_files(&dir), ["types.rs"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn registry_sources() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/vk.xml");
+    let registry = vulkan_codegen::parse_registry(&Config::default().source(&fixture)).unwrap();
+    assert!(registry.features.iter().any(|f| f.name == "VK_VERSION_1_0"));
+    // Only the configured API is selected
+    let registry = vulkan_codegen::parse_registry(&Config::default().api(Some("vulkansc")).source(&fixture)).unwrap();
+    assert!(registry.features.is_empty());
+    // Nothing is read besides the given sources
+    assert!(vulkan_codegen::parse_registry(&Config::default().source(&fixture).source(fixture.with_file_name("video.xml"))).is_err());
+}
+
+#[test]
+fn command_wrappers() {
+    let config = Config::default().outputs(vec![Output::Commands]);
+    let bindings = vulkan_codegen::generate(&commands_fixture(), &config).unwrap();
+    let commands = file(&bindings, "commands.rs");
+    // Slices that share a count have to be as long as each other, and the count has to fit
+    assert!(commands.contains("
+        assert_eq!(offsets.len(), buffers.len());
+        if let Some(s) = sizes { assert_eq!(s.len(), buffers.len()); }
+        fp(command_buffer, first_binding, count_u32(buffers.len()), buffers.as_ptr(), offsets.as_ptr(), sizes.map_or(ptr::null(), |s| s.as_ptr()));
+"));
+    // Outputs are always written when VK_SUCCESS is the only success code
+    assert!(commands.contains("get_semaphore_counter_value<'a>(&self, device: VkDevice<'a>, semaphore: VkSemaphore<'a>) -> Result<u64, VkResult> {"));
+    assert!(commands.contains("            VkResult::VK_SUCCESS => Ok(value.assume_init()),\n"));
+    // but not with VK_TIMEOUT or VK_NOT_READY
+    assert!(commands.contains("fence: Option<VkFence<'a>>) -> Result<(Option<u32>, VkResult), VkResult> {"));
+    assert!(commands.contains("
+        let mut image_index = MaybeUninit::uninit();
+        let result = fp(device, swapchain, timeout, semaphore.unwrap_or_else(NullableHandle::null), fence.unwrap_or_else(NullableHandle::null), image_index.as_mut_ptr());
+        match result {
+            VkResult::VK_SUCCESS | VkResult::VK_TIMEOUT | VkResult::VK_NOT_READY | VkResult::VK_SUBOPTIMAL_KHR => Ok((match result { VkResult::VK_SUCCESS | VkResult::VK_SUBOPTIMAL_KHR => Some(image_index.assume_init()), _ => None }, result)),
+            e => Err(e),
+        }
+"));
+}
+
+#[test]
+fn optional_handles() {
+    let config = Config::default().outputs(vec![Output::Commands]);
+    let bindings = vulkan_codegen::generate(&commands_fixture(), &config).unwrap();
+    let commands = file(&bindings, "commands.rs");
+    // Non-dispatchable handles have no null value of their own, so `None` is passed as VK_NULL_HANDLE
+    assert!(commands.contains("timeout: u64, semaphore: Option<VkSemaphore<'a>>, fence: Option<VkFence<'a>>)"));
+    assert!(commands.contains("fence.unwrap_or_else(NullableHandle::null)"));
+    // Handles that aren't optional are passed as they are
+    assert!(commands.contains("semaphore: VkSemaphore<'a>) -> Result<u64, VkResult> {"));
+    assert!(commands.contains("fp(device, semaphore, value.as_mut_ptr())"));
+}
+
+/// Runs cargo on smolder-vulkan-sys with the fixture registry, so that the code generated from it
+/// has to compile
+fn cargo_sys(args: &[&str]) {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .args(args)
+        .arg("--manifest-path").arg(manifest_dir.join("../smolder-vulkan-sys/Cargo.toml"))
+        .env("VULKAN_REGISTRY", manifest_dir.join("tests/fixtures/vk.xml"))
+        .env("CARGO_TARGET_DIR", Path::new(env!("CARGO_TARGET_TMPDIR")).join("smolder-vulkan-sys"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "cargo {} failed:\n{}", args.join(" "), String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn sys_crate_builds() {
+    // The tests include the layout tests of the safe structs
+    cargo_sys(&["test"]);
+    // Without runtime-loader, the core commands are declared too, which only links when they're called
+    cargo_sys(&["build", "--no-default-features"]);
+}